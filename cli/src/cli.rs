@@ -2,6 +2,7 @@
 
 use clap::{App, Arg};
 use radish::config::Config;
+use radish::error::{diagnostic::Severity, renderer::DEFAULT_TAB_WIDTH};
 
 #[derive(Debug)]
 pub struct Cli {
@@ -12,10 +13,41 @@ pub struct Cli {
     pub dump_ast: bool,
     pub dump_code: bool,
     pub trace: bool,
+    pub no_color: bool,
+    pub strict: bool,
+    /// Print the file's AST as JSON instead of running it.
+    pub ast_json: bool,
+    /// Print how often each opcode ran once the program finishes.
+    pub profile: bool,
+    /// Run without the builtin functions.
+    pub no_std_builtins: bool,
+    /// Only show errors.
+    pub quiet: bool,
+    /// Show notes as well as warnings and errors.
+    pub verbose: bool,
+    /// Show warnings as notes.
+    pub warn_as_note: bool,
+    /// Step through the file one instruction at a time.
+    pub debug: bool,
+    /// Print what the optimizer would change in the file instead of running it.
+    pub explain_opt: bool,
+    /// Evaluate a file holding a single expression and print its value.
+    pub eval_file_as_expr: bool,
+    /// How many columns a tab in a diagnostic's source line is shown as.
+    pub tab_width: usize,
 }
 
 impl Cli {
     pub fn new() -> Self {
+        Cli::from_args(std::env::args_os())
+    }
+
+    /// Parse the CLI from the given arguments, the first of which is the binary name.
+    pub fn from_args<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
         let version = &format!("v{}", env!("CARGO_PKG_VERSION"))[..];
 
         let app = App::new("radish")
@@ -39,6 +71,80 @@ impl Cli {
                     .short("t")
                     .help("Trace the VM's execution"),
             )
+            .arg(
+                Arg::with_name("no-color")
+                    .long("no-color")
+                    .help("Disable colored output"),
+            )
+            .arg(
+                Arg::with_name("strict")
+                    .long("strict")
+                    .help("Disallow implicitly `nil` variables and return values"),
+            )
+            .arg(
+                Arg::with_name("ast-json")
+                    .long("ast-json")
+                    .requires("FILE.rdsh")
+                    .help("Print the file's AST as JSON instead of running it"),
+            )
+            .arg(
+                Arg::with_name("profile")
+                    .long("profile")
+                    .requires("FILE.rdsh")
+                    .help("Print a histogram of executed opcodes to stderr"),
+            )
+            .arg(
+                Arg::with_name("no-std-builtins")
+                    .long("no-std-builtins")
+                    .help("Run without any of the builtin functions"),
+            )
+            .arg(
+                Arg::with_name("quiet")
+                    .long("quiet")
+                    .short("q")
+                    .conflicts_with("verbose")
+                    .help("Only report errors, not warnings"),
+            )
+            .arg(
+                Arg::with_name("verbose")
+                    .long("verbose")
+                    .short("v")
+                    .help("Report notes as well as warnings and errors"),
+            )
+            .arg(
+                Arg::with_name("warn-as-note")
+                    .long("warn-as-note")
+                    .help("Report warnings as notes, which are only shown with --verbose"),
+            )
+            .arg(
+                Arg::with_name("debug")
+                    .long("debug")
+                    .requires("FILE.rdsh")
+                    .help("Step through the file one instruction at a time"),
+            )
+            .arg(
+                Arg::with_name("explain-opt")
+                    .long("explain-opt")
+                    .requires("FILE.rdsh")
+                    .help("List the optimizations applied to the file instead of running it"),
+            )
+            .arg(
+                Arg::with_name("eval-file-as-expr")
+                    .long("eval-file-as-expr")
+                    .requires("FILE.rdsh")
+                    .help("Print the value of a file that is a single expression"),
+            )
+            .arg(
+                Arg::with_name("tab-width")
+                    .long("tab-width")
+                    .takes_value(true)
+                    .value_name("COLUMNS")
+                    .validator(|width| match width.parse::<usize>() {
+                        Ok(width) if width > 0 => Ok(()),
+                        _ => Err(String::from("the tab width must be a positive number")),
+                    })
+                    .help("Show tabs in diagnostics as this many columns [default: 4]"),
+            )
             .arg(
                 Arg::with_name("FILE.rdsh").help("Path to file"), //.required(true),
             )
@@ -48,17 +154,28 @@ impl Cli {
                     .multiple(true),
             );
 
-        let matches = app.get_matches();
+        let matches = app.get_matches_from(args);
 
-        let path = if let Some(path) = matches.value_of("FILE.rdsh") {
-            Some(path.to_string())
-        } else {
-            None
-        };
+        let path = matches.value_of("FILE.rdsh").map(|path| path.to_string());
 
         let dump_ast = matches.is_present("dump-ast");
         let dump_code = matches.is_present("dump-bytecode");
         let trace = matches.is_present("trace");
+        let no_color = matches.is_present("no-color");
+        let strict = matches.is_present("strict");
+        let ast_json = matches.is_present("ast-json");
+        let profile = matches.is_present("profile");
+        let no_std_builtins = matches.is_present("no-std-builtins");
+        let quiet = matches.is_present("quiet");
+        let verbose = matches.is_present("verbose");
+        let warn_as_note = matches.is_present("warn-as-note");
+        let debug = matches.is_present("debug");
+        let explain_opt = matches.is_present("explain-opt");
+        let eval_file_as_expr = matches.is_present("eval-file-as-expr");
+        let tab_width = matches
+            .value_of("tab-width")
+            .and_then(|width| width.parse().ok())
+            .unwrap_or(DEFAULT_TAB_WIDTH);
 
         let args = matches
             .values_of("arguments")
@@ -74,17 +191,132 @@ impl Cli {
             dump_ast,
             dump_code,
             trace,
+            no_color,
+            strict,
+            ast_json,
+            profile,
+            no_std_builtins,
+            quiet,
+            verbose,
+            warn_as_note,
+            debug,
+            explain_opt,
+            eval_file_as_expr,
+            tab_width,
         }
     }
 }
 
 impl From<&Cli> for Config {
     fn from(cli: &Cli) -> Self {
+        let severity_floor = if cli.quiet {
+            Severity::Error
+        } else if cli.verbose {
+            Severity::Help
+        } else {
+            Severity::Warning
+        };
+
         Config {
             dump_ast: cli.dump_ast,
             dump_bytecode: cli.dump_code,
             trace: cli.trace,
+            color: !cli.no_color,
+            strict: cli.strict,
+            profile: cli.profile,
+            no_builtins: cli.no_std_builtins,
+            severity_floor,
+            warn_as_note: cli.warn_as_note,
+            tab_width: cli.tab_width,
+            repl: cli.path.is_none(),
             ..Default::default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_repl_without_path() {
+        let cli = Cli::from_args(vec!["radish"]);
+        let config = Config::from(&cli);
+
+        assert!(config.repl);
+    }
+
+    #[test]
+    fn config_no_repl_with_path() {
+        let cli = Cli::from_args(vec!["radish", "main.rdsh"]);
+        let config = Config::from(&cli);
+
+        assert!(!config.repl);
+        assert_eq!(cli.path.as_deref(), Some("main.rdsh"));
+    }
+
+    #[test]
+    fn config_strict() {
+        assert!(!Config::from(&Cli::from_args(vec!["radish"])).strict);
+        assert!(Config::from(&Cli::from_args(vec!["radish", "--strict"])).strict);
+    }
+
+    #[test]
+    fn ast_json_flag() {
+        assert!(!Cli::from_args(vec!["radish", "main.rdsh"]).ast_json);
+        assert!(Cli::from_args(vec!["radish", "--ast-json", "main.rdsh"]).ast_json);
+    }
+
+    #[test]
+    fn config_profile() {
+        assert!(!Config::from(&Cli::from_args(vec!["radish", "main.rdsh"])).profile);
+        assert!(Config::from(&Cli::from_args(vec!["radish", "--profile", "main.rdsh"])).profile);
+    }
+
+    #[test]
+    fn config_no_std_builtins() {
+        assert!(!Config::from(&Cli::from_args(vec!["radish", "main.rdsh"])).no_builtins);
+        assert!(
+            Config::from(&Cli::from_args(vec!["radish", "--no-std-builtins", "main.rdsh"])).no_builtins
+        );
+    }
+
+    #[test]
+    fn debug_flag() {
+        assert!(!Cli::from_args(vec!["radish", "main.rdsh"]).debug);
+        assert!(Cli::from_args(vec!["radish", "--debug", "main.rdsh"]).debug);
+    }
+    #[test]
+    fn explain_opt_flag() {
+        assert!(!Cli::from_args(vec!["radish", "main.rdsh"]).explain_opt);
+        assert!(Cli::from_args(vec!["radish", "--explain-opt", "main.rdsh"]).explain_opt);
+    }
+
+    #[test]
+    fn eval_file_as_expr_flag() {
+        assert!(!Cli::from_args(vec!["radish", "main.rdsh"]).eval_file_as_expr);
+        assert!(Cli::from_args(vec!["radish", "--eval-file-as-expr", "main.rdsh"]).eval_file_as_expr);
+    }
+
+    #[test]
+    fn config_verbosity() {
+        let config = |args: Vec<&str>| Config::from(&Cli::from_args(args));
+
+        assert_eq!(config(vec!["radish", "main.rdsh"]).severity_floor, Severity::Warning);
+        assert_eq!(config(vec!["radish", "-q", "main.rdsh"]).severity_floor, Severity::Error);
+        assert_eq!(config(vec!["radish", "--verbose", "main.rdsh"]).severity_floor, Severity::Help);
+
+        let warn_as_note = config(vec!["radish", "--warn-as-note", "-v", "main.rdsh"]);
+        assert!(warn_as_note.warn_as_note);
+        assert_eq!(warn_as_note.severity_floor, Severity::Help);
+    }
+
+    #[test]
+    fn config_tab_width() {
+        assert_eq!(Config::from(&Cli::from_args(vec!["radish", "main.rdsh"])).tab_width, 4);
+        assert_eq!(
+            Config::from(&Cli::from_args(vec!["radish", "--tab-width", "8", "main.rdsh"])).tab_width,
+            8
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! An interactive stepper for `--debug`, which runs a file one instruction at
+//! a time.
+
+use std::io::{self, BufRead, Write};
+
+use radish::{vm::StepResult, RadishError, VM};
+
+const HELP: &str = "<enter> step, c continue, q quit";
+
+/// Step through the file at `path`, printing the next instruction and the
+/// value stack before each step.
+pub fn run(mut vm: VM, path: &str) -> Result<(), RadishError> {
+    vm.start_file(path)?;
+
+    println!("{}", HELP);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut stepping = true;
+
+    loop {
+        if stepping {
+            print_state(&vm);
+
+            print!("> ");
+            io::stdout().flush()?;
+
+            // end of input is the same as continuing to the end.
+            match lines.next().transpose()?.as_deref().map(str::trim) {
+                Some("") | Some("s") => {}
+                Some("c") | None => stepping = false,
+                Some("q") => return Ok(()),
+                Some(_) => {
+                    println!("{}", HELP);
+                    continue;
+                }
+            }
+        }
+
+        if let StepResult::Finished(result) = vm.step()? {
+            println!("finished with {}", result.repr());
+            return Ok(());
+        }
+    }
+}
+
+fn print_state(vm: &VM) {
+    let (ip, opcode) = match (vm.instruction_pointer(), vm.current_opcode()) {
+        (Some(ip), Some(opcode)) => (ip, opcode),
+        _ => return,
+    };
+
+    let stack: Vec<String> = vm.stack().iter().map(|value| format!("[ {} ]", value)).collect();
+
+    println!("{:04} {:<16}{}", ip, format!("{:?}", opcode), stack.join(""));
+}
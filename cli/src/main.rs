@@ -1,26 +1,209 @@
-use radish::{VM, RadishError, config::Config};
+use std::io::Write;
+
+use radish::{VM, RadishError, Value, config::Config};
+use radish::{common::source::Source, compiler::{explain_fold_constants, json, pipeline::CompilerPipeLine, Parser, Stmt, AST}};
+use radish::error;
 
 mod cli;
+mod debug;
 mod repl;
 mod hint;
 
 fn main() -> Result<(), RadishError> {
     let args = cli::Cli::new();
 
-    let mut config = Config::from(&args);
+    let config = Config::from(&args);
 
-    if let Some(path) = args.path {
+    if args.ast_json {
+        let path = args.path.as_deref().unwrap_or_default();
 
-        let mut vm = VM::with_config(config);
+        if let Err(err) = print_ast_json(path) {
+            err.emit_with_tab_width(config.color, config.tab_width);
+            std::process::exit(1);
+        }
+    } else if let (true, Some(path)) = (args.explain_opt, &args.path) {
+        if let Err(err) = print_optimizations(path) {
+            err.emit_with_tab_width(config.color, config.tab_width);
+            std::process::exit(1);
+        }
+    } else if let (true, Some(path)) = (args.debug, &args.path) {
+        let (color, tab_width) = (config.color, config.tab_width);
 
-        vm.exec_file(&path)?;
-    } else {
-        config.repl = true;
+        if let Err(err) = debug::run(VM::with_config(config), path) {
+            if !matches!(err, RadishError::RuntimeError(_)) {
+                err.emit_with_tab_width(color, tab_width);
+            }
+
+            std::process::exit(1);
+        }
+    } else if let Some(path) = args.path {
+        let (color, tab_width) = (config.color, config.tab_width);
+        // the compiler only keeps the value of a trailing expression in REPL
+        // mode, which only matters to a file that is a single expression.
+        let eval_as_expr = args.eval_file_as_expr;
+        let config = Config { repl: eval_as_expr, ..config };
+
+        let result = parse_file(&path, &config).and_then(|ast| {
+            let as_expr = eval_as_expr && is_expression(&ast);
+            let mut vm = VM::with_config(config);
+
+            let result = match as_expr {
+                true => eval_file_as_expr(&mut vm, &path, &ast, &mut std::io::stdout()),
+                false => vm.exec_file_ast(&path, &ast),
+            };
+
+            if let Some(profile) = vm.profile() {
+                eprint!("{}", profile);
+            }
+
+            result
+        });
 
+        if let Err(err) = result {
+            // runtime errors have already been reported by the VM.
+            if !matches!(err, RadishError::RuntimeError(_)) {
+                err.emit_with_tab_width(color, tab_width);
+            }
+
+            std::process::exit(1);
+        }
+    } else {
+        let (color, tab_width) = (config.color, config.tab_width);
         let vm = VM::with_config(config);
         
-        repl::Repl::new(vm).run()?;
+        repl::Repl::new(vm)
+            .with_color(color)
+            .with_tab_width(tab_width)
+            .with_formatter(repl::default_formatter)
+            .run()?;
+    }
+
+    Ok(())
+}
+
+/// Parse the file at `path`, printing the warnings about it that the config
+/// lets through.
+fn parse_file(path: &str, config: &Config) -> Result<AST, RadishError> {
+    let src = std::fs::read_to_string(path)?;
+
+    let (ast, warnings) = CompilerPipeLine::new(config)?
+        .with_default_passes()
+        .parse_with_warnings(path, &src)?;
+
+    // there isn't much that can be done if stderr can't be written to.
+    let _ = error::emit_filtered(&mut error::stderr_with_color(config.color), warnings, config);
+
+    Ok(ast)
+}
+
+/// Parse the file at `path` and print its AST as JSON.
+fn print_ast_json(path: &str) -> Result<(), RadishError> {
+    let src = std::fs::read_to_string(path)?;
+
+    let ast = Parser::new(Source::new(&src, path)).parse()?;
+
+    println!("{}", json::to_json(&ast));
+
+    Ok(())
+}
+
+/// Whether `ast` is a single expression, with nothing else but blank lines
+/// and comments around it.
+fn is_expression(ast: &AST) -> bool {
+    matches!(ast.items.as_slice(), [Stmt::ExpressionStmt(_)])
+}
+
+/// Evaluate the file at `path` from its `ast`, a single expression, and write
+/// its value to `out` as `print` would. `vm` has to be in REPL mode to keep
+/// the value.
+fn eval_file_as_expr(vm: &mut VM, path: &str, ast: &AST, out: &mut impl Write) -> Result<(), RadishError> {
+    let value: Value = vm.eval_file_ast(path, ast)?;
+
+    writeln!(out, "{}", value)?;
+
+    Ok(())
+}
+
+/// Parse the file at `path` and print the optimizations that compiling it
+/// would apply.
+fn print_optimizations(path: &str) -> Result<(), RadishError> {
+    let src = std::fs::read_to_string(path)?;
+
+    let ast = Parser::new(Source::new(&src, path)).parse()?;
+
+    for transformation in explain_fold_constants(&ast)? {
+        println!("{}", transformation);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn expr_config() -> Config {
+        let mut config = Config::new();
+        config.repl = true;
+
+        config
+    }
+
+    fn eval_as_expr(path: &str) -> String {
+        let ast = parse_file(path, &expr_config()).unwrap();
+        assert!(is_expression(&ast));
+
+        let mut out = vec![];
+        eval_file_as_expr(&mut VM::with_config(expr_config()), path, &ast, &mut out).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn eval_expression_file() {
+        let path = std::env::temp_dir().join("radish_eval_expr.rdsh");
+        let path = path.to_str().unwrap();
+
+        fs::write(path, "// the answer\n(1 + 2) * 14\n").unwrap();
+        assert_eq!(eval_as_expr(path), "42\n");
+
+        fs::write(path, "\"rad\" + \"ish\"").unwrap();
+        assert_eq!(eval_as_expr(path), "radish\n");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn statement_files_are_not_expressions() {
+        let path = std::env::temp_dir().join("radish_eval_statements.rdsh");
+        let path = path.to_str().unwrap();
+
+        fs::write(path, "var a = 1\na + 1\n").unwrap();
+        assert!(!is_expression(&parse_file(path, &expr_config()).unwrap()));
+
+        fs::write(path, "print 1").unwrap();
+        assert!(!is_expression(&parse_file(path, &expr_config()).unwrap()));
+
+        fs::remove_file(path).unwrap();
+
+        assert!(parse_file(path, &expr_config()).is_err());
+    }
+
+    #[test]
+    fn files_run_from_their_ast() {
+        let path = std::env::temp_dir().join("radish_run_ast.rdsh");
+        let path = path.to_str().unwrap();
+
+        fs::write(path, "var a = 20\na + 22\n").unwrap();
+        let ast = parse_file(path, &expr_config()).unwrap();
+
+        // the file isn't read again, so changing it makes no difference.
+        fs::write(path, "0\n").unwrap();
+
+        let mut vm = VM::with_config(expr_config());
+        assert_eq!(vm.eval_file_ast::<i32>(path, &ast), Ok(42));
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,13 +1,13 @@
 //! REPL for the Radish programming language.
 
-use radish::{RadishError, VM};
+use radish::{error::renderer::DEFAULT_TAB_WIDTH, RadishError, Value, VM};
+
+use std::fs;
 
 use rustyline::{error::ReadlineError, Editor};
 
 use crate::hint::{register_hints, RadishRustylineHelper};
 
-use term_size;
-
 /// Repl's line prompt character.
 const PROMPT: &str = "> ";
 /// line countinuation character.
@@ -15,6 +15,34 @@ const CONTINUE: &str = "| ";
 
 /// help command.
 const HELP: &str = ".help";
+/// load command, followed by the path of the file to load.
+const LOAD: &str = ":load";
+/// vars command, lists the session's global variables.
+const VARS: &str = ":vars";
+/// type command, followed by an expression whose type is printed.
+const TYPE: &str = ":type";
+/// reset command, forgets everything defined in the session.
+const RESET: &str = ":reset";
+
+/// Formats an evaluated result for display.
+pub type Formatter = Box<dyn Fn(&Value) -> String>;
+
+/// Where the REPL reads its lines from.
+trait LineReader {
+    fn readline(&mut self, prompt: &str) -> Result<String, ReadlineError>;
+
+    fn add_history_entry(&mut self, line: String);
+}
+
+impl LineReader for Editor<RadishRustylineHelper> {
+    fn readline(&mut self, prompt: &str) -> Result<String, ReadlineError> {
+        Editor::readline(self, prompt)
+    }
+
+    fn add_history_entry(&mut self, line: String) {
+        Editor::add_history_entry(self, line);
+    }
+}
 
 enum ReplResult<T> {
     Ok(T),
@@ -28,6 +56,12 @@ pub struct Repl<'a> {
     lines: Vec<String>,
     prev_len: usize,
     prompt: &'a str,
+    /// Whether errors are underlined using color.
+    color: bool,
+    /// How many columns a tab in an error's source line is shown as.
+    tab_width: usize,
+    /// Formats each evaluated result before it's printed.
+    formatter: Formatter,
 }
 
 impl<'a> Repl<'a> {
@@ -37,9 +71,34 @@ impl<'a> Repl<'a> {
             lines: vec![],
             prev_len: 0,
             prompt: PROMPT,
+            color: true,
+            tab_width: DEFAULT_TAB_WIDTH,
+            formatter: Box::new(default_formatter),
         }
     }
 
+    /// Underline errors using color when `color` is set.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Show tabs in the source lines of errors as `tab_width` columns.
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Format evaluated results with `formatter` instead of as `=> {repr}`.
+    /// Results that format as an empty string aren't printed.
+    pub fn with_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&Value) -> String + 'static,
+    {
+        self.formatter = Box::new(formatter);
+        self
+    }
+
     /// Fire up the REPL
     pub fn run(&mut self) -> Result<(), String> {
         self.print_welcome();
@@ -49,19 +108,37 @@ impl<'a> Repl<'a> {
         let mut editor = Editor::<RadishRustylineHelper>::new();
         editor.set_helper(Some(helper));
 
+        self.run_with(&mut editor)
+    }
+
+    /// The read-eval-print loop, reading lines from `reader` until it runs out.
+    fn run_with(&mut self, reader: &mut impl LineReader) -> Result<(), String> {
         loop {
-            match self.readline(&mut editor) {
+            match self.readline(reader) {
                 ReplResult::Ok(_) => {
                     self.reset();
                     continue;
                 }
                 ReplResult::Error(err) => {
-                    err.emit();
+                    // runtime errors have already been reported by the VM.
+                    if !matches!(err, RadishError::RuntimeError(_)) {
+                        err.emit_with_tab_width(self.color, self.tab_width);
+                    }
+
+                    self.lines.truncate(self.prev_len);
+                    self.reset();
+                    continue;
+                }
+                // Ctrl+D in the middle of an entry only cancels the entry.
+                ReplResult::Eof if self.prompt == CONTINUE => {
+                    println!();
                     self.lines.truncate(self.prev_len);
                     self.reset();
                     continue;
                 }
                 ReplResult::Eof => {
+                    // the prompt's line isn't ended when input runs out.
+                    println!();
                     println!("Goodbye!");
                     break;
                 }
@@ -76,7 +153,7 @@ impl<'a> Repl<'a> {
     }
 
     /// Read a line, also handling multiline input.
-    fn readline(&mut self, editor: &mut Editor<RadishRustylineHelper>) -> ReplResult<()> {
+    fn readline(&mut self, editor: &mut impl LineReader) -> ReplResult<()> {
         let line = editor.readline(self.prompt);
 
         match line {
@@ -85,6 +162,42 @@ impl<'a> Repl<'a> {
                 editor.add_history_entry(line.to_string());
                 ReplResult::Ok(())
             }
+            Ok(line) if line.trim() == VARS => {
+                editor.add_history_entry(line.to_string());
+
+                for var in self.vars() {
+                    println!("{}", var);
+                }
+
+                ReplResult::Ok(())
+            }
+            Ok(line) if line.trim() == RESET => {
+                editor.add_history_entry(line.to_string());
+
+                self.clear_session();
+                println!("Session cleared");
+
+                ReplResult::Ok(())
+            }
+            Ok(line) if line.starts_with(TYPE) => {
+                editor.add_history_entry(line.to_string());
+
+                match self.type_of(line[TYPE.len()..].trim()) {
+                    Ok(type_name) => {
+                        println!("{}", type_name);
+                        ReplResult::Ok(())
+                    }
+                    Err(err) => ReplResult::Error(err),
+                }
+            }
+            Ok(line) if line.starts_with(LOAD) => {
+                editor.add_history_entry(line.to_string());
+
+                match self.load(line[LOAD.len()..].trim()) {
+                    Ok(_) => ReplResult::Ok(()),
+                    Err(err) => ReplResult::Error(err),
+                }
+            }
             Ok(line) => {
                 self.lines.push(line.clone());
                 editor.add_history_entry(line.to_string());
@@ -116,13 +229,68 @@ impl<'a> Repl<'a> {
 
     /// The evil, err, _eval_ part of REPL.
     fn eval(&mut self) -> Result<(), RadishError> {
-        let result = self.vm.eval(&self.lines.join("\n"))?;
+        let result = self.evaluate()?;
+
+        if let Some(line) = self.format_result(&result) {
+            println!("{}", line);
+        }
+
+        Ok(())
+    }
+
+    /// The line to print for an evaluated result, if any. Statements evaluate
+    /// to nil, so nil is never shown.
+    fn format_result(&self, result: &Value) -> Option<String> {
+        if *result == Value::Nil {
+            return None;
+        }
+
+        Some((self.formatter)(result)).filter(|line| !line.is_empty())
+    }
+
+    /// Evaluate the session's lines, returning the value of the last one.
+    /// Globals defined outside of the lines, by loaded files, can be used.
+    fn evaluate(&mut self) -> Result<Value, RadishError> {
+        self.vm.eval_in_session(&self.lines.join("\n"))
+    }
 
-        println!("{:?}", result);
+    /// Run a file once, so that its definitions can be used from the lines
+    /// that follow. The file isn't added to the session's lines, so it isn't
+    /// run again with them.
+    fn load(&mut self, path: &str) -> Result<(), RadishError> {
+        let src = fs::read_to_string(path)?;
+
+        self.vm.eval_in_session::<Value>(&src)?;
 
         Ok(())
     }
 
+    /// Evaluate an expression against the session's globals, returning the
+    /// name of its value's type. Only the expression is run, and it isn't kept
+    /// in the session.
+    fn type_of(&mut self, expr: &str) -> Result<&'static str, RadishError> {
+        self.vm
+            .eval_in_session::<Value>(expr)
+            .map(|value| value.type_name())
+    }
+
+    /// The session's global variables and their values, in the order they
+    /// were defined.
+    fn vars(&self) -> Vec<String> {
+        self.vm
+            .globals()
+            .into_iter()
+            .map(|(name, value)| format!("{} = {}", name, value.repr()))
+            .collect()
+    }
+
+    /// Forget the session's lines and everything they defined, keeping the
+    /// builtins.
+    fn clear_session(&mut self) {
+        self.lines.clear();
+        self.vm.reset();
+    }
+
     /// Reset the REPL's state.
     fn reset(&mut self) {
         //self.lines.clear();
@@ -135,6 +303,10 @@ impl<'a> Repl<'a> {
         let help_message = "Press Ctrl+C to abort current expression, Ctrl+D to exit the REPL";
 
         println!("{}", help_message);
+        println!("{} <path>  run a file, keeping its definitions", LOAD);
+        println!("{}         list the session's variables", VARS);
+        println!("{} <expr>  show the type of an expression", TYPE);
+        println!("{}        forget the session's variables", RESET);
     }
 
     // TODO: a bit much? maybe scale it down a little?
@@ -166,11 +338,16 @@ impl<'a> Repl<'a> {
             println!("{}{}", lines[5], exit);
             println!("{}", lines[6]);
             println!("{}{}", lines[7], version);
-            println!("");
+            println!();
         }
     }
 }
 
+/// The REPL's default result formatter.
+pub fn default_formatter(value: &Value) -> String {
+    format!("=> {}", value.repr())
+}
+
 /// VM's welcome banner.
 const WELCOME: &str = r#"
  ________  ________  ________  ___  ________  ___  ___       
@@ -180,3 +357,225 @@ const WELCOME: &str = r#"
   \ \  \\  \\ \  \ \  \ \  \_\\ \ \  \|____|\  \ \  \ \  \   
    \ \__\\ _\\ \__\ \__\ \_______\ \__\_________\ \__\ \__\  
     \|__|\|__|\|__|\|__|\|_______|\|__||_________||__|\|__|  "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use radish::config::Config;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn repl() -> Repl<'static> {
+        let mut config = Config::new();
+        config.repl = true;
+
+        Repl::new(VM::with_config(config))
+    }
+
+    #[test]
+    fn load_file_definitions() {
+        let path = std::env::temp_dir().join("radish_repl_load.rdsh");
+        fs::write(&path, "print \"loaded\"\nfun double(x) {\n    return x * 2\n}\n").unwrap();
+
+        let out = Rc::new(RefCell::new(Vec::new()));
+
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(SharedOutput(Rc::clone(&out)));
+
+        let mut repl = Repl::new(vm);
+        repl.load(path.to_str().unwrap()).unwrap();
+        assert!(repl.lines.is_empty());
+
+        repl.lines.push("var a = double(21)".to_string());
+        repl.evaluate().unwrap();
+
+        repl.lines.push("double(a)".to_string());
+        assert_eq!(repl.evaluate(), Ok(Value::Integer(84)));
+
+        assert_eq!(String::from_utf8(out.borrow().clone()).unwrap(), "loaded\n");
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn vars_in_definition_order() {
+        let mut repl = repl();
+
+        for line in &["var zebra = 1", "var apple = \"a\"", "fun mango() {}", "var kiwi"] {
+            repl.lines.push(line.to_string());
+            repl.evaluate().unwrap();
+        }
+
+        assert_eq!(
+            repl.vars(),
+            vec![
+                "zebra = 1",
+                "apple = \"a\"",
+                "mango = <fun mango/0>",
+                "kiwi = nil"
+            ]
+        );
+    }
+
+    #[test]
+    fn type_of_expression() {
+        let mut repl = repl();
+
+        assert_eq!(repl.type_of("true"), Ok("boolean"));
+        assert_eq!(repl.type_of("1 + 2"), Ok("number"));
+
+        repl.lines.push("var name = \"radish\"".to_string());
+        repl.evaluate().unwrap();
+
+        assert_eq!(repl.type_of("name"), Ok("string"));
+        assert_eq!(repl.lines.len(), 1);
+    }
+
+    /// Output from the VM that can be inspected after being handed to it.
+    struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+    impl std::io::Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn type_of_only_runs_the_expression() {
+        let out = Rc::new(RefCell::new(Vec::new()));
+
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(SharedOutput(Rc::clone(&out)));
+
+        let mut repl = Repl::new(vm);
+
+        repl.lines.push("print \"hello\"\nvar n = 1".to_string());
+        repl.evaluate().unwrap();
+
+        assert_eq!(repl.type_of("n"), Ok("number"));
+        assert_eq!(repl.type_of("[n]"), Ok("array"));
+        assert_eq!(String::from_utf8(out.borrow().clone()).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn type_of_invalid_expression() {
+        let mut repl = repl();
+
+        assert!(matches!(
+            repl.type_of("1 +"),
+            Err(RadishError::CompilerError(_))
+        ));
+        assert!(repl.lines.is_empty());
+    }
+
+    #[test]
+    fn default_result_format() {
+        let mut repl = repl();
+
+        repl.lines.push("1 + 2".to_string());
+        let result = repl.evaluate().unwrap();
+
+        assert_eq!(repl.format_result(&result), Some("=> 3".to_string()));
+        assert_eq!(repl.format_result(&Value::Nil), None);
+    }
+
+    #[test]
+    fn custom_result_formatter() {
+        let mut repl = repl().with_formatter(|value| match value {
+            Value::Boolean(_) => String::new(),
+            _ => format!("{} :: {}", value.repr(), value.type_name()),
+        });
+
+        repl.lines.push("\"rad\" + \"ish\"".to_string());
+        let result = repl.evaluate().unwrap();
+
+        assert_eq!(
+            repl.format_result(&result),
+            Some("\"radish\" :: string".to_string())
+        );
+        assert_eq!(repl.format_result(&Value::Boolean(true)), None);
+    }
+
+    /// Reads lines from a script, then reports the end of input.
+    struct ScriptedReader(Vec<Result<String, ReadlineError>>);
+
+    impl ScriptedReader {
+        fn new(lines: Vec<Result<&str, ReadlineError>>) -> Self {
+            let mut lines: Vec<_> = lines.into_iter().map(|line| line.map(String::from)).collect();
+            lines.reverse();
+
+            ScriptedReader(lines)
+        }
+    }
+
+    impl LineReader for ScriptedReader {
+        fn readline(&mut self, _prompt: &str) -> Result<String, ReadlineError> {
+            self.0.pop().unwrap_or(Err(ReadlineError::Eof))
+        }
+
+        fn add_history_entry(&mut self, _line: String) {}
+    }
+
+    #[test]
+    fn eof_exits_cleanly() {
+        let mut repl = repl();
+        let mut reader = ScriptedReader::new(vec![Ok("var a = 1")]);
+
+        assert_eq!(repl.run_with(&mut reader), Ok(()));
+        assert_eq!(repl.lines, ["var a = 1"]);
+    }
+
+    #[test]
+    fn eof_cancels_continued_entry() {
+        let mut repl = repl();
+        let mut reader = ScriptedReader::new(vec![
+            Ok("var a = 1"),
+            Ok("fun f() {"),
+            Ok("    return 1"),
+            Err(ReadlineError::Eof),
+            Ok("var b = a + 1"),
+        ]);
+
+        assert_eq!(repl.run_with(&mut reader), Ok(()));
+        assert_eq!(repl.lines, ["var a = 1", "var b = a + 1"]);
+        assert_eq!(repl.prompt, PROMPT);
+    }
+
+    #[test]
+    fn reset_clears_the_session() {
+        let mut repl = repl();
+        let mut reader = ScriptedReader::new(vec![Ok("var a = 1"), Ok(":reset"), Ok("var b = 2")]);
+
+        assert_eq!(repl.run_with(&mut reader), Ok(()));
+        assert_eq!(repl.lines, ["var b = 2"]);
+        assert_eq!(repl.vars(), ["b = 2"]);
+
+        repl.lines.push("a".to_string());
+        assert!(repl.evaluate().is_err());
+        repl.lines.pop();
+
+        repl.lines.push("len([1, 2])".to_string());
+        assert_eq!(repl.evaluate(), Ok(Value::Integer(2)));
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let mut repl = repl();
+
+        assert!(matches!(
+            repl.load("does/not/exist.rdsh"),
+            Err(RadishError::IOError(_))
+        ));
+        assert!(repl.lines.is_empty());
+    }
+}
+
@@ -0,0 +1,91 @@
+//! Throughput benchmarks for each phase of Radish's pipeline.
+//!
+//! Run with `cargo bench -p radish`. A single workload or phase can be
+//! selected by passing a filter, e.g. `cargo bench -p radish -- parse/calls`.
+
+use std::io;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use radish::{
+    common::source::Source,
+    compiler::{pipeline::CompilerPipeLine, scanner::Scanner, token::TokenType},
+    config::Config,
+    VM,
+};
+
+/// Representative programs, stored as fixtures in `benches/fixtures`.
+const WORKLOADS: &[(&str, &str)] = &[
+    ("arithmetic", include_str!("fixtures/arithmetic.rdsh")),
+    ("variables", include_str!("fixtures/variables.rdsh")),
+    ("calls", include_str!("fixtures/calls.rdsh")),
+];
+
+fn pipeline() -> CompilerPipeLine {
+    CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes()
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+
+    for (name, src) in WORKLOADS {
+        group.throughput(Throughput::Bytes(src.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut scanner = Scanner::new(Source::new(black_box(src), name));
+                while scanner.scan_token().token_type != TokenType::Eof {}
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for (name, src) in WORKLOADS {
+        group.throughput(Throughput::Bytes(src.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| pipeline().parse(name, black_box(src)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn compile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compile");
+
+    for (name, src) in WORKLOADS {
+        let ast = pipeline().parse(name, src).unwrap();
+
+        group.throughput(Throughput::Bytes(src.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| pipeline().compile_ast(name, black_box(&ast)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn run(c: &mut Criterion) {
+    let mut group = c.benchmark_group("run");
+    group.sample_size(20);
+
+    for (name, src) in WORKLOADS {
+        group.throughput(Throughput::Bytes(src.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let mut vm = VM::new();
+                vm.set_stdout(io::sink());
+                vm.exec(black_box(src)).unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, scan, parse, compile, run);
+criterion_main!(benches);
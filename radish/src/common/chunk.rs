@@ -1,6 +1,10 @@
 use std::fmt;
 use std::collections::HashMap;
+use std::convert::TryInto;
+use std::rc::Rc;
 
+use crate::common::opcode::Opcode;
+use crate::common::span::Span;
 use crate::Value;
 
 /// A chunk of bytecode and its associated data.
@@ -12,6 +16,13 @@ pub struct Chunk {
     pub constants: Vec<Value>,
     /// Contains identifers mapped to thier location in the constants array.
     pub identifiers: HashMap<String, usize>,
+    /// The most values this chunk's code has on the stack at once, counting
+    /// its parameters. Filled in by the compiler once the code is complete.
+    pub max_stack: usize,
+    /// The source span of each run of bytes in `code`, as the offset of the
+    /// run's first byte and the span that its bytes were compiled from.
+    /// Consecutive bytes from the same span share a single entry.
+    spans: Vec<(usize, Span)>,
 }
 
 impl Chunk {
@@ -20,9 +31,139 @@ impl Chunk {
             code,
             constants,
             identifiers: HashMap::new(),
+            max_stack: 0,
+            spans: vec![],
         }
     }
 
+    /// Append a byte to the code, compiled from `span`.
+    pub fn write(&mut self, byte: u8, span: &Span) {
+        let offset = self.code.len();
+        self.code.push(byte);
+
+        match self.spans.last() {
+            Some((_, last)) if same_span(last, span) => {}
+            _ => self.spans.push((offset, Span::from(span))),
+        }
+    }
+
+    /// The source span that the byte at `offset` in the code was compiled
+    /// from, or an empty span if it wasn't written with one.
+    pub fn span_at(&self, offset: usize) -> Span {
+        let runs = self.spans.partition_point(|(start, _)| *start <= offset);
+
+        match runs.checked_sub(1) {
+            Some(run) => Span::from(&self.spans[run].1),
+            None => Span::empty(),
+        }
+    }
+
+    /// Work out the most values the code keeps on the stack at once, starting
+    /// with `params` arguments, by following the stack effect of each
+    /// instruction along every path through the code. The stack of the
+    /// functions it calls isn't included.
+    pub fn stack_depth(&self, params: usize) -> usize {
+        let code = &self.code;
+
+        // no instruction pushes more than two values, so any path that gets
+        // higher than this keeps growing a loop, which is a compiler bug.
+        let limit = params + 2 * code.len();
+
+        let read_short = |at: usize| u16::from_le_bytes([code[at], code[at + 1]]) as usize;
+        let read_long = |at: usize| {
+            let bytes = code[at..at + 4].try_into().expect("truncated operand");
+            u32::from_le_bytes(bytes) as usize
+        };
+
+        let mut heights: Vec<Option<usize>> = vec![None; code.len()];
+        let mut pending = vec![(0, params)];
+        let mut max = params;
+
+        while let Some((ip, height)) = pending.pop() {
+            if ip >= code.len() || height > limit {
+                continue;
+            }
+
+            if matches!(heights[ip], Some(seen) if seen >= height) {
+                continue;
+            }
+
+            heights[ip] = Some(height);
+            max = max.max(height);
+
+            let (len, effect): (usize, isize) = match Opcode::from(code[ip]) {
+                Opcode::LoadConst => (2, 1),
+                Opcode::LoadConstLong
+                | Opcode::LoadGlobal
+                | Opcode::LoadLocal
+                | Opcode::LoadCapture => (5, 1),
+                Opcode::True | Opcode::False | Opcode::Nil | Opcode::Zero | Opcode::One => (1, 1),
+                Opcode::SaveGlobal | Opcode::SaveLocal | Opcode::SaveCapture => (5, 0),
+                Opcode::DefGlobal => (5, -1),
+                Opcode::Del
+                | Opcode::LoadField
+                | Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Rem
+                | Opcode::CmpLT
+                | Opcode::CmpLTEq
+                | Opcode::CmpGT
+                | Opcode::CmpGTEq
+                | Opcode::CmpEq
+                | Opcode::CmpNotEq
+                | Opcode::BuildCon
+                | Opcode::Print => (1, -1),
+                Opcode::SaveField => (1, -3),
+                Opcode::DefCapture
+                | Opcode::Neg
+                | Opcode::Not
+                | Opcode::BuildClass
+                | Opcode::Import => (1, 0),
+                Opcode::Call => (2, -(code[ip + 1] as isize)),
+                Opcode::BuildArray => (5, 1 - read_long(ip + 1) as isize),
+                Opcode::BuildMap => (5, 1 - 2 * read_long(ip + 1) as isize),
+                Opcode::BuildString => (5, 1 - read_long(ip + 1) as isize),
+                Opcode::Closure => (2 + 2 * code[ip + 1] as usize, 0),
+                Opcode::JumpIfFalse | Opcode::JumpIfTrue => {
+                    pending.push((ip + 3 + read_short(ip + 1), height));
+                    (3, 0)
+                }
+                Opcode::Jump => {
+                    pending.push((ip + 3 + read_short(ip + 1), height));
+                    continue;
+                }
+                Opcode::Loop => {
+                    pending.push((ip + 3 - read_short(ip + 1), height));
+                    continue;
+                }
+                Opcode::Iterate => {
+                    // the next item and `true`, or just `false` once the
+                    // iterable is exhausted, which the following jump leaves on.
+                    let next = ip + 5;
+
+                    if Opcode::from(code[next]) == Opcode::JumpIfFalse {
+                        pending.push((next + 3 + read_short(next + 1), height + 1));
+                        pending.push((next + 3, height + 2));
+                        max = max.max(height + 2);
+                        continue;
+                    }
+
+                    (5, 2)
+                }
+                Opcode::Return => continue,
+            };
+
+            let height = (height as isize + effect).max(0) as usize;
+            max = max.max(height);
+
+            pending.push((ip + len, height));
+        }
+
+        max
+    }
+
     /// Add a [`Value`] to this [`Chunk`]'s constants array, 
     /// returning its index.
     pub fn add_constant(&mut self, value: Value) -> usize {
@@ -45,6 +186,12 @@ impl Chunk {
     }
 }
 
+/// Whether two spans cover the same bytes of the same source, without
+/// comparing the sources' contents.
+fn same_span(a: &Span, b: &Span) -> bool {
+    Rc::ptr_eq(&a.source, &b.source) && a.start == b.start && a.end == b.end
+}
+
 impl Default for Chunk {
     fn default() -> Self {
         Chunk::new(vec![], vec![])
@@ -66,3 +213,30 @@ impl fmt::Display for Chunk {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::source::Source;
+
+    #[test]
+    fn spans_are_run_length_encoded() {
+        let source = Source::new("print 1 + 2", "");
+        let print = Span::new(Rc::clone(&source), 0, 11);
+        let sum = Span::new(Rc::clone(&source), 6, 11);
+
+        let mut chunk = Chunk::default();
+        chunk.write(Opcode::One as u8, &sum);
+        chunk.write(Opcode::LoadConst as u8, &sum);
+        chunk.write(0, &sum);
+        chunk.write(Opcode::Add as u8, &sum);
+        chunk.write(Opcode::Print as u8, &print);
+
+        assert_eq!(chunk.spans.len(), 2);
+
+        let texts: Vec<_> = (0..5).map(|offset| chunk.span_at(offset).text().to_string()).collect();
+        assert_eq!(texts, ["1 + 2", "1 + 2", "1 + 2", "1 + 2", "print 1 + 2"]);
+
+        assert_eq!(Chunk::default().span_at(0).text(), "");
+    }
+}
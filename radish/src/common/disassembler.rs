@@ -60,26 +60,29 @@ impl<'a> Disassembler<'a> {
             Opcode::True => self.simple_instruction("True", offset),
             Opcode::False => self.simple_instruction("False", offset),
             Opcode::Nil => self.simple_instruction("Nil", offset),
+            Opcode::Zero => self.simple_instruction("Zero", offset),
+            Opcode::One => self.simple_instruction("One", offset),
 
-            Opcode::Add => self.simple_instruction("Add", offset),
-            Opcode::Sub => self.simple_instruction("Sub", offset),
-            Opcode::Mul => self.simple_instruction("Mul", offset),
-            Opcode::Div => self.simple_instruction("Div", offset),
-            Opcode::Rem => self.simple_instruction("Rem", offset),
+            Opcode::Add => self.operator_instruction("Add", offset),
+            Opcode::Sub => self.operator_instruction("Sub", offset),
+            Opcode::Mul => self.operator_instruction("Mul", offset),
+            Opcode::Div => self.operator_instruction("Div", offset),
+            Opcode::Rem => self.operator_instruction("Rem", offset),
 
             Opcode::Neg => self.simple_instruction("Negate", offset),
-            Opcode::Not => self.simple_instruction("Not", offset),
-            Opcode::CmpLT => self.simple_instruction("LessThan", offset),
-            Opcode::CmpLTEq => self.simple_instruction("LessThanEquals", offset),
-            Opcode::CmpGT => self.simple_instruction("GreaterThan", offset),
-            Opcode::CmpGTEq => self.simple_instruction("GreaterThanEquals", offset),
-            Opcode::CmpEq => self.simple_instruction("EqualsTo", offset),
-            Opcode::CmpNotEq => self.simple_instruction("NotEqual", offset),
+            Opcode::Not => self.operator_instruction("Not", offset),
+            Opcode::CmpLT => self.operator_instruction("LessThan", offset),
+            Opcode::CmpLTEq => self.operator_instruction("LessThanEquals", offset),
+            Opcode::CmpGT => self.operator_instruction("GreaterThan", offset),
+            Opcode::CmpGTEq => self.operator_instruction("GreaterThanEquals", offset),
+            Opcode::CmpEq => self.operator_instruction("EqualsTo", offset),
+            Opcode::CmpNotEq => self.operator_instruction("NotEqual", offset),
 
             Opcode::JumpIfTrue => self.jump_instruction("JumpIfTrue", 1, offset),
             Opcode::JumpIfFalse => self.jump_instruction("JumpIfFalse", 1, offset),
             Opcode::Jump => self.jump_instruction("Jump", 1, offset),
             Opcode::Loop => self.jump_instruction("Loop", -1, offset),
+            Opcode::Iterate => self.long_const_instruction("Iterate", offset, false),
 
             Opcode::Call => {
                 self.write_instruction("Call", offset);
@@ -97,6 +100,7 @@ impl<'a> Disassembler<'a> {
 
             Opcode::BuildArray => self.long_const_instruction("BuildArray", offset, false),
             Opcode::BuildMap => self.long_const_instruction("BuildMap", offset, false),
+            Opcode::BuildString => self.long_const_instruction("BuildString", offset, false),
             Opcode::Closure => self.closure(offset),
             Opcode::BuildClass => self.simple_instruction("Class", offset),
             Opcode::BuildCon => self.simple_instruction("BuildCon", offset),
@@ -112,6 +116,19 @@ impl<'a> Disassembler<'a> {
         offset + 1
     }
 
+    /// An instruction that applies an operator, shown with the operator's syntax.
+    fn operator_instruction(&self, name: &str, offset: usize) -> usize {
+        use crate::common::opcode::Opcode;
+        use crate::compiler::ast::Op;
+
+        self.write_instruction(name, offset);
+        match Op::from_opcode(&Opcode::from(self.function.chunk.code[offset])) {
+            Some(op) => println!(" ({})", op),
+            None => println!(),
+        }
+        offset + 1
+    }
+
     fn byte_instruction(&self, name: &str, offset: usize) -> usize {
         self.write_instruction(name, offset);
         let index = &self.function.chunk.code[offset + 1];
@@ -160,7 +177,7 @@ impl<'a> Disassembler<'a> {
 
         let index = u32::from_le_bytes(bytes);
 
-        print!("{}\n", index);
+        println!("{}", index);
 
         offset + 5
     }
@@ -9,6 +9,9 @@ use crate::{
 pub struct Loader {
     resolver: Resolver,
     cache: BTreeMap<String, CompiledModule>,
+    /// The names of the cached modules that were compiled from source files,
+    /// rather than registered natively.
+    files: Vec<String>,
 }
 
 impl Default for Loader {
@@ -22,35 +25,38 @@ impl Loader {
         Self {
             resolver: Resolver::new(),
             cache: BTreeMap::new(),
+            files: Vec::new(),
         }
     }
 
     pub fn is_cached(&self, path: &str) -> bool {
-        let file_path = path;
+        self.cache.contains_key(path)
+    }
 
-        if !self.cache.is_empty() {
-            self.cache.contains_key(file_path)
-        } else {
-            false
-        }
+    pub fn get(&self, path: &str) -> Option<CompiledModule> {
+        self.cache.get(path).map(Rc::clone)
+    }
+
+    /// Resolve the file imported as `path` from the file `importer`.
+    pub fn resolve(&self, path: &str, importer: &str) -> String {
+        self.resolver.resolve(path, importer)
     }
 
+    /// Load the module at `path`, which must already be resolved. A module is
+    /// only compiled the first time it is loaded; afterwards the cached module
+    /// is returned.
     pub fn load(
         &mut self,
         path: &str,
         compiler: &mut CompilerPipeLine,
     ) -> Result<CompiledModule, RadishError> {
-        let name = self.resolver.resolve(path)?;
-
-        // TODO: handle chached files here? 
-        if self.is_cached(&name) {
-            let module = self.cache.get(&name);
-            return Ok(Rc::clone(module.unwrap()))
-        } else if let Ok(src) = fs::read_to_string(&name) {
-            return self.load_file(&src, &name, compiler)
-        } else {
-            panic!("failed to import module {}", name);
+        if let Some(module) = self.cache.get(path) {
+            return Ok(Rc::clone(module));
         }
+
+        let src = fs::read_to_string(path).map_err(|e| RadishError::IOError(e.into()))?;
+
+        self.load_file(&src, path, compiler)
     }
 
     pub fn load_file(
@@ -65,13 +71,28 @@ impl Loader {
             return Ok(module.clone());
         }
 
-        let module = compiler.compile(name, &src)?;
+        let module = compiler.compile_module(name, src)?;
 
-        self.cache.insert(name.to_string(), module.clone());
+        self.load_compiled(name, module.clone());
 
         Ok(module)
     }
 
+    /// Cache `module`, already compiled from the file at `path`, as if it had
+    /// been loaded from that file.
+    pub fn load_compiled(&mut self, path: &str, module: CompiledModule) {
+        self.cache.insert(path.to_string(), module);
+        self.files.push(path.to_string());
+    }
+
+    /// Forget every module compiled from a file, so they're loaded afresh the
+    /// next time. Natively registered modules are kept.
+    pub fn unload_files(&mut self) {
+        for name in self.files.drain(..) {
+            self.cache.remove(&name);
+        }
+    }
+
     pub fn load_module(&mut self, name: &str, module: Module) {
         let mod_ref = Rc::new(RefCell::new(module));
         self.cache.insert(name.to_string(), mod_ref);
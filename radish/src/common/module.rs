@@ -14,6 +14,10 @@ pub struct Module {
     pub name: Box<str>,
     pub variables: Vec<Value>,
     pub symbols: HashMap<String, usize>,
+    /// The name of each variable, in the order they were added.
+    names: Vec<String>,
+    /// Whether each variable has been given a value.
+    initialized: Vec<bool>,
 }
 
 impl Module {
@@ -27,6 +31,8 @@ impl Module {
             name: name.to_string().into_boxed_str(),
             variables: Vec::new(),
             symbols: HashMap::new(),
+            names: Vec::new(),
+            initialized: Vec::new(),
         }
     }
 
@@ -34,11 +40,32 @@ impl Module {
         Module::new("")
     }
 
-    pub fn add_native<F: 'static>(&mut self, name: &str, airty: u8, fun: F) -> &mut Self
+    pub fn add_native<F>(&mut self, name: &str, airty: u8, fun: F) -> &mut Self
     where
-        F: Fn(&mut VM, Vec<Value>) -> Result<Value, Trace>,
+        F: 'static + Fn(&mut VM, Vec<Value>) -> Result<Value, Trace>,
     {
-        let native_fun = NativeFunction::new::<F>(Rc::new(fun), airty);
+        let native_fun = NativeFunction::new(Rc::new(fun), airty);
+
+        let index = self.add_symbol(name.to_string());
+
+        self.set_value_at_index(index, Value::NativeFunction(Rc::new(native_fun)));
+
+        self
+    }
+
+    /// Add a native function that takes `arity` arguments, followed by up to
+    /// `optional` more.
+    pub fn add_native_with_optional_args<F>(
+        &mut self,
+        name: &str,
+        arity: u8,
+        optional: u8,
+        fun: F,
+    ) -> &mut Self
+    where
+        F: 'static + Fn(&mut VM, Vec<Value>) -> Result<Value, Trace>,
+    {
+        let native_fun = NativeFunction::with_optional_args(Rc::new(fun), arity, optional);
 
         let index = self.add_symbol(name.to_string());
 
@@ -72,10 +99,7 @@ impl Module {
     }
 
     pub fn get_variable<F: FromValue>(&self, name: &str) -> Option<F> {
-        let index = match self.get_index(name) {
-            Some(i) => i,
-            None => return None,
-        };
+        let index = self.get_index(name)?;
 
         let value = self.get_value_at_index(index).clone();
 
@@ -86,7 +110,9 @@ impl Module {
     pub(crate) fn add_symbol(&mut self, name: String) -> usize {
         let index = self.variables.len();
         self.variables.push(Value::Nil);
+        self.initialized.push(false);
 
+        self.names.push(name.clone());
         self.symbols.insert(name, index);
 
         index
@@ -100,6 +126,31 @@ impl Module {
     #[inline]
     pub(crate) fn set_value_at_index(&mut self, index: usize, value: Value) {
         self.variables[index] = value;
+        self.initialized[index] = true;
+    }
+
+    /// Whether the variable at `index` has been given a value, rather than only
+    /// being declared.
+    #[inline]
+    pub(crate) fn is_initialized(&self, index: usize) -> bool {
+        self.initialized[index]
+    }
+
+    /// The name of the variable at `index`.
+    pub(crate) fn name_of(&self, index: usize) -> Option<&str> {
+        self.names.get(index).map(|name| name.as_str())
+    }
+
+    /// The module's named variables in the order they were defined. Variables
+    /// that have since been redeclared are left out.
+    pub fn globals(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(move |(index, name)| {
+                !name.is_empty() && self.symbols.get(name.as_str()) == Some(index)
+            })
+            .map(move |(index, name)| (name.as_str(), &self.variables[index]))
     }
 
     #[inline]
@@ -111,8 +162,10 @@ impl Module {
         let index = self.variables.len();
 
         self.variables.push(Value::from(fun));
+        self.initialized.push(true);
 
-        self.symbols.insert(String::from(""), index);
+        self.names.push(String::new());
+        self.symbols.insert(String::new(), index);
     }
 
     pub(crate) fn entry(&self) -> Option<Rc<Function>> {
@@ -169,8 +222,8 @@ impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name)?;
 
-        for (name, index) in &self.symbols {
-            let value = &self.variables[*index];
+        for (index, name) in self.names.iter().enumerate() {
+            let value = &self.variables[index];
             write!(
                 f,
                 "\n    {} {}: {}",
@@ -215,6 +268,27 @@ mod tests {
         assert_eq!(value, &Value::Boolean(true));
     }
 
+    #[test]
+    fn globals_in_definition_order() {
+        let mut module = Module::new_("test");
+
+        module.add_value("c", 1.0);
+        module.add_value("a", 2.0);
+        module.add_value("b", 3.0);
+        module.add_value("a", 4.0);
+
+        let globals: Vec<_> = module.globals().collect();
+
+        assert_eq!(
+            globals,
+            vec![
+                ("c", &Value::Number(1.0)),
+                ("b", &Value::Number(3.0)),
+                ("a", &Value::Number(4.0)),
+            ]
+        );
+    }
+
     #[test]
     fn entry() {
         let module = Module::new("test");
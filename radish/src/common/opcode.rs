@@ -77,6 +77,14 @@ pub enum Opcode {
     ///
     /// -> [Value::Nil]
     Nil,
+    /// Push `[Value::Integer(0)]` onto the stack.
+    ///
+    /// -> [Value::Integer(0)]
+    Zero,
+    /// Push `[Value::Integer(1)]` onto the stack.
+    ///
+    /// -> [Value::Integer(1)]
+    One,
     /// Remove the top two `[Value]`s from the stack, add them and push the result
     /// onto the stack.
     ///
@@ -167,6 +175,15 @@ pub enum Opcode {
     ///
     /// [no change]
     Loop,
+    /// Read `index` from the bytecode stream. Step the iteration kept in the local
+    /// slots at `index` (the iterable) and `index + 1` (the position of the next item).
+    /// Push the next item followed by `[Value::Boolean(true)]`, or just
+    /// `[Value::Boolean(false)]` once the iterable is exhausted.
+    ///
+    /// operand: u32 (index)
+    ///
+    /// -> [Value][Value::Boolean(true)] | [Value::Boolean(false)]
+    Iterate,
     /// Read `arg_count` from the top of the stack then peek `arg_count` places into the
     /// stack and call the `[Value]` that it finds, pushing a new call frame onto the callstack.
     ///
@@ -176,6 +193,14 @@ pub enum Opcode {
     Call,
     BuildArray,
     BuildMap,
+    /// Remove `count` values from the stack and push a string of them joined
+    /// together, in the order they were pushed. Each value is written as
+    /// `print` would write it.
+    ///
+    /// operand: u32 (count)
+    ///
+    /// [Value_1]...[Value_n] -> [Value::String]
+    BuildString,
     /// TODO:
     BuildClass,
     /// TODO:
@@ -1,6 +1,4 @@
-use std::path::PathBuf;
-
-use crate::RadishError;
+use std::path::{Component, Path, PathBuf};
 
 pub const RADISH_FILE_EXTENSION: &str = "rdsh";
 
@@ -22,15 +20,66 @@ impl Resolver {
         }
     }
 
-    pub fn resolve(&mut self, path: &str) -> Result<String, RadishError> {
-        let mut file_path = PathBuf::from(path);
+    /// Resolve an import path to the name of the file it refers to.
+    ///
+    /// Relative paths are resolved against the directory of `importer`, the
+    /// file containing the import, and the `.rdsh` extension is added when the
+    /// path has none. The result is normalized so that the same file always
+    /// resolves to the same name.
+    pub fn resolve(&self, path: &str, importer: &str) -> String {
+        let path = Path::new(path);
+
+        let mut file_path = match Path::new(importer).parent() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+
+        if file_path.extension().is_none() {
+            file_path.set_extension(&self.extension);
+        }
+
+        normalize(&file_path).to_string_lossy().into()
+    }
+}
+
+/// Remove `.` components and fold `..` components into their parent,
+/// without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
 
-        match file_path.components().nth(0).unwrap() {
-            std::path::Component::Normal(_) => Ok(path.to_string()),
-            _ => {
-                file_path.set_extension(&self.extension);
-                Ok(file_path.to_string_lossy().into())
-            }
+    for component in path.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
         }
     }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Resolver;
+
+    #[test]
+    fn resolve_adds_extension() {
+        assert_eq!(Resolver::new().resolve("utils", ""), "utils.rdsh");
+        assert_eq!(Resolver::new().resolve("utils.rdsh", ""), "utils.rdsh");
+    }
+
+    #[test]
+    fn resolve_relative_to_importer() {
+        let resolver = Resolver::new();
+
+        assert_eq!(resolver.resolve("utils", "src/main.rdsh"), "src/utils.rdsh");
+        assert_eq!(resolver.resolve("./utils", "src/main.rdsh"), "src/utils.rdsh");
+        assert_eq!(resolver.resolve("../lib/utils", "src/main.rdsh"), "lib/utils.rdsh");
+        assert_eq!(resolver.resolve("/lib/utils", "src/main.rdsh"), "/lib/utils.rdsh");
+    }
 }
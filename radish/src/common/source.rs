@@ -1,19 +1,128 @@
+use std::cell::OnceCell;
+use std::cmp::Ordering;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+/// The character sequences that end a line. `\r\n` comes first so that it's
+/// treated as one line break rather than a `\r` followed by a break. A lone
+/// `\r` is just whitespace.
+pub const LINE_BREAKS: &[&str] = &["\r\n", "\n"];
+
+/// The UTF-8 byte order mark some editors put at the start of a file.
+pub const BOM: char = '\u{FEFF}';
+
+/// The length in bytes of the line break at the start of `text`, if there is one.
+pub fn line_break_at(text: &str) -> Option<usize> {
+    LINE_BREAKS
+        .iter()
+        .find(|line_break| text.starts_with(*line_break))
+        .map(|line_break| line_break.len())
+}
+
+/// Split `contents` into lines, without their line breaks.
+pub fn lines(contents: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = contents.split('\n').collect();
+    let last = lines.len() - 1;
+
+    for line in &mut lines[..last] {
+        *line = line.strip_suffix('\r').unwrap_or(line);
+    }
+
+    lines
+}
+
+/// The zero based line and column (in chars) of the byte `offset` into `contents`.
+pub fn line_col(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (line, before[line_start..].chars().count())
+}
+
+/// The byte offsets at which each line of a source starts, built the first
+/// time they're needed. Being a cache, it never affects how sources compare.
+#[derive(Debug, Clone, Default)]
+struct LineMap(OnceCell<Vec<usize>>);
+
+#[cfg(test)]
+thread_local! {
+    /// The number of line maps built on this thread.
+    static LINE_MAPS_BUILT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl LineMap {
+    fn get(&self, contents: &str) -> &[usize] {
+        self.0.get_or_init(|| {
+            #[cfg(test)]
+            LINE_MAPS_BUILT.with(|built| built.set(built.get() + 1));
+
+            std::iter::once(0)
+                .chain(contents.match_indices('\n').map(|(index, _)| index + 1))
+                .collect()
+        })
+    }
+}
+
+impl PartialEq for LineMap {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for LineMap {}
+
+impl PartialOrd for LineMap {
+    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq)]
 pub struct Source {
     pub contents: String,
     pub path: PathBuf,
+    /// The number of lines that came before this source, such as the earlier
+    /// entries of a REPL session. Only affects the line numbers reported to
+    /// the user.
+    pub base_line: usize,
+    line_map: LineMap,
 }
 
 impl Source {
     pub fn new(source: &str, path: impl ToString) -> Rc<Source> {
+        Source::with_base_line(source, path, 0)
+    }
+
+    /// Create a source whose first line is reported as line `base_line + 1`.
+    ///
+    /// A leading byte order mark is dropped, so offsets into the source are
+    /// offsets into the text after it.
+    pub fn with_base_line(source: &str, path: impl ToString, base_line: usize) -> Rc<Source> {
         Rc::new(Source {
-            contents: source.to_string(),
+            contents: source.strip_prefix(BOM).unwrap_or(source).to_string(),
             path: PathBuf::from(path.to_string()),
+            base_line,
+            line_map: LineMap::default(),
         })
     }
+
+    /// The zero based line and column of the byte `offset` into this source,
+    /// counting from the source's base line.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line_starts = self.line_starts();
+        let line = line_starts.partition_point(|&start| start <= offset) - 1;
+        let col = self.contents[line_starts[line]..offset].chars().count();
+
+        (self.base_line + line, col)
+    }
+
+    /// The byte offset at which each line starts, computed on first use and
+    /// shared by everything holding this source.
+    pub fn line_starts(&self) -> &[usize] {
+        self.line_map.get(&self.contents)
+    }
 }
 
 #[cfg(test)]
@@ -25,4 +134,57 @@ mod tests {
         let source = Source::new("Hello, Radish!", "file/path");
         assert_eq!(source.contents, "Hello, Radish!");
     }
+
+    #[test]
+    fn crlf_is_a_single_line_break() {
+        assert_eq!(line_break_at("\r\nabc"), Some(2));
+        assert_eq!(line_break_at("\nabc"), Some(1));
+        assert_eq!(line_break_at("\rabc"), None);
+        assert_eq!(lines("a\r\nb\nc\r"), vec!["a", "b", "c\r"]);
+    }
+
+    #[test]
+    fn crlf_line_col_matches_lf() {
+        let lf = Source::new("var a\nprint a\n", "");
+        let crlf = Source::new("var a\r\nprint a\r\n", "");
+
+        assert_eq!(lf.line_col(6), (1, 0));
+        assert_eq!(crlf.line_col(7), (1, 0));
+        assert_eq!(lf.line_col(12), crlf.line_col(13));
+    }
+
+    #[test]
+    fn base_line_offsets_line_col() {
+        let source = Source::with_base_line("var a\nprint a\n", "", 2);
+
+        assert_eq!(source.line_col(0), (2, 0));
+        assert_eq!(source.line_col(8), (3, 2));
+        assert_eq!(Source::new("var a\nprint a\n", "").line_col(8), (1, 2));
+    }
+
+    #[test]
+    fn line_map_is_built_once() {
+        let built = || LINE_MAPS_BUILT.with(|built| built.get());
+        let source = Source::new("var a\r\nprint 猫\n\nend", "");
+        let before = built();
+
+        assert_eq!(source.line_starts(), &[0, 7, 17, 18]);
+
+        for offset in (0..=source.contents.len()).filter(|&i| source.contents.is_char_boundary(i)) {
+            assert_eq!(source.line_col(offset), line_col(&source.contents, offset));
+        }
+
+        let shared = Rc::clone(&source);
+        assert_eq!(shared.line_col(20), (3, 2));
+        assert_eq!(built(), before + 1);
+    }
+
+    #[test]
+    fn leading_bom_is_stripped() {
+        let source = Source::new("\u{FEFF}print 1", "");
+
+        assert_eq!(source.contents, "print 1");
+        assert_eq!(source.line_col(6), (0, 6));
+        assert_eq!(Source::new("print \u{FEFF}", "").contents, "print \u{FEFF}");
+    }
 }
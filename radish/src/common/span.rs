@@ -3,7 +3,7 @@ use std::{
     rc::Rc,
 };
 
-use crate::common::source::Source;
+use crate::common::source::{self, Source};
 
 #[derive(PartialEq, PartialOrd, Eq)]
 pub struct Span {
@@ -58,20 +58,24 @@ impl Span {
         }
     }
 
-    fn lines_newline(string: &str) -> Vec<String> {
-        string.split('\n').map(|l| l.to_string() + "\n").collect()
+    /// The slice of source code this span covers.
+    pub fn text(&self) -> &str {
+        debug_assert!(
+            self.source.contents.is_char_boundary(self.start)
+                && self.source.contents.is_char_boundary(self.end),
+            "span {:?} does not lie on char boundaries",
+            self,
+        );
+
+        &self.source.contents[self.start..self.end]
     }
 
     pub fn lines(string: &str) -> Vec<String> {
-        string.split('\n').map(|l| l.to_string()).collect()
+        source::lines(string).into_iter().map(|l| l.to_string()).collect()
     }
 
     pub fn get_line_index(string: &str, index: usize) -> (usize, usize) {
-        let lines = Span::lines_newline(&string[..index]);
-        let line = lines.len() - 1;
-        let col = lines.last().unwrap().chars().count() - 1;
-
-        (line, col)
+        source::line_col(string, index)
     }
 }
 
@@ -81,9 +85,16 @@ impl Clone for Span {
     }
 }
 
+/// Just the byte range, prefixed by the source's path if it has one, e.g.
+/// `main.rdsh:4..9`. The source's contents are left out so that debug output
+/// of an AST stays readable.
 impl fmt::Debug for Span {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Span({}, {})", self.start, self.end)
+        if !self.source.path.as_os_str().is_empty() {
+            write!(f, "{}:", self.source.path.display())?;
+        }
+
+        write!(f, "{}..{}", self.start, self.end)
     }
 }
 
@@ -116,8 +127,9 @@ impl fmt::Display for Span {
 
         let (end_line, _end_col) = Span::get_line_index(&contents, self.end);
 
-        let readable_start_line = (start_line + 1).to_string();
-        let readable_end_line = (end_line + 1).to_string();
+        let base_line = self.source.base_line;
+        let readable_start_line = (base_line + start_line + 1).to_string();
+        let readable_end_line = (base_line + end_line + 1).to_string();
         let readable_start_col = (start_col + 1).to_string();
         let padding = readable_end_line.len();
 
@@ -151,7 +163,7 @@ impl fmt::Display for Span {
             let mut formatted = vec![];
 
             for (i, line) in lines.iter().enumerate() {
-                let readable_line_no = (start_line + i + 1).to_string();
+                let readable_line_no = (base_line + start_line + i + 1).to_string();
                 let partial_padding = " ".repeat(padding - readable_line_no.len());
                 formatted.push(format!(
                     " {}{} |> {}",
@@ -204,6 +216,35 @@ mod tests {
         )
     }
 
+    #[test]
+    fn debug_is_compact() {
+        let contents = "var x = 1234\n".repeat(100);
+
+        let span = Span::new(Source::new(&contents, "main.rdsh"), 8, 12);
+        assert_eq!(format!("{:?}", span), "main.rdsh:8..12");
+
+        let span = Span::new(Source::new(&contents, ""), 8, 12);
+        assert_eq!(format!("{:?}", span), "8..12");
+        assert_eq!(format!("{:?}", Some(span)), "Some(8..12)");
+    }
+
+    #[test]
+    fn literal_span_text() {
+        let source = Source::new("var x = 1234", "");
+        let span = Span::new(source, 8, 12);
+
+        assert_eq!(span.text(), "1234");
+    }
+
+    #[test]
+    fn combined_span_text() {
+        let source = Source::new("print 1 + 23", "");
+        let lhs = Span::new(Rc::clone(&source), 6, 7);
+        let rhs = Span::new(Rc::clone(&source), 10, 12);
+
+        assert_eq!(Span::combine(&lhs, &rhs).text(), "1 + 23");
+    }
+
     #[test]
     fn combine_span() {
         let source = "Hello, Radish!";
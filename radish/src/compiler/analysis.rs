@@ -7,11 +7,12 @@ use std::fmt;
 
 use crate::compiler::{
     ast::*,
-    error::{SyntaxError, SyntaxErrorKind},
+    error::{unsupported_destructuring, SyntaxError, SyntaxErrorKind},
     scope::{ScopeMap, Symbol, SymbolKind},
     visitor::{Visitor, VisitorResult},
 };
 
+use crate::core::builtins;
 use crate::error::Item;
 
 pub fn resolve_symbols(ast: &mut AST) -> Result<(), SyntaxError> {
@@ -20,12 +21,40 @@ pub fn resolve_symbols(ast: &mut AST) -> Result<(), SyntaxError> {
     analyzer.analyze(ast)
 }
 
+/// Resolve symbols for a VM that has no builtin functions, so that their
+/// names are unresolved like any other undefined variable.
+pub fn resolve_symbols_without_builtins(ast: &mut AST) -> Result<(), SyntaxError> {
+    let mut analyzer = Analyzer::new();
+    analyzer.builtins = false;
+
+    analyzer.analyze(ast)
+}
+
 #[derive(Debug, Clone)]
 pub struct Analyzer {
     /// Chain of enclosing scopes.
     pub scopes: Vec<ScopeMap>,
     /// Keep track of variables that where referenced before assignment
     pub unresolved: HashSet<Ident>,
+    /// Direct calls to functions with the wrong number of arguments. These are
+    /// only reported once the whole program has been seen, since the function
+    /// may turn out to be reassigned.
+    arg_count_mismatches: Vec<ArgCountMismatch>,
+    /// The definitions of functions that are assigned a new value somewhere.
+    reassigned: Vec<Span>,
+    /// Whether the builtin functions are defined.
+    builtins: bool,
+    /// Globals defined outside of the AST being analyzed.
+    outer_globals: HashSet<String>,
+}
+
+/// A call to a known function with the wrong number of arguments.
+#[derive(Debug, Clone)]
+struct ArgCountMismatch {
+    call: Item,
+    definition: Span,
+    expected: usize,
+    got: usize,
 }
 
 impl Analyzer {
@@ -37,10 +66,15 @@ impl Analyzer {
         Analyzer {
             scopes: vec![scope],
             unresolved: HashSet::new(),
+            arg_count_mismatches: Vec::new(),
+            reassigned: Vec::new(),
+            builtins: true,
+            outer_globals: HashSet::new(),
         }
     }
 
     pub fn analyze(&mut self, ast: &mut AST) -> Result<(), SyntaxError> {
+        self.outer_globals.extend(ast.outer_globals.iter().cloned());
         self.foward_declare(ast);
 
         for node in ast.items.iter_mut() {
@@ -64,6 +98,8 @@ impl Analyzer {
             return Err(SyntaxError::new(err_kind));
         }
 
+        self.check_arg_counts()?;
+
         let global_scope = self.scopes.pop().expect("missing global scope");
 
         ast.scope.extend(global_scope);
@@ -211,6 +247,26 @@ impl Analyzer {
         Ok(())
     }
 
+    /// Report the first direct call with the wrong number of arguments to a
+    /// function that is never reassigned. Calls to reassigned functions are left
+    /// to the runtime check.
+    fn check_arg_counts(&self) -> Result<(), SyntaxError> {
+        let mismatch = self
+            .arg_count_mismatches
+            .iter()
+            .find(|mismatch| !self.reassigned.contains(&mismatch.definition));
+
+        match mismatch {
+            Some(mismatch) => Err(SyntaxError::new(SyntaxErrorKind::WrongArgCount {
+                call: mismatch.call.clone(),
+                definition: mismatch.definition.clone(),
+                expected: mismatch.expected,
+                got: mismatch.got,
+            })),
+            None => Ok(()),
+        }
+    }
+
     /// Create an unresolved name error.
     fn unresolved_err(&self, name: &str, span: &Span) -> SyntaxError {
         let err_kind = SyntaxErrorKind::UnresolvedIdent {
@@ -267,6 +323,10 @@ impl Visitor<'_> for Analyzer {
             )?;
         }
 
+        self.visit_lambda(fun)
+    }
+
+    fn visit_lambda(&mut self, fun: &mut FunctionDecl) -> VisitorResult {
         self.enter_scope();
 
         self.param_list(&fun.params)?;
@@ -329,6 +389,22 @@ impl Visitor<'_> for Analyzer {
         Ok(())
     }
 
+    fn visit_for_stmt(&mut self, stmt: &mut ForStmt) -> VisitorResult {
+        self.visit_expr(&mut stmt.iterable)?;
+
+        self.enter_scope();
+
+        self.declare_variable(&stmt.var.name, SymbolKind::Var, &stmt.var.pos)?;
+
+        for node in stmt.body.iter_mut() {
+            self.visit_stmt(node)?;
+        }
+
+        self.exit_scope();
+
+        Ok(())
+    }
+
     fn visit_var_decl(
         &mut self,
         id: &mut Ident,
@@ -341,7 +417,7 @@ impl Visitor<'_> for Analyzer {
         }
 
         // check if this is the definition of a previously unresolved global variable.
-        if self.scopes.len() == 1 && self.unresolved.get(id).is_some() {
+        if self.scopes.len() == 1 && self.unresolved.contains(id) {
             self.unresolved.remove(id);
         }
 
@@ -351,8 +427,22 @@ impl Visitor<'_> for Analyzer {
         Ok(())
     }
 
+    /// Report declarations with a list pattern before the names in them are
+    /// reported as unresolved.
+    fn visit_destructure(
+        &mut self,
+        target: &mut Pattern,
+        _expr: &mut Option<Expr>,
+        _kind: VarKind,
+    ) -> VisitorResult {
+        Err(unsupported_destructuring(target))
+    }
+
     fn visit_ident(&mut self, ident: &mut Ident) -> VisitorResult {
-        if self.resolve_symbol(&ident.name) == None {
+        if self.resolve_symbol(&ident.name).is_none()
+            && !(self.builtins && builtins::is_builtin(&ident.name))
+            && !self.outer_globals.contains(&ident.name)
+        {
             // if its inside the global scope, then its an error.
             if self.scopes.len() == 1 {
                 return Err(self.unresolved_err(&ident.name, &ident.pos));
@@ -370,6 +460,51 @@ impl Visitor<'_> for Analyzer {
         self.resolve_member_expression(obj)
     }
 
+    fn visit_call_expr(
+        &mut self,
+        callee: &mut Expr,
+        args: &mut Vec<Expr>,
+        span: &Span,
+    ) -> VisitorResult {
+        self.visit_expr(callee)?;
+
+        for arg in args.iter_mut() {
+            self.visit_expr(arg)?;
+        }
+
+        // calls to a function by its name can have their argument count
+        // checked now, instead of at runtime.
+        if let Expr::Identifier(id) = callee {
+            if let Some(Symbol(SymbolKind::Fun { arg_count }, definition, _)) =
+                self.resolve_symbol(&id.name)
+            {
+                if arg_count != args.len() {
+                    self.arg_count_mismatches.push(ArgCountMismatch {
+                        call: Item::new(span, &id.name),
+                        definition,
+                        expected: arg_count,
+                        got: args.len(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_assignment(&mut self, stmt: &mut AssignmentStmt) -> VisitorResult {
+        if let Expr::Identifier(id) = &stmt.lhs {
+            if let Some(Symbol(SymbolKind::Fun { .. }, definition, _)) =
+                self.resolve_symbol(&id.name)
+            {
+                self.reassigned.push(definition);
+            }
+        }
+
+        self.visit_expr(&mut stmt.lhs)?;
+        self.visit_expr(&mut stmt.rhs)
+    }
+
     fn visit_import_stmt(&mut self, import_stmt: &mut ImportStatement) -> VisitorResult {
         let module_name = match import_stmt.name() {
             Some(n) => n,
@@ -378,6 +513,10 @@ impl Visitor<'_> for Analyzer {
 
         self.declare_variable(&module_name.name, SymbolKind::Var, &module_name.pos)?;
 
+        for item in import_stmt.items() {
+            self.declare_variable(&item.name, SymbolKind::Var, &item.pos)?;
+        }
+
         Ok(())
     }
 }
@@ -2,10 +2,12 @@
 //! along with all its related data structures.
 
 use crate::common::span::Span;
+use crate::common::Opcode;
 
 use crate::compiler::scope::ScopeMap;
 
 use std::cmp::Ordering;
+use std::fmt;
 
 use super::hoist::{VarScope, Scope};
 use super::SyntaxError;
@@ -17,6 +19,9 @@ pub struct AST {
     pub items: Vec<Stmt>,
     pub scope: ScopeMap,
     pub other_scope: Option<Scope>,
+    /// Globals defined outside of the AST, such as by the earlier lines of a
+    /// REPL session, that its code may use.
+    pub outer_globals: Vec<String>,
 }
 
 impl AST {
@@ -25,6 +30,7 @@ impl AST {
             items,
             scope: ScopeMap::new(),
             other_scope: None,
+            outer_globals: vec![],
         }
     }
 
@@ -62,8 +68,8 @@ impl AST {
         Stmt::ClassDeclaration(class, span)
     }
 
-    pub fn var_decl(id: Ident, expr: Option<Expr>, kind: VarKind, span: Span) -> Stmt {
-        Stmt::VarDeclaration(id, expr, kind, span)
+    pub fn var_decl(target: Pattern, expr: Option<Expr>, kind: VarKind, span: Span) -> Stmt {
+        Stmt::VarDeclaration(target, expr, kind, span)
     }
 
     pub fn assignment(stmt: AssignmentStmt, span: Span) -> Stmt {
@@ -82,12 +88,16 @@ impl AST {
         Stmt::WhileStmt(condition, block, span)
     }
 
+    pub fn for_stmt(var: Ident, iterable: Expr, block: Vec<Stmt>, span: Span) -> Stmt {
+        Stmt::ForStmt(Box::new(ForStmt::new(var, iterable, block)), span)
+    }
+
     pub fn import_stmt(path: String, items: Vec<Ident>, span: Span) -> Stmt {
         Stmt::ImportStmt(ImportStatement::new(path, items, span))
     }
 
-    pub fn break_stmt(span: Span) -> Stmt {
-        Stmt::BreakStmt(span)
+    pub fn break_stmt(value: Option<Expr>, span: Span) -> Stmt {
+        Stmt::BreakStmt(value, span)
     }
 
     pub fn continue_stmt(span: Span) -> Stmt {
@@ -110,6 +120,10 @@ impl AST {
         Expr::MapExpr(values, span)
     }
 
+    pub fn interpolation(parts: Vec<Expr>, span: Span) -> Expr {
+        Expr::Interpolation(parts, span)
+    }
+
     pub fn binary_expr(expr: Box<BinaryExpr>, span: Span) -> Expr {
         Expr::BinaryExpr(expr, span)
     }
@@ -118,18 +132,30 @@ impl AST {
         Expr::ParenExpr(expr, span)
     }
 
-    pub fn unary_expr(op: Op, expr: Box<Expr>, span: Span) -> Expr {
-        Expr::UnaryExpr(op, expr, span)
+    pub fn unary_expr(op: Op, op_span: Span, expr: Box<Expr>, span: Span) -> Expr {
+        Expr::UnaryExpr(op, op_span, expr, span)
     }
 
     pub fn logical_expr(expr: Box<BinaryExpr>, span: Span) -> Expr {
         Expr::LogicalExpr(expr, span)
     }
 
+    pub fn ternary_expr(condition: Box<Expr>, then: Box<Expr>, alt: Box<Expr>, span: Span) -> Expr {
+        Expr::TernaryExpr(condition, then, alt, span)
+    }
+
+    pub fn loop_expr(stmt: Stmt, span: Span) -> Expr {
+        Expr::LoopExpr(Box::new(stmt), span)
+    }
+
     pub fn call_expr(expr: Box<Expr>, args: Vec<Expr>, span: Span) -> Expr {
         Expr::CallExpr(expr, args, span)
     }
 
+    pub fn lambda(fun: FunctionDecl, span: Span) -> Expr {
+        Expr::Lambda(Box::new(fun), span)
+    }
+
     pub fn member_expr(obj: Box<Expr>, prop: Box<Expr>, span: Span) -> Expr {
         Expr::MemberExpr(obj, prop, span)
     }
@@ -142,10 +168,18 @@ impl AST {
         Expr::String(str, span)
     }
 
+    pub fn bytes(bytes: Vec<u8>, span: Span) -> Expr {
+        Expr::Bytes(bytes, span)
+    }
+
     pub fn number(num: f64, span: Span) -> Expr {
         Expr::Number(num, span)
     }
 
+    pub fn integer(num: i64, span: Span) -> Expr {
+        Expr::Integer(num, span)
+    }
+
     pub fn bool(val: bool, span: Span) -> Expr {
         Expr::Bool(val, span)
     }
@@ -156,6 +190,7 @@ impl AST {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Stmt {
     /// A block statement
     /// ```txt
@@ -185,9 +220,9 @@ pub enum Stmt {
     /// A variable declaration. [`VarKind`] determines whether its a
     /// constant delclaration or not.
     /// ```txt
-    /// ('var'|'fin') <ident> ['=' <expr>]
+    /// ('var'|'fin') <pattern> ['=' <expr>]
     /// ```
-    VarDeclaration(Ident, Option<Expr>, VarKind, Span),
+    VarDeclaration(Pattern, Option<Expr>, VarKind, Span),
     /// An assignment statement.
     /// ```txt
     /// <ident> <op>'=' <expr>
@@ -208,17 +243,22 @@ pub enum Stmt {
     /// 'while' <expr> 'loop' stmt... 'endloop'
     /// ```
     WhileStmt(Expr, Vec<Stmt>, Span),
+    /// A `for` loop over the items of an iterable value.
+    /// ```txt
+    /// 'for' <ident> 'in' <expr> 'loop' stmt... 'endloop'
+    /// ```
+    ForStmt(Box<ForStmt>, Span),
     /// A `import` statement
     /// ```txt
     /// 'import' <path> { 'for' ident... }
     /// ```
     ImportStmt(ImportStatement),
     //ImportStmt(String, Vec<Ident>, Span),
-    /// A break statement.
+    /// A break statement, optionally carrying the value of the loop it exits.
     /// ```txt
-    /// break
+    /// break [<expr>]
     /// ```
-    BreakStmt(Span),
+    BreakStmt(Option<Expr>, Span),
     /// A continue statement
     /// ```txt
     /// continue
@@ -237,6 +277,28 @@ pub enum Stmt {
     PrintStmt(Expr, Span),
 }
 
+/// The target of a variable declaration.
+/// ```txt
+/// <ident> | '[' <pattern> {',' <pattern>} ']'
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// A single variable.
+    Name(Ident),
+    /// Variables taken from the items of a list, in order. Only parsed for
+    /// now; compiling one is an error.
+    List(Vec<Pattern>, Span),
+}
+
+impl Pattern {
+    pub fn position(&self) -> Span {
+        match self {
+            Pattern::Name(id) => id.pos.clone(),
+            Pattern::List(_, span) => span.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VarKind {
     Var,
@@ -256,9 +318,10 @@ impl Stmt {
             | Self::IfStmt(_, _, _, pos)
             | Self::LoopStmt(_, pos)
             | Self::WhileStmt(_, _, pos)
+            | Self::ForStmt(_, pos)
             | Self::ReturnStmt(_, pos)
             | Self::ContinueStmt(pos)
-            | Self::BreakStmt(pos) => pos.clone(),
+            | Self::BreakStmt(_, pos) => pos.clone(),
             Self::ExpressionStmt(expr) => expr.position(),
             Self::ImportStmt(stmt) => stmt.pos(),
         }
@@ -273,7 +336,7 @@ impl Stmt {
 
     pub fn as_ident(&mut self) -> (&mut Ident, &mut Option<Expr>, &mut VarKind, &mut Span) {
         match self {
-            Stmt::VarDeclaration(id, expr, kind, span) => (id, expr, kind, span),
+            Stmt::VarDeclaration(Pattern::Name(id), expr, kind, span) => (id, expr, kind, span),
             _ => panic!("not an identifier"),
         }
     }
@@ -294,12 +357,34 @@ impl AssignmentStmt {
     }
 }
 
+/// A `for` loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForStmt {
+    /// The variable each item is bound to.
+    pub var: Ident,
+    /// The value being iterated over.
+    pub iterable: Expr,
+    pub body: Vec<Stmt>,
+}
+
+impl ForStmt {
+    pub fn new(var: Ident, iterable: Expr, body: Vec<Stmt>) -> Self {
+        Self { var, iterable, body }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     /// An array literal.
     ArrayExpr(Vec<Expr>, Span),
     /// A map literal.
     MapExpr(Vec<Expr>, Span),
+    /// A string with embedded expressions, as the pieces of its text and the
+    /// expressions in the order they appear.
+    /// ```txt
+    /// '"' text '{' <expr> '}' text... '"'
+    /// ```
+    Interpolation(Vec<Expr>, Span),
     /// A binary expression
     /// ```txt
     /// <expr> <op> <expr>
@@ -314,12 +399,29 @@ pub enum Expr {
     /// ```txt
     /// <op> <expr>
     /// ```
-    UnaryExpr(Op, Box<Expr>, Span),
+    /// The first span is the operator token's, the last the whole expression's.
+    UnaryExpr(Op, Span, Box<Expr>, Span),
     /// A logical expression
     /// ```txt
     /// <expr> 'and'|'or' <expr>
     /// ```
     LogicalExpr(Box<BinaryExpr>, Span),
+    /// A conditional expression
+    /// ```txt
+    /// <expr> '?' <expr> ':' <expr>
+    /// ```
+    TernaryExpr(Box<Expr>, Box<Expr>, Box<Expr>, Span),
+    /// A `loop` or `while` loop used as an expression, evaluating to the
+    /// value given to `break`, or `nil`.
+    /// ```txt
+    /// 'loop' stmt... 'endloop'
+    /// ```
+    LoopExpr(Box<Stmt>, Span),
+    /// An anonymous function.
+    /// ```txt
+    /// 'fun' '(' <params> ')' '{' <body> '}'
+    /// ```
+    Lambda(Box<FunctionDecl>, Span),
     /// A call expression
     /// ```txt
     /// <callee> '(' <args> ')'
@@ -332,8 +434,11 @@ pub enum Expr {
     MemberExpr(Box<Expr>, Box<Expr>, Span),
     /// An identifier
     Identifier(Ident),
-    /// A number literal
+    /// A floating point number literal, written with a `.` or an exponent.
     Number(f64, Span),
+    /// An integer literal, written as a run of digits (or with a `0b`, `0o`,
+    /// or `0x` prefix).
+    Integer(i64, Span),
     /// A boolean literal
     /// ```txt
     /// true | false
@@ -341,6 +446,11 @@ pub enum Expr {
     Bool(bool, Span),
     /// A string literal
     String(String, Span),
+    /// A byte string literal
+    /// ```txt
+    /// b"\x00bytes"
+    /// ```
+    Bytes(Vec<u8>, Span),
     /// `nil` literal.
     Nil(Span),
 }
@@ -349,16 +459,22 @@ impl Expr {
     pub fn position(&self) -> Span {
         match self {
             Self::ArrayExpr(_, pos)
+            | Self::Interpolation(_, pos)
             | Self::MapExpr(_, pos)
             | Self::BinaryExpr(_, pos)
             | Self::ParenExpr(_, pos)
-            | Self::UnaryExpr(_, _, pos)
+            | Self::UnaryExpr(_, _, _, pos)
             | Self::LogicalExpr(_, pos)
+            | Self::TernaryExpr(_, _, _, pos)
+            | Self::LoopExpr(_, pos)
+            | Self::Lambda(_, pos)
             | Self::CallExpr(_, _, pos)
             | Self::MemberExpr(_, _, pos)
             | Self::Number(_, pos)
+            | Self::Integer(_, pos)
             | Self::Bool(_, pos)
             | Self::String(_, pos)
+            | Self::Bytes(_, pos)
             | Self::Nil(pos) => pos.clone(),
             Self::Identifier(id) => id.pos.clone(),
         }
@@ -372,13 +488,20 @@ pub struct BinaryExpr {
     pub lhs: Expr,
     /// Expression's operand.
     pub op: Op,
+    /// The location of the operator itself.
+    pub op_span: Span,
     /// Right hand side of the expression.
     pub rhs: Expr,
 }
 
 impl BinaryExpr {
-    pub fn new(op: Op, l: Expr, r: Expr) -> BinaryExpr {
-        BinaryExpr { op, lhs: l, rhs: r }
+    pub fn new(op: Op, op_span: Span, l: Expr, r: Expr) -> BinaryExpr {
+        BinaryExpr {
+            op,
+            op_span,
+            lhs: l,
+            rhs: r,
+        }
     }
 }
 
@@ -460,7 +583,8 @@ impl ImportStatement {
 
         let path = Path::new(&self.path);
 
-        let file_path = path.file_name();
+        // `import "lib/utils.rdsh"` binds the module to `utils`.
+        let file_path = path.file_stem();
 
         file_path.map(|p| p.to_str().unwrap()).map(|name| {
             let span = self.pos.clone();
@@ -468,6 +592,11 @@ impl ImportStatement {
         })
     }
 
+    /// The names brought into the importing scope with `for`.
+    pub fn items(&self) -> &[Ident] {
+        &self.items
+    }
+
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -522,7 +651,6 @@ impl std::hash::Hash for Ident {
         H: std::hash::Hasher,
     {
         self.name.hash(state);
-        state.finish();
     }
 }
 
@@ -545,6 +673,74 @@ pub enum Op {
     Or,
 }
 
+impl Op {
+    /// The instruction that the compiler emits for the operator. `-` used as
+    /// a unary operator is the exception, it negates with `Opcode::Neg`.
+    /// The logical operators are the jumps that short-circuit them.
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Op::Add => Opcode::Add,
+            Op::Subtract => Opcode::Sub,
+            Op::Multiply => Opcode::Mul,
+            Op::Divide => Opcode::Div,
+            Op::Remainder => Opcode::Rem,
+            Op::Bang => Opcode::Not,
+            Op::LessThan => Opcode::CmpLT,
+            Op::LessThanEquals => Opcode::CmpLTEq,
+            Op::GreaterThan => Opcode::CmpGT,
+            Op::GreaterThanEquals => Opcode::CmpGTEq,
+            Op::EqualsTo => Opcode::CmpEq,
+            Op::NotEqual => Opcode::CmpNotEq,
+            Op::And => Opcode::JumpIfFalse,
+            Op::Or => Opcode::JumpIfTrue,
+        }
+    }
+
+    /// The operator that [`opcode()`](Op::opcode) maps to `opcode`, if any.
+    pub fn from_opcode(opcode: &Opcode) -> Option<Op> {
+        Some(match opcode {
+            Opcode::Add => Op::Add,
+            Opcode::Sub => Op::Subtract,
+            Opcode::Mul => Op::Multiply,
+            Opcode::Div => Op::Divide,
+            Opcode::Rem => Op::Remainder,
+            Opcode::Not => Op::Bang,
+            Opcode::CmpLT => Op::LessThan,
+            Opcode::CmpLTEq => Op::LessThanEquals,
+            Opcode::CmpGT => Op::GreaterThan,
+            Opcode::CmpGTEq => Op::GreaterThanEquals,
+            Opcode::CmpEq => Op::EqualsTo,
+            Opcode::CmpNotEq => Op::NotEqual,
+            Opcode::JumpIfFalse => Op::And,
+            Opcode::JumpIfTrue => Op::Or,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let syntax = match self {
+            Op::Add => "+",
+            Op::Subtract => "-",
+            Op::Multiply => "*",
+            Op::Divide => "/",
+            Op::Remainder => "%",
+            Op::Bang => "!",
+            Op::LessThan => "<",
+            Op::LessThanEquals => "<=",
+            Op::GreaterThan => ">",
+            Op::GreaterThanEquals => ">=",
+            Op::EqualsTo => "==",
+            Op::NotEqual => "!=",
+            Op::And => "and",
+            Op::Or => "or",
+        };
+
+        f.write_str(syntax)
+    }
+}
+
 /// An assignment operand.
 #[derive(Debug, Clone, PartialEq)]
 pub enum OpAssignment {
@@ -555,3 +751,186 @@ pub enum OpAssignment {
     DivAssign,
     RemAssign,
 }
+
+/// Comparison of syntax trees by their shape and literal values alone. Unlike
+/// `PartialEq`, [`Span`]s and the results of later passes, such as scopes,
+/// are ignored, so trees parsed from differently formatted code can be equal.
+pub trait StructurallyEq {
+    fn structurally_eq(&self, other: &Self) -> bool;
+}
+
+impl<T: StructurallyEq> StructurallyEq for Box<T> {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        (**self).structurally_eq(other)
+    }
+}
+
+impl<T: StructurallyEq> StructurallyEq for Option<T> {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.structurally_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: StructurallyEq> StructurallyEq for Vec<T> {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.structurally_eq(b))
+    }
+}
+
+impl StructurallyEq for AST {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.items.structurally_eq(&other.items)
+    }
+}
+
+impl StructurallyEq for Stmt {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        use Stmt::*;
+
+        match (self, other) {
+            (BlockStmt(a, _), BlockStmt(b, _)) | (LoopStmt(a, _), LoopStmt(b, _)) => {
+                a.structurally_eq(b)
+            }
+            (ExpressionStmt(a), ExpressionStmt(b)) => a.structurally_eq(b),
+            (FunDeclaration(a, _), FunDeclaration(b, _)) => a.structurally_eq(b),
+            (ClassDeclaration(a, _), ClassDeclaration(b, _)) => a.structurally_eq(b),
+            (ConDeclaration(a, _), ConDeclaration(b, _)) => a.structurally_eq(b),
+            (VarDeclaration(a_target, a, a_kind, _), VarDeclaration(b_target, b, b_kind, _)) => {
+                a_target.structurally_eq(b_target) && a_kind == b_kind && a.structurally_eq(b)
+            }
+            (AssignmentStmt(a, _), AssignmentStmt(b, _)) => {
+                a.op == b.op && a.lhs.structurally_eq(&b.lhs) && a.rhs.structurally_eq(&b.rhs)
+            }
+            (IfStmt(a_cond, a, a_alt, _), IfStmt(b_cond, b, b_alt, _)) => {
+                a_cond.structurally_eq(b_cond) && a.structurally_eq(b) && a_alt.structurally_eq(b_alt)
+            }
+            (WhileStmt(a_cond, a, _), WhileStmt(b_cond, b, _)) => {
+                a_cond.structurally_eq(b_cond) && a.structurally_eq(b)
+            }
+            (ForStmt(a, _), ForStmt(b, _)) => {
+                a.var == b.var
+                    && a.iterable.structurally_eq(&b.iterable)
+                    && a.body.structurally_eq(&b.body)
+            }
+            (ImportStmt(a), ImportStmt(b)) => a.path == b.path && a.items == b.items,
+            (BreakStmt(a, _), BreakStmt(b, _))
+            | (ReturnStmt(a, _), ReturnStmt(b, _)) => a.structurally_eq(b),
+            (ContinueStmt(_), ContinueStmt(_)) => true,
+            (PrintStmt(a, _), PrintStmt(b, _)) => a.structurally_eq(b),
+            _ => false,
+        }
+    }
+}
+
+impl StructurallyEq for Expr {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        use Expr::*;
+
+        match (self, other) {
+            (ArrayExpr(a, _), ArrayExpr(b, _))
+            | (MapExpr(a, _), MapExpr(b, _))
+            | (Interpolation(a, _), Interpolation(b, _)) => a.structurally_eq(b),
+            (BinaryExpr(a, _), BinaryExpr(b, _)) | (LogicalExpr(a, _), LogicalExpr(b, _)) => {
+                a.structurally_eq(b)
+            }
+            (ParenExpr(a, _), ParenExpr(b, _)) => a.structurally_eq(b),
+            (UnaryExpr(a_op, _, a, _), UnaryExpr(b_op, _, b, _)) => {
+                a_op == b_op && a.structurally_eq(b)
+            }
+            (TernaryExpr(a_cond, a_then, a_alt, _), TernaryExpr(b_cond, b_then, b_alt, _)) => {
+                a_cond.structurally_eq(b_cond)
+                    && a_then.structurally_eq(b_then)
+                    && a_alt.structurally_eq(b_alt)
+            }
+            (LoopExpr(a, _), LoopExpr(b, _)) => a.structurally_eq(b),
+            (Lambda(a, _), Lambda(b, _)) => a.structurally_eq(b),
+            (CallExpr(a_callee, a, _), CallExpr(b_callee, b, _)) => {
+                a_callee.structurally_eq(b_callee) && a.structurally_eq(b)
+            }
+            (MemberExpr(a_obj, a, _), MemberExpr(b_obj, b, _)) => {
+                a_obj.structurally_eq(b_obj) && a.structurally_eq(b)
+            }
+            (Identifier(a), Identifier(b)) => a == b,
+            (Number(a, _), Number(b, _)) => a == b,
+            (Integer(a, _), Integer(b, _)) => a == b,
+            (Bool(a, _), Bool(b, _)) => a == b,
+            (String(a, _), String(b, _)) => a == b,
+            (Bytes(a, _), Bytes(b, _)) => a == b,
+            (Nil(_), Nil(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl StructurallyEq for BinaryExpr {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.op == other.op && self.lhs.structurally_eq(&other.lhs) && self.rhs.structurally_eq(&other.rhs)
+    }
+}
+
+impl StructurallyEq for FunctionDecl {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.params == other.params && self.body.structurally_eq(&other.body)
+    }
+}
+
+impl StructurallyEq for Pattern {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Pattern::Name(a), Pattern::Name(b)) => a == b,
+            (Pattern::List(a, _), Pattern::List(b, _)) => a.structurally_eq(b),
+            _ => false,
+        }
+    }
+}
+
+impl StructurallyEq for ClassDecl {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.constructors.structurally_eq(&other.constructors)
+    }
+}
+
+impl StructurallyEq for ConstructorDecl {
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.params == other.params && self.body.structurally_eq(&other.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn op_opcodes_round_trip() {
+        let ops = [
+            Op::Add,
+            Op::Subtract,
+            Op::Multiply,
+            Op::Divide,
+            Op::Remainder,
+            Op::Bang,
+            Op::LessThan,
+            Op::LessThanEquals,
+            Op::GreaterThan,
+            Op::GreaterThanEquals,
+            Op::EqualsTo,
+            Op::NotEqual,
+            Op::And,
+            Op::Or,
+        ];
+
+        let opcodes: std::collections::HashSet<u8> = ops.iter().map(|op| op.opcode() as u8).collect();
+        assert_eq!(opcodes.len(), ops.len());
+
+        for op in ops {
+            assert_eq!(Op::from_opcode(&op.opcode()), Some(op));
+        }
+
+        assert_eq!(Op::from_opcode(&Opcode::Neg), None);
+        assert_eq!(Op::from_opcode(&Opcode::Print), None);
+    }
+}
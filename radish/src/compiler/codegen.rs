@@ -1,13 +1,20 @@
 use crate::common::{Chunk, CompiledModule, Disassembler, Module, Opcode, Span};
 
 use crate::vm::value::Function as FunctionValue;
+use crate::core::builtins;
 
 use crate::Value;
 
-use crate::compiler::{ast::*, Rc, SyntaxError};
+use crate::compiler::{
+    ast::*,
+    error::{unsupported_destructuring, SyntaxErrorKind},
+    Rc, SyntaxError,
+};
 
 use super::hoist::VarScope;
 use super::pipeline::PipelineSettings;
+use crate::config::DEFAULT_MAX_CONSTANTS;
+use crate::error::Item;
 
 /// Track the state of a loop.
 struct Loop {
@@ -16,6 +23,9 @@ struct Loop {
     /// Placeholders emitted by `break` statements within the loop,
     /// back patched once the loop is exited.
     jump_placeholders: Vec<usize>,
+    /// The stack slots of the variables declared in the loop's body that are
+    /// in scope, which `break` and `continue` have to remove.
+    locals: Vec<u32>,
 }
 
 impl Loop {
@@ -23,6 +33,7 @@ impl Loop {
         Loop {
             loop_start,
             jump_placeholders: vec![],
+            locals: vec![],
         }
     }
 }
@@ -70,12 +81,22 @@ impl Frame {
 
 struct CompilerSettings {
     pub dump_bytecode: bool,
+    /// Return the value of a trailing expression statement from the script
+    /// so that the REPL can echo it.
+    pub repl: bool,
+    /// Leave the builtin functions out of new modules.
+    pub no_builtins: bool,
+    /// The most constants a single chunk may hold.
+    pub max_constants: usize,
 }
 
 impl CompilerSettings {
     pub fn new() -> Self {
         Self {
             dump_bytecode: false,
+            repl: false,
+            no_builtins: false,
+            max_constants: DEFAULT_MAX_CONSTANTS,
         }
     }
 }
@@ -90,6 +111,9 @@ impl From<&PipelineSettings> for CompilerSettings {
     fn from(pipeline: &PipelineSettings) -> Self {
         Self {
             dump_bytecode: pipeline.dump_bytecode,
+            repl: pipeline.repl,
+            no_builtins: pipeline.no_builtins,
+            max_constants: pipeline.max_constants,
         }
     }
 }
@@ -101,20 +125,67 @@ pub struct Compiler {
     frame: Vec<Frame>,
     loops: Vec<Loop>,
     module: CompiledModule,
+    /// The span of the node being compiled, recorded for each emitted byte.
+    span: Span,
 }
 
 impl Compiler {
     pub fn new(config: &PipelineSettings) -> Self {
         let config = CompilerSettings::from(config);
 
+        let module = Compiler::new_module(&config);
+
         Self {
             config,
             scope_depth: 0,
             loops: vec![],
             frame_count: 0,
             frame: vec![],
-            module: Module::empty(),
+            module,
+            span: Span::empty(),
+        }
+    }
+
+    /// Compile an AST into a module of its own. Unlike [`compile()`](Self::compile),
+    /// the globals of previously compiled code are not visible to it.
+    pub fn compile_module(
+        &mut self,
+        file_name: &str,
+        ast: &AST,
+    ) -> Result<CompiledModule, SyntaxError> {
+        let previous = std::mem::replace(&mut self.module, Compiler::new_module(&self.config));
+
+        let module = self.compile(file_name, ast);
+
+        self.module = previous;
+
+        module
+    }
+
+    /// The names of the globals defined by previously compiled code.
+    pub fn global_names(&self) -> Vec<String> {
+        self.module
+            .borrow()
+            .globals()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Forget the globals of previously compiled code.
+    pub fn reset(&mut self) {
+        self.module = Compiler::new_module(&self.config);
+    }
+
+    /// Create an empty module holding only the builtin functions, unless
+    /// they're disabled.
+    fn new_module(config: &CompilerSettings) -> CompiledModule {
+        let module = Module::empty();
+
+        if !config.no_builtins {
+            builtins::define(&mut module.borrow_mut());
         }
+
+        module
     }
 
     // TODO: mutable borrow the ast.
@@ -134,16 +205,48 @@ impl Compiler {
 
         self.declare_globals(ast)?;
 
-        for node in &ast.items {
-            match self.statement(node) {
-                Ok(_) => continue,
-                Err(_) => continue,
+        let echo = match ast.items.last() {
+            Some(Stmt::ExpressionStmt(expr)) if self.config.repl => Some(expr),
+            _ => None,
+        };
+
+        let body = match echo {
+            Some(_) => &ast.items[..ast.items.len() - 1],
+            None => &ast.items[..],
+        };
+
+        let mut error = None;
+
+        for node in body {
+            if let Err(err) = self.statement(node) {
+                error.get_or_insert(err);
+            }
+        }
+
+        if let Some(expr) = echo {
+            match self.expression(expr) {
+                Ok(_) => self.emit_byte(Opcode::Return as u8),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
             }
         }
 
+        // an error can leave the compiler partway into a function, so its
+        // state is thrown away along with the code.
+        if let Some(err) = error {
+            self.frame.clear();
+            self.frame_count = 0;
+            self.scope_depth = 0;
+            self.loops.clear();
+
+            return Err(err);
+        }
+
         self.emit_return();
 
-        let script = self.frame.pop().unwrap().function;
+        let mut script = self.frame.pop().unwrap().function;
+        script.chunk.max_stack = script.chunk.stack_depth(0);
 
         if self.config.dump_bytecode {
             Disassembler::disassemble_chunk(&script.name, &script);
@@ -156,9 +259,17 @@ impl Compiler {
 
     /// Write a unsigned byte to the current `[Chunk]` being compiled.
     fn emit_byte(&mut self, byte: u8) {
-        // should also emit the byte's location in source?
         let chunk = &mut self.frame[self.frame_count].function.chunk;
-        chunk.code.push(byte);
+        chunk.write(byte, &self.span);
+    }
+
+    /// Write an opcode recorded with `span` rather than the span of the node
+    /// being compiled, so that a runtime error from an operator points at the
+    /// operator itself.
+    fn emit_operator(&mut self, opcode: Opcode, span: &Span) {
+        let outer = std::mem::replace(&mut self.span, Span::from(span));
+        self.emit_byte(opcode as u8);
+        self.span = outer;
     }
 
     /// Write two unsigned bytes to the current `[Chunk]` being compiled.
@@ -224,15 +335,26 @@ impl Compiler {
     }
 
     /// add a constant to the chunk's constant array. Returns the
-    /// constant's index in the constant array as a u32.
-    fn make_constant(&mut self, value: Value) -> u32 {
+    /// constant's index in the constant array as a u32, or an error if the
+    /// chunk already holds as many constants as it's allowed.
+    fn make_constant(&mut self, value: Value) -> Result<u32, SyntaxError> {
+        let max = self.config.max_constants.min(DEFAULT_MAX_CONSTANTS);
         let chunk = &mut self.frame[self.frame_count].function.chunk;
-        chunk.add_constant(value) as u32
+
+        if chunk.constants.len() >= max {
+            return Err(SyntaxError::new(SyntaxErrorKind::TooManyConstants {
+                item: Item::new(&self.span, self.span.text()),
+                max,
+            }));
+        }
+
+        Ok(chunk.add_constant(value) as u32)
     }
 
-    /// Write a `[Value]` to the current chunk begin compiled.
-    fn emit_constant(&mut self, value: Value) {
-        let index = self.make_constant(value);
+    /// Write a `[Value]` to the current chunk begin compiled. Constants past
+    /// the first 256 are loaded with `LoadConstLong`, whose operand is wider.
+    fn emit_constant(&mut self, value: Value) -> Result<(), SyntaxError> {
+        let index = self.make_constant(value)?;
 
         if index > 255 {
             self.emit_byte(Opcode::LoadConstLong as u8);
@@ -245,6 +367,8 @@ impl Compiler {
         } else {
             self.emit_bytes(Opcode::LoadConst as u8, index as u8);
         }
+
+        Ok(())
     }
 
     /// Define a variable.
@@ -376,13 +500,22 @@ impl Compiler {
 
         self.emit_return();
         self.frame_count -= 1;
-        self.frame.pop().unwrap()
+
+        let mut frame = self.frame.pop().unwrap();
+        let chunk = &mut frame.function.chunk;
+        chunk.max_stack = chunk.stack_depth(frame.function.arity as usize);
+
+        frame
     }
 
     /// Sort of foward declare all globally scoped functions.
     fn declare_globals(&mut self, ast: &AST) -> Result<(), SyntaxError> {
-        // add all global declarations to the module with a value of nil.
-        for (name, _) in ast.scope.locals.iter() {
+        // add all global declarations to the module with a value of nil, in
+        // the order they appear in the source.
+        let mut globals: Vec<_> = ast.scope.locals.iter().collect();
+        globals.sort_by_key(|(_, symbol)| symbol.1.start);
+
+        for (name, _) in globals {
             self.module.borrow_mut().add_symbol(name.clone());
         }
 
@@ -406,9 +539,13 @@ impl Compiler {
                     self.define_global(index as u32);
                 }
                 Stmt::ImportStmt(import) => {
-                    self.module
-                        .borrow_mut()
-                        .add_symbol(import.name().unwrap().name.clone());
+                    let mut module = self.module.borrow_mut();
+
+                    module.add_symbol(import.name().unwrap().name.clone());
+
+                    for item in import.items() {
+                        module.add_symbol(item.name.clone());
+                    }
                 }
                 _ => continue,
             }
@@ -435,7 +572,7 @@ impl Compiler {
         self.enter_function(Frame::function(frame));
         {
             for param in &fun.params {
-                self.define_variable(&param);
+                self.define_variable(param);
             }
 
             for stmt in fun.body.iter() {
@@ -449,7 +586,7 @@ impl Compiler {
             Disassembler::disassemble_chunk(&frame.function.name, &frame.function);
         }
 
-        self.emit_constant(Value::from(frame.function));
+        self.emit_constant(Value::from(frame.function))?;
         self.emit_byte(Opcode::Closure as u8);
 
         let scope = fun.other_scope.clone().unwrap();
@@ -491,7 +628,7 @@ impl Compiler {
         // I'm gonna go with option (b) for now, but could try (a)
 
         // emit the class's name
-        self.emit_constant(Value::from(&class.id.name));
+        self.emit_constant(Value::from(&class.id.name))?;
 
         // self.emit_constant(Value::from(class));
         // emit build instruction
@@ -515,23 +652,31 @@ impl Compiler {
 
 impl Compiler {
     fn statement(&mut self, stmt: &Stmt) -> Result<(), SyntaxError> {
-        match stmt {
+        let outer = std::mem::replace(&mut self.span, stmt.position());
+
+        let result = match stmt {
             Stmt::BlockStmt(body, _) => self.block(body),
             Stmt::ExpressionStmt(expr) => self.expression_stmt(expr),
             Stmt::FunDeclaration(fun, _) => self.function_declaration(fun),
             Stmt::ConDeclaration(con, _) => self.constructor_declaration(con),
             Stmt::ClassDeclaration(class, _) => self.class_declaration(class),
-            Stmt::VarDeclaration(id, init, kind, _) => self.var_declaration(id, init, kind),
+            Stmt::VarDeclaration(Pattern::Name(id), init, kind, _) => {
+                self.var_declaration(id, init, kind)
+            }
+            Stmt::VarDeclaration(target, ..) => Err(unsupported_destructuring(target)),
             Stmt::AssignmentStmt(stmt, _) => self.assignment(stmt),
             Stmt::IfStmt(expr, body, alt, _) => self.if_statement(expr, body, alt),
-            Stmt::LoopStmt(body, _) => self.loop_statement(body),
-            Stmt::WhileStmt(expr, body, _) => self.while_statement(expr, body),
+            Stmt::LoopStmt(..) | Stmt::WhileStmt(..) => self.loop_statement(stmt),
+            Stmt::ForStmt(stmt, _) => self.for_loop(stmt),
             Stmt::ImportStmt(stmt) => self.import_statement(stmt),
             Stmt::ReturnStmt(value, _) => self.return_statement(value),
-            Stmt::BreakStmt(pos) => self.break_statement(pos),
+            Stmt::BreakStmt(value, _) => self.break_statement(value),
             Stmt::ContinueStmt(pos) => self.continue_statement(pos),
             Stmt::PrintStmt(expr, _) => self.print(expr),
-        }
+        };
+
+        self.span = outer;
+        result
     }
 
     fn function_declaration(&mut self, fun: &FunctionDecl) -> Result<(), SyntaxError> {
@@ -574,7 +719,7 @@ impl Compiler {
         self.enter_function(Frame::constructor(frame));
         {
             for param in &con.params {
-                self.define_variable(&param);
+                self.define_variable(param);
             }
 
             for stmt in con.body.iter() {
@@ -589,7 +734,7 @@ impl Compiler {
         }
 
         // emit the constructor's body
-        self.emit_constant(Value::from(frame.function));
+        self.emit_constant(Value::from(frame.function))?;
 
         // create a closure from the constructor's body
         self.emit_byte(Opcode::Closure as u8);
@@ -628,7 +773,24 @@ impl Compiler {
         Ok(())
     }
 
-    fn loop_statement(&mut self, body: &[Stmt]) -> Result<(), SyntaxError> {
+    /// Compile a loop used as a statement, discarding the loop's value.
+    fn loop_statement(&mut self, stmt: &Stmt) -> Result<(), SyntaxError> {
+        self.loop_expr(stmt)?;
+        self.emit_byte(Opcode::Del as u8);
+
+        Ok(())
+    }
+
+    /// Compile a loop, leaving the value passed to `break` (or `nil`) on the stack.
+    fn loop_expr(&mut self, stmt: &Stmt) -> Result<(), SyntaxError> {
+        match stmt {
+            Stmt::LoopStmt(body, _) => self.loop_body(body),
+            Stmt::WhileStmt(condition, body, _) => self.while_loop(condition, body),
+            _ => unreachable!("{:?} is not a loop.", stmt),
+        }
+    }
+
+    fn loop_body(&mut self, body: &[Stmt]) -> Result<(), SyntaxError> {
         let loop_start = self.last_byte();
         self.enter_loop(loop_start);
 
@@ -640,7 +802,7 @@ impl Compiler {
         Ok(())
     }
 
-    fn while_statement(&mut self, expr: &Expr, body: &[Stmt]) -> Result<(), SyntaxError> {
+    fn while_loop(&mut self, expr: &Expr, body: &[Stmt]) -> Result<(), SyntaxError> {
         let loop_start = self.last_byte();
         self.enter_loop(loop_start);
 
@@ -652,9 +814,51 @@ impl Compiler {
 
         self.emit_loop(loop_start);
 
+        // the loop finished without a `break`, so its value is `nil`.
         self.patch_jump(exit_jump);
+        self.emit_byte(Opcode::Del as u8);
+        self.emit_byte(Opcode::Nil as u8);
+
         self.leave_loop();
+
+        Ok(())
+    }
+
+    /// Compile a `for` loop. The iterable and the position of its next item are
+    /// kept in the two local slots below the loop variable.
+    fn for_loop(&mut self, stmt: &ForStmt) -> Result<(), SyntaxError> {
+        self.enter_scope();
+
+        self.expression(&stmt.iterable)?;
+        self.number(&0.0)?;
+        self.emit_byte(Opcode::Nil as u8);
+        self.define_variable(&stmt.var);
+
+        let loop_start = self.last_byte();
+        self.enter_loop(loop_start);
+
+        self.emit_byte(Opcode::Iterate as u8);
+        for byte in (stmt.var.index - 2).to_le_bytes() {
+            self.emit_byte(byte);
+        }
+
+        let exit_jump = self.emit_jump(Opcode::JumpIfFalse);
         self.emit_byte(Opcode::Del as u8);
+        self.save_variable(&stmt.var);
+
+        self.block(&stmt.body)?;
+
+        self.emit_loop(loop_start);
+
+        self.patch_jump(exit_jump);
+        self.leave_loop();
+
+        // remove the loop's value, the loop variable, the position and the iterable.
+        for _ in 0..4 {
+            self.emit_byte(Opcode::Del as u8);
+        }
+
+        self.leave_scope();
 
         Ok(())
     }
@@ -662,21 +866,31 @@ impl Compiler {
     fn block(&mut self, body: &[Stmt]) -> Result<(), SyntaxError> {
         self.enter_scope();
 
+        let mut declared = 0;
+
         for node in body {
             self.statement(node)?;
-        }
 
-        self.leave_scope();
+            if let Stmt::VarDeclaration(pattern, _, _, _) = node {
+                declared += 1;
 
-        for stmt in body {
-            match stmt {
-                Stmt::VarDeclaration(_, _, _, _) => {
-                    self.emit_byte(Opcode::Del as u8);
+                if let (Some(current), Pattern::Name(id)) = (self.loops.last_mut(), pattern) {
+                    current.locals.push(id.index);
                 }
-                _ => continue,
             }
         }
 
+        self.leave_scope();
+
+        if let Some(current) = self.loops.last_mut() {
+            let remaining = current.locals.len().saturating_sub(declared);
+            current.locals.truncate(remaining);
+        }
+
+        for _ in 0..declared {
+            self.emit_byte(Opcode::Del as u8);
+        }
+
         Ok(())
     }
 
@@ -685,7 +899,16 @@ impl Compiler {
 
         self.emit_byte(Opcode::Import as u8);
 
-        self.define_variable(&import_stmt.name().unwrap());
+        let module = import_stmt.name().unwrap();
+        self.define_variable(&module);
+
+        // copy each of the requested items out of the module.
+        for item in import_stmt.items() {
+            self.string(&item.name)?;
+            self.load_variable(&module);
+            self.emit_byte(Opcode::LoadField as u8);
+            self.define_variable(item);
+        }
 
         Ok(())
     }
@@ -706,9 +929,27 @@ impl Compiler {
         Ok(())
     }
 
-    fn break_statement(&mut self, _: &Span) -> Result<(), SyntaxError> {
+    fn break_statement(&mut self, value: &Option<Expr>) -> Result<(), SyntaxError> {
+        match value {
+            Some(expr) => self.expression(expr)?,
+            None => self.emit_byte(Opcode::Nil as u8),
+        }
+
+        // move the value into the slot of the loop's first local, then remove
+        // the locals above it.
+        let locals = self.loops.last().unwrap().locals.clone();
+        if let Some(first) = locals.first() {
+            self.emit_byte(Opcode::SaveLocal as u8);
+            for byte in first.to_le_bytes() {
+                self.emit_byte(byte);
+            }
+
+            for _ in 0..locals.len() {
+                self.emit_byte(Opcode::Del as u8);
+            }
+        }
+
         let exit_jump = self.emit_jump(Opcode::Jump);
-        self.emit_byte(Opcode::Del as u8);
 
         let index = &self.loops.len() - 1;
         self.loops[index].jump_placeholders.push(exit_jump);
@@ -717,7 +958,12 @@ impl Compiler {
     }
 
     fn continue_statement(&mut self, _: &Span) -> Result<(), SyntaxError> {
-        let loop_start = self.loops.last().unwrap().loop_start;
+        let current = self.loops.last().unwrap();
+        let (loop_start, locals) = (current.loop_start, current.locals.len());
+
+        for _ in 0..locals {
+            self.emit_byte(Opcode::Del as u8);
+        }
 
         self.emit_loop(loop_start);
 
@@ -776,7 +1022,7 @@ impl Compiler {
         match &stmt.lhs {
             Expr::Identifier(id) => {
                 if let Some(op) = op {
-                    self.load_variable(&id);
+                    self.load_variable(id);
                     self.expression(&stmt.rhs)?;
                     self.emit_byte(op as u8);
                 } else {
@@ -817,21 +1063,32 @@ impl Compiler {
     }
 
     fn expression(&mut self, expr: &Expr) -> Result<(), SyntaxError> {
-        match expr {
+        let outer = std::mem::replace(&mut self.span, expr.position());
+
+        let result = match expr {
             Expr::ArrayExpr(array, _) => self.array(array),
+            Expr::Interpolation(parts, _) => self.interpolation(parts),
             Expr::MapExpr(values, _) => self.map(values),
             Expr::BinaryExpr(expr, _) => self.binary_expression(expr),
             Expr::ParenExpr(expr, _) => self.expression(expr),
-            Expr::UnaryExpr(op, arg, _) => self.unary(arg, op),
+            Expr::UnaryExpr(op, op_span, arg, _) => self.unary(arg, op, op_span),
             Expr::LogicalExpr(expr, _) => self.logical_expr(expr),
+            Expr::LoopExpr(stmt, _) => self.loop_expr(stmt),
+            Expr::Lambda(fun, _) => self.function(fun),
+            Expr::TernaryExpr(condition, then, alt, _) => self.ternary_expr(condition, then, alt),
             Expr::CallExpr(callee, args, _) => self.call_expr(callee, args),
             Expr::MemberExpr(obj, prop, _) => self.member_expr(obj, prop),
             Expr::Identifier(id) => self.identifier(id),
             Expr::Number(val, _) => self.number(val),
+            Expr::Integer(val, _) => self.integer(val),
             Expr::String(val, _) => self.string(val),
+            Expr::Bytes(val, _) => self.bytes(val),
             Expr::Bool(val, _) => self.boolean(val),
             Expr::Nil(_) => self.nil(),
-        }
+        };
+
+        self.span = outer;
+        result
     }
 
     fn array(&mut self, array: &[Expr]) -> Result<(), SyntaxError> {
@@ -850,6 +1107,22 @@ impl Compiler {
         Ok(())
     }
 
+    fn interpolation(&mut self, parts: &[Expr]) -> Result<(), SyntaxError> {
+        for part in parts {
+            self.expression(part)?;
+        }
+
+        self.emit_byte(Opcode::BuildString as u8);
+
+        let part_count = parts.len() as u32;
+
+        for byte in part_count.to_le_bytes() {
+            self.emit_byte(byte);
+        }
+
+        Ok(())
+    }
+
     fn map(&mut self, values: &[Expr]) -> Result<(), SyntaxError> {
         for pair in values.chunks(2).rev() {
             self.expression(&pair[0])?;
@@ -872,18 +1145,10 @@ impl Compiler {
         self.expression(&expr.rhs)?;
 
         match &expr.op {
-            Op::Add => self.emit_byte(Opcode::Add as u8),
-            Op::Subtract => self.emit_byte(Opcode::Sub as u8),
-            Op::Multiply => self.emit_byte(Opcode::Mul as u8),
-            Op::Divide => self.emit_byte(Opcode::Div as u8),
-            Op::Remainder => self.emit_byte(Opcode::Rem as u8),
-            Op::LessThan => self.emit_byte(Opcode::CmpLT as u8),
-            Op::LessThanEquals => self.emit_byte(Opcode::CmpLTEq as u8),
-            Op::GreaterThan => self.emit_byte(Opcode::CmpGT as u8),
-            Op::GreaterThanEquals => self.emit_byte(Opcode::CmpGTEq as u8),
-            Op::EqualsTo => self.emit_byte(Opcode::CmpEq as u8),
-            Op::NotEqual => self.emit_byte(Opcode::CmpNotEq as u8),
-            _ => unreachable!("{:?} is not a binary operator.", &expr.op),
+            Op::Bang | Op::And | Op::Or => {
+                unreachable!("{:?} is not a binary operator.", &expr.op)
+            }
+            op => self.emit_operator(op.opcode(), &expr.op_span),
         }
 
         Ok(())
@@ -893,8 +1158,7 @@ impl Compiler {
         self.expression(&expr.lhs)?;
 
         let op = match &expr.op {
-            Op::And => Opcode::JumpIfFalse,
-            Op::Or => Opcode::JumpIfTrue,
+            op @ (Op::And | Op::Or) => op.opcode(),
             _ => unreachable!("Invalid logical operator."),
         };
 
@@ -908,12 +1172,29 @@ impl Compiler {
         Ok(())
     }
 
-    fn unary(&mut self, arg: &Expr, op: &Op) -> Result<(), SyntaxError> {
+    fn ternary_expr(&mut self, condition: &Expr, then: &Expr, alt: &Expr) -> Result<(), SyntaxError> {
+        self.expression(condition)?;
+        let then_jump = self.emit_jump(Opcode::JumpIfFalse);
+        self.emit_byte(Opcode::Del as u8);
+        self.expression(then)?;
+
+        let else_jump = self.emit_jump(Opcode::Jump);
+
+        self.patch_jump(then_jump);
+        self.emit_byte(Opcode::Del as u8);
+        self.expression(alt)?;
+
+        self.patch_jump(else_jump);
+
+        Ok(())
+    }
+
+    fn unary(&mut self, arg: &Expr, op: &Op, op_span: &Span) -> Result<(), SyntaxError> {
         self.expression(arg)?;
 
         match op {
-            Op::Subtract => self.emit_byte(Opcode::Neg as u8),
-            Op::Bang => self.emit_byte(Opcode::Not as u8),
+            Op::Subtract => self.emit_operator(Opcode::Neg, op_span),
+            Op::Bang => self.emit_operator(op.opcode(), op_span),
             _ => unreachable!("{:?} is not an unary operator.", &op),
         }
 
@@ -946,18 +1227,29 @@ impl Compiler {
     }
 
     fn identifier(&mut self, id: &Ident) -> Result<(), SyntaxError> {
-        self.load_variable(&id);
+        self.load_variable(id);
         Ok(())
     }
 
     fn number(&mut self, val: &f64) -> Result<(), SyntaxError> {
-        self.emit_constant(Value::Number(*val));
+        self.emit_constant(Value::Number(*val))
+    }
+
+    fn integer(&mut self, val: &i64) -> Result<(), SyntaxError> {
+        match val {
+            0 => self.emit_byte(Opcode::Zero as u8),
+            1 => self.emit_byte(Opcode::One as u8),
+            _ => return self.emit_constant(Value::Integer(*val)),
+        }
         Ok(())
     }
 
     fn string(&mut self, val: &str) -> Result<(), SyntaxError> {
-        self.emit_constant(Value::from(val));
-        Ok(())
+        self.emit_constant(Value::from(val))
+    }
+
+    fn bytes(&mut self, val: &[u8]) -> Result<(), SyntaxError> {
+        self.emit_constant(Value::from(val.to_vec()))
     }
 
     fn boolean(&mut self, val: &bool) -> Result<(), SyntaxError> {
@@ -1,4 +1,5 @@
 use crate::common::span::Span;
+use crate::compiler::ast::Pattern;
 use crate::error::{AsDiagnostic, Diagnostic, Item, Label};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -22,12 +23,14 @@ impl SyntaxError {
     }
 
     pub fn is_unexpected_eof(self) -> bool {
-        if let SyntaxErrorKind::UnexpectedEof { location: _ } = self.kind {
+        if let SyntaxErrorKind::UnexpectedEof { .. }
+        | SyntaxErrorKind::ExpectedExpressionAtEof { .. } = self.kind
+        {
             return true;
         }
 
-        if self.cause != None {
-            return self.cause.unwrap().is_unexpected_eof();
+        if let Some(cause) = self.cause {
+            return cause.is_unexpected_eof();
         }
 
         false
@@ -72,14 +75,22 @@ pub enum SyntaxErrorKind {
     ExpectedExpression {
         actual: Item,
     },
+    /// Input ended where an expression was expected.
+    ExpectedExpressionAtEof {
+        location: Span,
+    },
     /// Expected a newline
     ExpectedNewline {
         actual: Item,
     },
     /// Found a mismatched closing delimiter.
     MismatchedDelimiter {
+        /// The innermost delimiter that's still open.
         first: Item,
+        /// The closing delimiter that doesn't match it.
         second: Item,
+        /// The delimiter that would have closed `first`.
+        expected: &'static str,
     },
     /// break statement outside of a loop.
     BreakOutsideLoop {
@@ -112,6 +123,55 @@ pub enum SyntaxErrorKind {
     AssignToConst {
         item: Item,
     },
+    /// A variable declared without an initializer in strict mode.
+    MissingVarInit {
+        item: Item,
+    },
+    /// A function that can end without a `return` in strict mode.
+    MissingReturn {
+        item: Item,
+    },
+    /// A numeric literal longer than the configured maximum.
+    NumberTooLong {
+        literal: Item,
+        max: usize,
+    },
+    /// A block or expression nested deeper than the configured maximum.
+    NestingTooDeep {
+        item: Item,
+        max: usize,
+    },
+    /// A source with more top-level statements than the configured maximum.
+    TooManyStatements {
+        item: Item,
+        max: usize,
+    },
+    /// A function with more constants than the configured maximum.
+    TooManyConstants {
+        item: Item,
+        max: usize,
+    },
+    /// A token the scanner couldn't make sense of.
+    InvalidToken {
+        item: Item,
+        message: String,
+    },
+    /// A direct call to a function with the wrong number of arguments.
+    WrongArgCount {
+        call: Item,
+        definition: Span,
+        expected: usize,
+        got: usize,
+    },
+    /// A declaration with a list pattern, which can be parsed but not compiled.
+    UnsupportedDestructuring {
+        pattern: Item,
+    },
+    /// An `=` straight after the condition of an `if` or `while`, most likely
+    /// meant to be `==`.
+    AssignmentInCondition {
+        equals: Item,
+    },
 }
 
 impl SyntaxError {
@@ -157,6 +217,11 @@ impl SyntaxError {
                 .with_labels(vec![
                     Label::primary(actual.span.clone()).with_message("expected an expression")
                 ]),
+            ExpectedExpressionAtEof { location } => Diagnostic::error()
+                .with_message("unexpected end of input; expected an expression")
+                .with_labels(vec![
+                    Label::primary(location.clone()).with_message("expected an expression")
+                ]),
             ExpectedNewline { actual } => Diagnostic::error()
                 .with_message("expected a newline")
                 .with_labels(vec![
@@ -166,9 +231,12 @@ impl SyntaxError {
                     "statements are newline terminated",
                     "try inserting a newline",
                 ]),
-            MismatchedDelimiter { first, second } => Diagnostic::error()
+            MismatchedDelimiter { first, second, expected } => Diagnostic::error()
                 .with_message(format!(
-                    "mismatched closing delimiter: `{}`",
+                    "mismatched closing delimiter: expected `{}` to match `{}` at {}, found `{}`",
+                    expected,
+                    first.content,
+                    position(&first.span),
                     second.content
                 ))
                 .with_labels(vec![
@@ -203,9 +271,9 @@ impl SyntaxError {
                 ))
                 .with_labels(vec![
                     Label::secondary(first.span.clone())
-                        .with_message(&format!("previous definition of `{}` here", first.content)),
+                        .with_message(format!("previous definition of `{}` here", first.content)),
                     Label::primary(second.span.clone())
-                        .with_message(&format!("`{}` redefined here", second.content)),
+                        .with_message(format!("`{}` redefined here", second.content)),
                 ])
                 .with_notes(vec!["identifiers can only be defined once in a scope"]),
             DuplicateParam { param } => Diagnostic::error()
@@ -221,18 +289,96 @@ impl SyntaxError {
                     Label::primary(item.span.clone()).with_message("missing initalizer")
                 ])
                 .with_notes(vec!["add a definition for the constant: `= <expr>`"]),
+            MissingVarInit { item } => Diagnostic::error()
+                .with_message(format!("variable `{}` is declared without a value", &item.content))
+                .with_labels(vec![
+                    Label::primary(item.span.clone()).with_message("missing initializer")
+                ])
+                .with_notes(vec![
+                    "strict mode doesn't allow variables to implicitly be `nil`",
+                    "add an initial value: `= <expr>`",
+                ]),
+            MissingReturn { item } => Diagnostic::error()
+                .with_message(format!(
+                    "function `{}` can end without returning a value",
+                    &item.content
+                ))
+                .with_labels(vec![Label::primary(item.span.clone())
+                    .with_message("doesn't return on every path")])
+                .with_notes(vec![
+                    "strict mode doesn't allow functions to implicitly return `nil`",
+                    "add a `return` statement to the end of the function",
+                ]),
             AssignToConst { item } => Diagnostic::error()
-                .with_message(&format!(
+                .with_message(format!(
                     "attempt to assign to constant variable: `{}`",
                     &item.content
                 ))
                 .with_labels(vec![
                     Label::primary(item.span.clone()).with_message("cannot assign")
                 ]),
+            NumberTooLong { literal, max } => Diagnostic::error()
+                .with_message("numeric literal too long")
+                .with_labels(vec![Label::primary(literal.span.clone())
+                    .with_message(format!("literal is longer than {} characters", max))]),
+            NestingTooDeep { item, max } => Diagnostic::error()
+                .with_message("maximum nesting depth exceeded")
+                .with_labels(vec![Label::primary(item.span.clone())
+                    .with_message(format!("nests deeper than {} levels", max))]),
+            TooManyStatements { item, max } => Diagnostic::error()
+                .with_message("too many statements")
+                .with_labels(vec![Label::primary(item.span.clone())
+                    .with_message(format!("a source can have at most {} top-level statements", max))]),
+            TooManyConstants { item, max } => Diagnostic::error()
+                .with_message("too many constants in one chunk")
+                .with_labels(vec![Label::primary(item.span.clone())
+                    .with_message(format!("a chunk can hold at most {} constants", max))]),
+            InvalidToken { item, message } => Diagnostic::error()
+                .with_message(message)
+                .with_labels(vec![
+                    Label::primary(item.span.clone()).with_message("invalid token")
+                ]),
+            WrongArgCount {
+                call,
+                definition,
+                expected,
+                got,
+            } => Diagnostic::error()
+                .with_message(format!("wrong number of arguments to `{}`", call.content))
+                .with_labels(vec![
+                    Label::primary(call.span.clone())
+                        .with_message(format!("expected {} arguments but got {}", expected, got)),
+                    Label::secondary(definition.clone())
+                        .with_message(format!("`{}` defined here", call.content)),
+                ]),
+            UnsupportedDestructuring { pattern } => Diagnostic::error()
+                .with_message("destructuring not yet supported")
+                .with_labels(vec![Label::primary(pattern.span.clone())
+                    .with_message("declare each variable separately instead")]),
+            AssignmentInCondition { equals } => Diagnostic::error()
+                .with_message("assignment in a condition")
+                .with_labels(vec![Label::primary(equals.span.clone())
+                    .with_message("use `==` to compare values")])
+                .with_notes(vec!["assignment is a statement, so it can't be used as a condition"]),
         }
     }
 }
 
+/// Where `span` starts, as `line:column` counting from 1.
+pub(crate) fn position(span: &Span) -> String {
+    let (line, col) = span.source.line_col(span.start);
+    format!("{}:{}", span.source.base_line + line + 1, col + 1)
+}
+
+/// The error for a declaration whose `target` is a list pattern.
+pub fn unsupported_destructuring(target: &Pattern) -> SyntaxError {
+    let span = target.position();
+
+    SyntaxError::new(SyntaxErrorKind::UnsupportedDestructuring {
+        pattern: Item::new(&span, span.text()),
+    })
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CompileError {
     msg: String,
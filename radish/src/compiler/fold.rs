@@ -0,0 +1,329 @@
+//! Constant folding for logical expressions.
+//!
+//! A logical expression whose left hand side is a boolean literal can be
+//! decided at compile time:
+//!
+//! * `true or <expr>` is `true` and `false and <expr>` is `false`. The right
+//!   hand side is dropped, so this is only done when it can't have a side
+//!   effect (it contains no calls).
+//! * `false or <expr>` and `true and <expr>` are just `<expr>`.
+//!
+//! An `if` statement whose condition is a literal is replaced by the branch
+//! that would be taken, as a block so that it keeps its scope. A falsey
+//! condition without an `else` leaves an empty block.
+//!
+//! [`explain_fold_constants`] reports these changes instead of making them.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::common::Span;
+use crate::Value;
+
+use super::visitor::{walk_expr, walk_stmt, VisitorResult};
+use super::{Expr, FunctionDecl, Op, Stmt, SyntaxError, Visitor, AST};
+
+pub fn fold_constants(ast: &mut AST) -> Result<(), SyntaxError> {
+    let mut folder = Folder::default();
+
+    for stmt in ast.items.iter_mut() {
+        folder.visit_stmt(stmt)?;
+    }
+
+    Ok(())
+}
+
+/// Describe what [`fold_constants`] would change in `ast`, in the order the
+/// changes are made, without changing `ast` itself.
+pub fn explain_fold_constants(ast: &AST) -> Result<Vec<Transformation>, SyntaxError> {
+    let mut items = ast.items.clone();
+    let mut folder = Folder::default();
+
+    for stmt in items.iter_mut() {
+        folder.visit_stmt(stmt)?;
+    }
+
+    Ok(folder.applied)
+}
+
+/// A change made by constant folding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transformation {
+    /// What was changed.
+    pub message: String,
+    /// The code that was changed.
+    pub span: Span,
+}
+
+impl Transformation {
+    fn new(message: String, span: Span) -> Self {
+        Transformation { message, span }
+    }
+}
+
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.message, self.span.start, self.span.end)
+    }
+}
+
+#[derive(Default)]
+struct Folder {
+    /// The changes made so far.
+    applied: Vec<Transformation>,
+}
+
+impl Folder {
+    fn fold_logical(&mut self, expr: &mut Expr) {
+        let (logical, span) = match expr {
+            Expr::LogicalExpr(logical, span) => (logical, span),
+            _ => return,
+        };
+
+        let folded = match (constant_bool(&logical.lhs), &logical.op) {
+            (Some(value), op) if value == (*op == Op::Or) => {
+                if has_side_effects(&mut logical.rhs) {
+                    return;
+                }
+
+                Expr::Bool(value, span.clone())
+            }
+            (Some(_), _) => std::mem::replace(&mut logical.rhs, Expr::Nil(Span::empty())),
+            (None, _) => return,
+        };
+
+        // a folded boolean keeps the span of the whole expression.
+        let result = match &folded {
+            Expr::Bool(value, _) => value.to_string(),
+            expr => expr.position().text().to_string(),
+        };
+
+        let message = format!("folded `{}` into `{}`", span.text(), result);
+        self.applied.push(Transformation::new(message, span.clone()));
+
+        *expr = folded;
+    }
+
+    fn fold_if(&mut self, stmt: &mut Stmt) {
+        let (condition, body, alt, span) = match stmt {
+            Stmt::IfStmt(condition, body, alt, span) => (condition, body, alt, span),
+            _ => return,
+        };
+
+        let (taken, dead) = match constant_value(condition) {
+            Some(value) if value.truthy() => (
+                Stmt::BlockStmt(std::mem::take(body), span.clone()),
+                alt.as_ref().map(|alt| alt.position()),
+            ),
+            Some(_) => {
+                let dead = block_span(body);
+
+                match alt.take() {
+                    Some(alt) => (*alt, dead),
+                    None => (Stmt::BlockStmt(vec![], span.clone()), dead),
+                }
+            }
+            None => return,
+        };
+
+        let transformation = match dead {
+            Some(dead) => Transformation::new("eliminated dead branch".to_string(), dead),
+            None => {
+                let message = format!("removed constant condition `{}`", condition.position().text());
+                Transformation::new(message, condition.position())
+            }
+        };
+        self.applied.push(transformation);
+
+        *stmt = taken;
+    }
+}
+
+impl<'a> Visitor<'a> for Folder {
+    fn visit_stmt(&mut self, stmt: &mut Stmt) -> VisitorResult {
+        walk_stmt(self, stmt)?;
+        self.fold_if(stmt);
+
+        Ok(())
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) -> VisitorResult {
+        walk_expr(self, expr)?;
+        self.fold_logical(expr);
+
+        Ok(())
+    }
+}
+
+/// The span of a block's statements, if it has any.
+fn block_span(body: &[Stmt]) -> Option<Span> {
+    let first = body.first()?.position();
+    let last = body.last()?.position();
+
+    Some(Span::combine(&first, &last))
+}
+
+/// Returns the value of `expr` if it's a (possibly parenthesized) boolean
+/// literal.
+pub(super) fn constant_bool(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Bool(value, _) => Some(*value),
+        Expr::ParenExpr(inner, _) => constant_bool(inner),
+        _ => None,
+    }
+}
+
+/// Returns the value of `expr` if it's a (possibly parenthesized) literal
+/// that evaluates to an immutable value.
+fn constant_value(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Bool(value, _) => Some(Value::Boolean(*value)),
+        Expr::Integer(value, _) => Some(Value::Integer(*value)),
+        Expr::Number(value, _) => Some(Value::Number(*value)),
+        Expr::String(value, _) => Some(Value::String(Rc::new(RefCell::new(value.clone())))),
+        Expr::Nil(_) => Some(Value::Nil),
+        Expr::ParenExpr(inner, _) => constant_value(inner),
+        _ => None,
+    }
+}
+
+/// Returns whether evaluating `expr` could have an observable effect besides
+/// producing its value.
+fn has_side_effects(expr: &mut Expr) -> bool {
+    let mut finder = SideEffectFinder { found: false };
+    // The finder never fails.
+    let _ = finder.visit_expr(expr);
+
+    finder.found
+}
+
+struct SideEffectFinder {
+    found: bool,
+}
+
+impl<'a> Visitor<'a> for SideEffectFinder {
+    fn visit_stmt(&mut self, _stmt: &mut Stmt) -> VisitorResult {
+        // Loop expressions run arbitrary statements.
+        self.found = true;
+        Ok(())
+    }
+
+    fn visit_lambda(&mut self, _fun: &mut FunctionDecl) -> VisitorResult {
+        // Creating a closure captures variables, which the passes after this
+        // one still need to see.
+        self.found = true;
+        Ok(())
+    }
+
+    fn visit_call_expr(
+        &mut self,
+        _callee: &mut Expr,
+        _args: &mut Vec<Expr>,
+        _span: &Span,
+    ) -> VisitorResult {
+        self.found = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::source::Source;
+    use crate::compiler::{Parser, StructurallyEq};
+
+    use super::*;
+
+    fn fold(src: &str) -> AST {
+        let mut ast = Parser::new(Source::new(src, "")).parse().unwrap();
+        fold_constants(&mut ast).unwrap();
+        ast
+    }
+
+    fn parse(src: &str) -> AST {
+        Parser::new(Source::new(src, "")).parse().unwrap()
+    }
+
+    #[test]
+    fn folds_short_circuit_without_side_effects() {
+        assert!(fold("print true or x\n").structurally_eq(&parse("print true\n")));
+        assert!(fold("print false and x[0]\n").structurally_eq(&parse("print false\n")));
+    }
+
+    #[test]
+    fn keeps_right_side_with_calls() {
+        let src = "print true or f()\n";
+        assert!(fold(src).structurally_eq(&parse(src)));
+
+        let src = "print false and [1, g(2)]\n";
+        assert!(fold(src).structurally_eq(&parse(src)));
+    }
+
+    #[test]
+    fn folds_to_right_side() {
+        assert!(fold("print false or f()\n").structurally_eq(&parse("print f()\n")));
+        assert!(fold("print true and x\n").structurally_eq(&parse("print x\n")));
+    }
+
+    #[test]
+    fn folds_nested_logical_constants() {
+        let ast = fold("print false and x or true and false or true\n");
+        assert!(ast.structurally_eq(&parse("print true\n")));
+
+        let ast = fold("print f() or false and x\n");
+        assert!(ast.structurally_eq(&parse("print f() or false\n")));
+    }
+
+    #[test]
+    fn folds_constant_true_condition() {
+        let ast = fold("if true then\n    print a\nelse\n    print b\nendif\n");
+        assert!(ast.structurally_eq(&parse("{\n    print a\n}\n")));
+
+        let ast = fold("if (1) then\n    print a\nendif\n");
+        assert!(ast.structurally_eq(&parse("{\n    print a\n}\n")));
+    }
+
+    #[test]
+    fn folds_constant_false_condition() {
+        let ast = fold("if false then\n    print a\nelse\n    print b\nendif\n");
+        assert!(ast.structurally_eq(&parse("{\n    print b\n}\n")));
+
+        let ast = fold("if nil then\n    print a\nendif\n");
+        assert!(ast.structurally_eq(&parse("{\n}\n")));
+
+        // the taken branch of an `else if` chain is folded too.
+        let ast = fold("if false then\n    print a\nelif true then\n    print b\nendif\n");
+        assert!(ast.structurally_eq(&parse("{\n    print b\n}\n")));
+    }
+
+    #[test]
+    fn keeps_variable_condition() {
+        let src = "if x then\n    print a\nelse\n    print b\nendif\n";
+        assert!(fold(src).structurally_eq(&parse(src)));
+
+        // only the nested constant `if` is folded.
+        let ast = fold("if x then\n    if true then\n        print a\n    endif\nendif\n");
+        let expected = parse("if x then\n    {\n        print a\n    }\nendif\n");
+        assert!(ast.structurally_eq(&expected));
+    }
+
+    #[test]
+    fn explains_transformations() {
+        let src = "print true or x\nif false then\n    print 1\nelse\n    print 2\nendif\nif true then\n    print 3\nendif\n";
+        let ast = parse(src);
+
+        let applied: Vec<_> = explain_fold_constants(&ast).unwrap().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            applied,
+            [
+                "folded `true or x` into `true` at 6..15",
+                "eliminated dead branch at 34..41",
+                "removed constant condition `true` at 68..72",
+            ]
+        );
+
+        // the AST itself is left alone.
+        assert!(ast.structurally_eq(&parse(src)));
+        assert!(explain_fold_constants(&parse("print x or y\n")).unwrap().is_empty());
+    }
+}
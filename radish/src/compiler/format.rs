@@ -0,0 +1,584 @@
+//! Prints an AST back out as canonically formatted source code.
+//!
+//! Every statement is put on its own line, with blocks indented by four
+//! spaces. Binary operators are written with a space on either side.
+//! Parentheses from the source are dropped, and the only ones written are
+//! those that the precedence of the operators needs. Formatting is idempotent:
+//! formatting the parsed output of [`format()`] gives back the same text.
+//!
+//! The parser doesn't keep comments, so they're lost.
+
+use crate::compiler::ast::*;
+use crate::compiler::token::TokenType;
+
+const INDENT: &str = "    ";
+
+/// Format `ast` as source code.
+pub fn format(ast: &AST) -> String {
+    let mut formatter = Formatter::default();
+
+    for item in &ast.items {
+        formatter.stmt(item);
+        formatter.out.push('\n');
+    }
+
+    formatter.out
+}
+
+/// How tightly an expression binds, from the loosest to the tightest. An
+/// operand binding looser than its position allows is parenthesized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Ternary,
+    Or,
+    And,
+    Comparison,
+    Sum,
+    Product,
+    Unary,
+    Member,
+    Primary,
+}
+
+impl Precedence {
+    fn of(expr: &Expr) -> Precedence {
+        match expr {
+            Expr::TernaryExpr(..) => Precedence::Ternary,
+            Expr::LogicalExpr(binary, _) | Expr::BinaryExpr(binary, _) => Precedence::of_op(&binary.op),
+            Expr::UnaryExpr(..) => Precedence::Unary,
+            Expr::CallExpr(..) | Expr::MemberExpr(..) => Precedence::Member,
+            Expr::ParenExpr(expr, _) => Precedence::of(expr),
+            _ => Precedence::Primary,
+        }
+    }
+
+    fn of_op(op: &Op) -> Precedence {
+        match op {
+            Op::Or => Precedence::Or,
+            Op::And => Precedence::And,
+            Op::LessThan
+            | Op::LessThanEquals
+            | Op::GreaterThan
+            | Op::GreaterThanEquals
+            | Op::EqualsTo
+            | Op::NotEqual => Precedence::Comparison,
+            Op::Add | Op::Subtract => Precedence::Sum,
+            Op::Multiply | Op::Divide | Op::Remainder => Precedence::Product,
+            Op::Bang => Precedence::Unary,
+        }
+    }
+
+    /// The next tighter precedence, for the right operand of a
+    /// left-associative operator.
+    fn next(self) -> Precedence {
+        match self {
+            Precedence::Ternary => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Sum,
+            Precedence::Sum => Precedence::Product,
+            Precedence::Product => Precedence::Unary,
+            Precedence::Unary => Precedence::Member,
+            Precedence::Member | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+}
+
+impl Formatter {
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Write `body` indented on the lines after the current one, leaving the
+    /// output at the start of a new line for whatever closes the block.
+    fn block(&mut self, body: &[Stmt]) {
+        self.indent += 1;
+
+        for stmt in body {
+            self.newline();
+            self.stmt(stmt);
+        }
+
+        self.indent -= 1;
+        self.newline();
+    }
+
+    /// Write `body` between braces, or `{}` if it's empty.
+    fn braced(&mut self, body: &[Stmt]) {
+        if body.is_empty() {
+            return self.write("{}");
+        }
+
+        self.write("{");
+        self.block(body);
+        self.write("}");
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::BlockStmt(body, _) => self.braced(body),
+            Stmt::ExpressionStmt(expr) => self.expr(expr),
+            Stmt::FunDeclaration(fun, _) => {
+                self.write("fun ");
+                self.write(&fun.id.name);
+                self.function(&fun.params, &fun.body);
+            }
+            Stmt::ClassDeclaration(class, _) => self.class(class),
+            Stmt::ConDeclaration(con, _) => self.constructor(con),
+            Stmt::VarDeclaration(target, init, kind, _) => {
+                self.write(match kind {
+                    VarKind::Var => "var ",
+                    VarKind::Fin => "fin ",
+                });
+                self.pattern(target);
+
+                if let Some(init) = init {
+                    self.write(" = ");
+                    self.expr(init);
+                }
+            }
+            Stmt::AssignmentStmt(assignment, _) => {
+                self.expr(&assignment.lhs);
+                self.write(match assignment.op {
+                    OpAssignment::Equals => " = ",
+                    OpAssignment::AddAssign => " += ",
+                    OpAssignment::SubAssign => " -= ",
+                    OpAssignment::MulAssign => " *= ",
+                    OpAssignment::DivAssign => " /= ",
+                    OpAssignment::RemAssign => " %= ",
+                });
+                self.expr(&assignment.rhs);
+            }
+            Stmt::IfStmt(condition, body, alt, _) => self.if_stmt("if", condition, body, alt),
+            Stmt::LoopStmt(body, _) => {
+                self.write("loop");
+                self.block(body);
+                self.write("endloop");
+            }
+            Stmt::WhileStmt(condition, body, _) => {
+                self.write("while ");
+                self.expr(condition);
+                self.write(" loop");
+                self.block(body);
+                self.write("endloop");
+            }
+            Stmt::ForStmt(for_stmt, _) => {
+                self.write("for ");
+                self.write(&for_stmt.var.name);
+                self.write(" in ");
+                self.expr(&for_stmt.iterable);
+                self.write(" loop");
+                self.block(&for_stmt.body);
+                self.write("endloop");
+            }
+            Stmt::ImportStmt(import) => {
+                self.write("import ");
+                self.string(import.path());
+
+                if !import.items().is_empty() {
+                    self.write(" for ");
+                    self.names(import.items());
+                }
+            }
+            Stmt::BreakStmt(value, _) => self.keyword_with_value("break", value),
+            Stmt::ContinueStmt(_) => self.write("continue"),
+            Stmt::ReturnStmt(value, _) => self.keyword_with_value("return", value),
+            Stmt::PrintStmt(expr, _) => {
+                self.write("print ");
+                self.expr(expr);
+            }
+        }
+    }
+
+    fn keyword_with_value(&mut self, keyword: &str, value: &Option<Expr>) {
+        self.write(keyword);
+
+        if let Some(value) = value {
+            self.write(" ");
+            self.expr(value);
+        }
+    }
+
+    /// Write an `if` or `elif` branch, followed by the rest of the chain. An
+    /// `if` in the else branch is written as an `elif`.
+    fn if_stmt(&mut self, keyword: &str, condition: &Expr, body: &[Stmt], alt: &Option<Box<Stmt>>) {
+        self.write(keyword);
+        self.write(" ");
+        self.expr(condition);
+        self.write(" then");
+        self.block(body);
+
+        match alt.as_deref() {
+            None => self.write("endif"),
+            Some(Stmt::IfStmt(condition, body, alt, _)) => self.if_stmt("elif", condition, body, alt),
+            Some(alt) => {
+                self.write("else");
+
+                match alt {
+                    Stmt::BlockStmt(body, _) => self.block(body),
+                    alt => self.block(std::slice::from_ref(alt)),
+                }
+
+                self.write("endif");
+            }
+        }
+    }
+
+    fn class(&mut self, class: &ClassDecl) {
+        self.write("class ");
+        self.write(&class.id.name);
+
+        if class.constructors.is_empty() {
+            return self.write(" {}");
+        }
+
+        self.write(" {");
+        self.indent += 1;
+
+        for con in &class.constructors {
+            self.newline();
+            self.constructor(con);
+        }
+
+        self.indent -= 1;
+        self.newline();
+        self.write("}");
+    }
+
+    fn constructor(&mut self, con: &ConstructorDecl) {
+        self.write("con ");
+        self.write(&con.id.name);
+        self.function(&con.params, &con.body);
+    }
+
+    /// Write a parameter list and a function body.
+    fn function(&mut self, params: &[Ident], body: &[Stmt]) {
+        self.write("(");
+        self.names(params);
+        self.write(") ");
+        self.braced(body);
+    }
+
+    fn names(&mut self, names: &[Ident]) {
+        for (i, name) in names.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.write(&name.name);
+        }
+    }
+
+    fn pattern(&mut self, target: &Pattern) {
+        match target {
+            Pattern::Name(id) => self.write(&id.name),
+            Pattern::List(items, _) => {
+                self.write("[");
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.pattern(item);
+                }
+
+                self.write("]");
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.expr_at(expr, Precedence::Ternary);
+    }
+
+    /// Write `expr` where only an expression binding at least as tightly as
+    /// `min` can appear without parentheses.
+    fn expr_at(&mut self, expr: &Expr, min: Precedence) {
+        if let Expr::ParenExpr(inner, _) = expr {
+            return self.expr_at(inner, min);
+        }
+
+        if Precedence::of(expr) < min {
+            self.write("(");
+            self.expr_unparenthesized(expr);
+            self.write(")");
+        } else {
+            self.expr_unparenthesized(expr);
+        }
+    }
+
+    fn exprs_at(&mut self, exprs: &[Expr], min: Precedence) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            self.expr_at(expr, min);
+        }
+    }
+
+    fn expr_unparenthesized(&mut self, expr: &Expr) {
+        match expr {
+            Expr::ArrayExpr(elements, _) => {
+                self.write("[");
+                self.exprs_at(elements, Precedence::Sum);
+                self.write("]");
+            }
+            Expr::MapExpr(entries, _) => {
+                self.write("{");
+
+                for (i, entry) in entries.chunks(2).enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.expr_at(&entry[0], Precedence::Sum);
+                    self.write(": ");
+                    if let Some(value) = entry.get(1) {
+                        self.expr_at(value, Precedence::Sum);
+                    }
+                }
+
+                self.write("}");
+            }
+            Expr::Interpolation(parts, _) => {
+                self.write("\"");
+
+                for part in parts {
+                    match part {
+                        Expr::String(text, _) => self.string_contents(text),
+                        part => {
+                            self.write("{");
+                            self.expr(part);
+                            self.write("}");
+                        }
+                    }
+                }
+
+                self.write("\"");
+            }
+            Expr::BinaryExpr(binary, _) | Expr::LogicalExpr(binary, _) => {
+                let precedence = Precedence::of_op(&binary.op);
+
+                self.expr_at(&binary.lhs, precedence);
+                self.write(" ");
+                self.write(&binary.op.to_string());
+                self.write(" ");
+                self.expr_at(&binary.rhs, precedence.next());
+            }
+            Expr::ParenExpr(expr, _) => self.expr_unparenthesized(expr),
+            Expr::UnaryExpr(op, _, arg, _) => {
+                self.write(match op {
+                    Op::Bang => "!",
+                    _ => "-",
+                });
+                self.expr_at(arg, Precedence::Unary);
+            }
+            Expr::TernaryExpr(condition, then, alt, _) => {
+                self.expr_at(condition, Precedence::Or);
+                self.write(" ? ");
+                self.expr(then);
+                self.write(" : ");
+                self.expr(alt);
+            }
+            Expr::LoopExpr(stmt, _) => self.stmt(stmt),
+            Expr::Lambda(fun, _) => {
+                self.write("fun");
+                self.function(&fun.params, &fun.body);
+            }
+            Expr::CallExpr(callee, args, _) => {
+                self.expr_at(callee, Precedence::Member);
+                self.write("(");
+                self.exprs_at(args, Precedence::Ternary);
+                self.write(")");
+            }
+            Expr::MemberExpr(object, property, _) => {
+                self.expr_at(object, Precedence::Member);
+
+                match &**property {
+                    Expr::String(name, _) if is_property_name(name) => {
+                        self.write(".");
+                        self.write(name);
+                    }
+                    property => {
+                        self.write("[");
+                        self.expr_at(property, Precedence::Sum);
+                        self.write("]");
+                    }
+                }
+            }
+            Expr::Identifier(id) => self.write(&id.name),
+            Expr::Number(val, _) => self.write(&format!("{:?}", val)),
+            Expr::Integer(val, _) => self.write(&val.to_string()),
+            Expr::Bool(val, _) => self.write(if *val { "true" } else { "false" }),
+            Expr::String(val, _) => self.string(val),
+            Expr::Bytes(bytes, _) => {
+                self.write("b\"");
+
+                for &byte in bytes {
+                    match byte {
+                        b'"' => self.write("\\\""),
+                        b'\\' => self.write("\\\\"),
+                        b' '..=b'~' => self.out.push(byte as char),
+                        byte => self.write(&format!("\\x{:02x}", byte)),
+                    }
+                }
+
+                self.write("\"");
+            }
+            Expr::Nil(_) => self.write("nil"),
+        }
+    }
+
+    fn string(&mut self, val: &str) {
+        self.write("\"");
+        self.string_contents(val);
+        self.write("\"");
+    }
+
+    /// Strings don't have escapes, apart from doubling the braces that would
+    /// otherwise start or end an embedded expression.
+    fn string_contents(&mut self, val: &str) {
+        for ch in val.chars() {
+            match ch {
+                '{' => self.write("{{"),
+                '}' => self.write("}}"),
+                ch => self.out.push(ch),
+            }
+        }
+    }
+}
+
+/// Whether a property can be written as `object.name`, rather than indexing
+/// with a string.
+fn is_property_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && TokenType::keyword(name).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::source::Source;
+    use crate::compiler::Parser;
+
+    fn parse(src: &str) -> AST {
+        Parser::new(Source::new(src, "")).parse().unwrap()
+    }
+
+    fn fmt(src: &str) -> String {
+        format(&parse(src))
+    }
+
+    #[test]
+    fn spaces_around_operators() {
+        assert_eq!(fmt("1+2*3"), "1 + 2 * 3\n");
+        assert_eq!(fmt("var   a=-b.c [ 1 ]  ==d"), "var a = -b.c[1] == d\n");
+        assert_eq!(fmt("print a and!b or c?1:2"), "print a and !b or c ? 1 : 2\n");
+    }
+
+    #[test]
+    fn minimal_parentheses() {
+        assert_eq!(fmt("(1 + 2) * 3"), "(1 + 2) * 3\n");
+        assert_eq!(fmt("1 + (2 * 3)"), "1 + 2 * 3\n");
+        assert_eq!(fmt("((a))"), "a\n");
+        assert_eq!(fmt("(1 - 2) - 3"), "1 - 2 - 3\n");
+        assert_eq!(fmt("1 - (2 - 3)"), "1 - (2 - 3)\n");
+        assert_eq!(fmt("-(a + 1)"), "-(a + 1)\n");
+        assert_eq!(fmt("(-a).b"), "(-a).b\n");
+        assert_eq!(fmt("(f)(1)"), "f(1)\n");
+    }
+
+    #[test]
+    fn stacked_unary_operators() {
+        assert_eq!(fmt("-----2"), "-----2\n");
+        assert_eq!(fmt("!!true"), "!!true\n");
+        assert_eq!(fmt("-(-a.b)"), "--a.b\n");
+
+        for src in ["-----2", "!!true", "!-a"] {
+            assert!(parse(&fmt(src)).structurally_eq(&parse(src)), "formatting {:?}", src);
+        }
+    }
+
+    #[test]
+    fn statements_and_blocks() {
+        let src = r#"
+import "lib/utils.rdsh" for a,b
+fun add(a,b){ return a+b }
+fin [x, [y]] = [1, 2]
+if x<1 then print "{{small}}"
+elif x<2 then
+print "a {x + 1}!"
+else x += 1 endif
+while true loop break endloop
+for i in range(0, 3) loop continue
+endloop
+var f = fun (n) { n * 2 }
+var m = {"a": b"\x00\"", 1: 2.5}
+print m.a + m["not a name"] + m["print"]
+"#;
+
+        let expected = r#"import "lib/utils.rdsh" for a, b
+fun add(a, b) {
+    return a + b
+}
+fin [x, [y]] = [1, 2]
+if x < 1 then
+    print "{{small}}"
+elif x < 2 then
+    print "a {x + 1}!"
+else
+    x += 1
+endif
+while true loop
+    break
+endloop
+for i in range(0, 3) loop
+    continue
+endloop
+var f = fun(n) {
+    return n * 2
+}
+var m = {"a": b"\x00\"", 1: 2.5}
+print m.a + m["not a name"] + m["print"]
+"#;
+
+        assert_eq!(fmt(src), expected);
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let srcs = [
+            "print (1 + 2) * -(3 - 4) % 5",
+            "var a = loop\n if x then break 1 endif\nendloop",
+            "fun f() {}\nclass A { con new(a) { print a } }",
+            "print a ? b ? 1 : 2 : c(d, e)[0].f",
+            "var big = 1234567890123456789 + 1e300 + 1e-7",
+            "{ print \"{\"{a}\"}\" }",
+        ];
+
+        for src in srcs {
+            let once = fmt(src);
+            let twice = fmt(&once);
+
+            assert_eq!(once, twice, "formatting {:?}", src);
+            assert!(parse(&once).structurally_eq(&parse(src)), "formatting {:?}", src);
+        }
+    }
+}
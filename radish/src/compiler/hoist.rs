@@ -2,8 +2,10 @@ use std::collections::HashMap;
 
 use super::visitor::VisitorResult;
 use super::Expr;
+use super::ForStmt;
 use super::FunctionDecl;
 use super::Ident;
+use super::Pattern;
 use super::Stmt;
 use super::SyntaxError;
 use super::VarKind;
@@ -18,31 +20,21 @@ pub fn hoist(ast: &mut AST) -> Result<(), SyntaxError> {
     Ok(())
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum VarScope {
+    #[default]
     Global,
     Local(bool),
     NonLocal,
 }
 
-impl Default for VarScope {
-    fn default() -> Self {
-        VarScope::Global
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum ScopeTyp {
+    #[default]
     Unknown,
     Local(Local),
 }
 
-impl Default for ScopeTyp {
-    fn default() -> Self {
-        ScopeTyp::Unknown
-    }
-}
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct Local(String, usize, bool);
 
@@ -131,16 +123,12 @@ impl Scope {
     }
 
     fn get_upvalue(&self, name: &str) -> Option<usize> {
-        if let Some(index) = self.upvalue_indexes.get(name) {
-            Some(*index)
-        } else {
-            None
-        }
+        self.upvalue_indexes.get(name).copied()
     }
 
     /// find the local with the given name and capture it.
     fn capture_local(&mut self, name: &str) {
-        for mut local in self.locals.iter_mut().rev() {
+        for local in self.locals.iter_mut().rev() {
             if local.0 == name {
                 local.2 = true;
                 break;
@@ -253,18 +241,14 @@ impl Hoister {
         if let Some(scope) = self.scopes.pop() {
             //println!("[hoist] exiting scope \"{}\"", scope.name);
             //println!("[hoist] now in scope \"{}\"", self.scope().name);
-            return scope;
+            scope
         } else {
             panic!("Tried to exit global scope");
         }
     }
 
     fn in_global_scope(&self) -> bool {
-        if self.scopes.len() == 1 && self.scope().depth == 0 {
-            true
-        } else {
-            false
-        }
+        self.scopes.len() == 1 && self.scope().depth == 0
     }
 
     fn declare_local(&mut self, id: &mut Ident) {
@@ -420,17 +404,12 @@ impl Hoister {
             self.scopes.push(scope);
         }
 
-        //id.index = index as u32;
-        id.index = self.scope_mut().upvalues.len() as u32;
+        id.index = self.scope().get_upvalue(&id.name).unwrap() as u32;
         id.scope = VarScope::NonLocal;
     }
 
     fn _handle_resolved_upvalue(&mut self, id: &mut Ident, pos: usize, depth: usize) {
-        let _on_stack = if depth == 0 {
-            false
-        } else {
-            true
-        };
+        let _on_stack = depth != 0;
 
         //println!("[hoist] the current scope: {:#?}", self.scope());
         //println!(
@@ -456,14 +435,14 @@ impl Hoister {
         id.scope = VarScope::NonLocal;
     }
 
-    fn capture_locals(&mut self, locals: Vec<Local>, block: &mut Vec<Stmt>) {
+    fn capture_locals(&mut self, locals: Vec<Local>, block: &mut [Stmt]) {
         //println!("[hoist] dealing with the following locals: {:?}", locals);
 
         let mut captures = vec![];
 
         for stmt in block.iter_mut() {
             match stmt {
-                Stmt::VarDeclaration(id, _, _, _) => {
+                Stmt::VarDeclaration(Pattern::Name(id), _, _, _) => {
                     for local in locals.iter() {
                         if local.is_captured() && id.name == local.0 {
                             //println!(
@@ -512,6 +491,10 @@ impl Visitor<'_> for Hoister {
     fn visit_fun_decl(&mut self, fun: &mut FunctionDecl) -> VisitorResult {
         self.declare_local(&mut fun.id);
 
+        self.visit_lambda(fun)
+    }
+
+    fn visit_lambda(&mut self, fun: &mut FunctionDecl) -> VisitorResult {
         self.enter_scope(&fun.id.name);
 
         self.scope_mut().enter_block();
@@ -564,6 +547,32 @@ impl Visitor<'_> for Hoister {
         Ok(())
     }
 
+    fn visit_for_stmt(&mut self, stmt: &mut ForStmt) -> VisitorResult {
+        self.visit_expr(&mut stmt.iterable)?;
+
+        self.scope_mut().enter_block();
+
+        // the iterable and the position of the next item live in the two
+        // slots below the loop variable. Neither name is a valid identifier.
+        self.scope_mut().add_local("<iterable>");
+        self.scope_mut().add_local("<position>");
+        self.declare_local(&mut stmt.var);
+
+        for node in stmt.body.iter_mut() {
+            self.visit_stmt(node)?;
+        }
+
+        let locals = self.scope_mut().exit_block();
+
+        if locals.iter().any(|local| local.is_captured() && local.0 == stmt.var.name) {
+            stmt.var.scope = VarScope::Local(true);
+        }
+
+        self.capture_locals(locals, &mut stmt.body);
+
+        Ok(())
+    }
+
     fn visit_var_decl(
         &mut self,
         id: &mut Ident,
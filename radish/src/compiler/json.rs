@@ -0,0 +1,371 @@
+//! A JSON view of the AST, for editors and other external tools.
+//!
+//! The output is a single line. Every node is an object whose first key is
+//! `"type"`, naming the node after its [`Stmt`] or [`Expr`] variant, and whose
+//! second key is `"span"`, the node's `[start, end]` byte offsets into the
+//! source. The remaining keys, always in the order listed, are:
+//!
+//! | type                  | fields                                               |
+//! |-----------------------|------------------------------------------------------|
+//! | `Program`             | `body` (the root node, it has no span)               |
+//! | `BlockStmt`           | `body`                                               |
+//! | `ExpressionStmt`      | `expr`                                               |
+//! | `FunDeclaration`      | `name`, `params`, `body`                             |
+//! | `ConDeclaration`      | `name`, `params`, `body`                             |
+//! | `ClassDeclaration`    | `name`, `constructors`                               |
+//! | `VarDeclaration`      | `kind` (`"var"` or `"fin"`), `name`, `init`          |
+//! | `AssignmentStmt`      | `op`, `target`, `value`                              |
+//! | `IfStmt`              | `condition`, `body`, `else`                          |
+//! | `LoopStmt`            | `body`                                               |
+//! | `WhileStmt`           | `condition`, `body`                                  |
+//! | `ForStmt`             | `var`, `iterable`, `body`                            |
+//! | `ImportStmt`          | `path`, `items`                                      |
+//! | `BreakStmt`           | `value`                                              |
+//! | `ContinueStmt`        |                                                      |
+//! | `ReturnStmt`          | `value`                                              |
+//! | `PrintStmt`           | `expr`                                               |
+//! | `ArrayExpr`           | `elements`                                           |
+//! | `MapExpr`             | `entries` (`[key, value]` pairs)                     |
+//! | `Interpolation`       | `parts` (`String` nodes and embedded expressions)    |
+//! | `BinaryExpr`          | `op`, `op_span`, `lhs`, `rhs`                        |
+//! | `LogicalExpr`         | `op`, `op_span`, `lhs`, `rhs`                        |
+//! | `ParenExpr`           | `expr`                                               |
+//! | `UnaryExpr`           | `op`, `op_span`, `arg`                               |
+//! | `TernaryExpr`         | `condition`, `then`, `else`                          |
+//! | `LoopExpr`            | `loop`                                               |
+//! | `Lambda`              | `params`, `body`                                     |
+//! | `CallExpr`            | `callee`, `args`                                     |
+//! | `MemberExpr`          | `object`, `property`                                 |
+//! | `Identifier`          | `name`                                               |
+//! | `Number`, `Integer`   | `value`                                              |
+//! | `Bool`, `String`      | `value`                                              |
+//! | `Bytes`               | `value` (an array of numbers)                        |
+//! | `Nil`                 |                                                      |
+//! | `ListPattern`         | `items`                                              |
+//!
+//! Names (`name`, `params`, `var` and `items`) are `Identifier` nodes, except
+//! that a destructuring `VarDeclaration`'s `name` is a `ListPattern` of names.
+//! Operators are written as they appear in source, e.g. `"+"` or `"+="`.
+//! Missing optional children are `null`, as are numbers JSON can't represent.
+
+use crate::common::span::Span;
+use crate::compiler::ast::*;
+
+/// Serialize `ast` to JSON.
+pub fn to_json(ast: &AST) -> String {
+    format!("{{\"type\":\"Program\",\"body\":{}}}", stmts(&ast.items))
+}
+
+/// Builds a JSON object one field at a time.
+struct Object(String);
+
+impl Object {
+    fn new(node_type: &str, span: &Span) -> Object {
+        Object(format!(
+            "{{\"type\":{},\"span\":{}",
+            string(node_type),
+            span_json(span)
+        ))
+    }
+
+    fn field(mut self, key: &str, value: String) -> Object {
+        self.0.push(',');
+        self.0.push_str(&string(key));
+        self.0.push(':');
+        self.0.push_str(&value);
+        self
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+}
+
+/// A JSON string literal for `val`.
+pub(crate) fn string(val: &str) -> String {
+    let mut out = String::with_capacity(val.len() + 2);
+
+    out.push('"');
+
+    for ch in val.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+
+    out.push('"');
+
+    out
+}
+
+/// A JSON number, or `null` for numbers JSON can't represent.
+pub(crate) fn number(val: f64) -> String {
+    if val.is_finite() {
+        val.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn span_json(span: &Span) -> String {
+    format!("[{},{}]", span.start, span.end)
+}
+
+fn array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+fn stmts(body: &[Stmt]) -> String {
+    array(body.iter().map(stmt))
+}
+
+fn optional_expr(val: &Option<Expr>) -> String {
+    val.as_ref().map(expr).unwrap_or_else(|| "null".to_string())
+}
+
+fn ident(id: &Ident) -> String {
+    Object::new("Identifier", &id.pos)
+        .field("name", string(&id.name))
+        .finish()
+}
+
+fn pattern(target: &Pattern) -> String {
+    match target {
+        Pattern::Name(id) => ident(id),
+        Pattern::List(items, span) => Object::new("ListPattern", span)
+            .field("items", array(items.iter().map(pattern)))
+            .finish(),
+    }
+}
+
+fn idents(ids: &[Ident]) -> String {
+    array(ids.iter().map(ident))
+}
+
+fn function(node_type: &str, fun: &FunctionDecl, span: &Span) -> String {
+    let object = Object::new(node_type, span);
+
+    let object = if node_type == "Lambda" {
+        object
+    } else {
+        object.field("name", ident(&fun.id))
+    };
+
+    object
+        .field("params", idents(&fun.params))
+        .field("body", stmts(&fun.body))
+        .finish()
+}
+
+fn constructor(con: &ConstructorDecl, span: &Span) -> String {
+    Object::new("ConDeclaration", span)
+        .field("name", ident(&con.id))
+        .field("params", idents(&con.params))
+        .field("body", stmts(&con.body))
+        .finish()
+}
+
+fn assignment_op(op: &OpAssignment) -> &'static str {
+    match op {
+        OpAssignment::Equals => "=",
+        OpAssignment::AddAssign => "+=",
+        OpAssignment::SubAssign => "-=",
+        OpAssignment::MulAssign => "*=",
+        OpAssignment::DivAssign => "/=",
+        OpAssignment::RemAssign => "%=",
+    }
+}
+
+fn stmt(node: &Stmt) -> String {
+    let span = node.position();
+
+    match node {
+        Stmt::BlockStmt(body, _) => Object::new("BlockStmt", &span)
+            .field("body", stmts(body))
+            .finish(),
+        Stmt::ExpressionStmt(val) => Object::new("ExpressionStmt", &span)
+            .field("expr", expr(val))
+            .finish(),
+        Stmt::FunDeclaration(fun, _) => function("FunDeclaration", fun, &span),
+        Stmt::ConDeclaration(con, _) => constructor(con, &span),
+        Stmt::ClassDeclaration(class, _) => Object::new("ClassDeclaration", &span)
+            .field("name", ident(&class.id))
+            .field(
+                "constructors",
+                array(class.constructors.iter().map(|con| constructor(con, &con.id.pos))),
+            )
+            .finish(),
+        Stmt::VarDeclaration(id, init, kind, _) => {
+            let kind = match kind {
+                VarKind::Var => "var",
+                VarKind::Fin => "fin",
+            };
+
+            Object::new("VarDeclaration", &span)
+                .field("kind", string(kind))
+                .field("name", pattern(id))
+                .field("init", optional_expr(init))
+                .finish()
+        }
+        Stmt::AssignmentStmt(assignment, _) => Object::new("AssignmentStmt", &span)
+            .field("op", string(assignment_op(&assignment.op)))
+            .field("target", expr(&assignment.lhs))
+            .field("value", expr(&assignment.rhs))
+            .finish(),
+        Stmt::IfStmt(condition, body, alt, _) => Object::new("IfStmt", &span)
+            .field("condition", expr(condition))
+            .field("body", stmts(body))
+            .field(
+                "else",
+                alt.as_ref()
+                    .map(|alt| stmt(alt))
+                    .unwrap_or_else(|| "null".to_string()),
+            )
+            .finish(),
+        Stmt::LoopStmt(body, _) => Object::new("LoopStmt", &span)
+            .field("body", stmts(body))
+            .finish(),
+        Stmt::WhileStmt(condition, body, _) => Object::new("WhileStmt", &span)
+            .field("condition", expr(condition))
+            .field("body", stmts(body))
+            .finish(),
+        Stmt::ForStmt(for_stmt, _) => Object::new("ForStmt", &span)
+            .field("var", ident(&for_stmt.var))
+            .field("iterable", expr(&for_stmt.iterable))
+            .field("body", stmts(&for_stmt.body))
+            .finish(),
+        Stmt::ImportStmt(import) => Object::new("ImportStmt", &span)
+            .field("path", string(import.path()))
+            .field("items", idents(import.items()))
+            .finish(),
+        Stmt::BreakStmt(value, _) => Object::new("BreakStmt", &span)
+            .field("value", optional_expr(value))
+            .finish(),
+        Stmt::ContinueStmt(_) => Object::new("ContinueStmt", &span).finish(),
+        Stmt::ReturnStmt(value, _) => Object::new("ReturnStmt", &span)
+            .field("value", optional_expr(value))
+            .finish(),
+        Stmt::PrintStmt(val, _) => Object::new("PrintStmt", &span)
+            .field("expr", expr(val))
+            .finish(),
+    }
+}
+
+fn binary(node_type: &str, node: &BinaryExpr, span: &Span) -> String {
+    Object::new(node_type, span)
+        .field("op", string(&node.op.to_string()))
+        .field("op_span", span_json(&node.op_span))
+        .field("lhs", expr(&node.lhs))
+        .field("rhs", expr(&node.rhs))
+        .finish()
+}
+
+fn expr(node: &Expr) -> String {
+    let span = node.position();
+
+    match node {
+        Expr::ArrayExpr(elements, _) => Object::new("ArrayExpr", &span)
+            .field("elements", array(elements.iter().map(expr)))
+            .finish(),
+        Expr::MapExpr(values, _) => Object::new("MapExpr", &span)
+            .field(
+                "entries",
+                array(values.chunks(2).map(|entry| array(entry.iter().map(expr)))),
+            )
+            .finish(),
+        Expr::Interpolation(parts, _) => Object::new("Interpolation", &span)
+            .field("parts", array(parts.iter().map(expr)))
+            .finish(),
+        Expr::BinaryExpr(binary_expr, _) => binary("BinaryExpr", binary_expr, &span),
+        Expr::LogicalExpr(logical_expr, _) => binary("LogicalExpr", logical_expr, &span),
+        Expr::ParenExpr(inner, _) => Object::new("ParenExpr", &span)
+            .field("expr", expr(inner))
+            .finish(),
+        Expr::UnaryExpr(op, op_span, arg, _) => Object::new("UnaryExpr", &span)
+            .field("op", string(&op.to_string()))
+            .field("op_span", span_json(op_span))
+            .field("arg", expr(arg))
+            .finish(),
+        Expr::TernaryExpr(condition, then, alt, _) => Object::new("TernaryExpr", &span)
+            .field("condition", expr(condition))
+            .field("then", expr(then))
+            .field("else", expr(alt))
+            .finish(),
+        Expr::LoopExpr(loop_stmt, _) => Object::new("LoopExpr", &span)
+            .field("loop", stmt(loop_stmt))
+            .finish(),
+        Expr::Lambda(fun, _) => function("Lambda", fun, &span),
+        Expr::CallExpr(callee, args, _) => Object::new("CallExpr", &span)
+            .field("callee", expr(callee))
+            .field("args", array(args.iter().map(expr)))
+            .finish(),
+        Expr::MemberExpr(object, property, _) => Object::new("MemberExpr", &span)
+            .field("object", expr(object))
+            .field("property", expr(property))
+            .finish(),
+        Expr::Identifier(id) => ident(id),
+        Expr::Number(val, _) => Object::new("Number", &span)
+            .field("value", number(*val))
+            .finish(),
+        Expr::Integer(val, _) => Object::new("Integer", &span)
+            .field("value", val.to_string())
+            .finish(),
+        Expr::Bool(val, _) => Object::new("Bool", &span)
+            .field("value", val.to_string())
+            .finish(),
+        Expr::String(val, _) => Object::new("String", &span)
+            .field("value", string(val))
+            .finish(),
+        Expr::Bytes(val, _) => Object::new("Bytes", &span)
+            .field("value", array(val.iter().map(|byte| byte.to_string())))
+            .finish(),
+        Expr::Nil(_) => Object::new("Nil", &span).finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::source::Source, compiler::Parser};
+
+    use super::*;
+
+    fn json(src: &str) -> String {
+        to_json(&Parser::new(Source::new(src, "")).parse().unwrap())
+    }
+
+    #[test]
+    fn binary_expression() {
+        assert_eq!(
+            json("1 + 2"),
+            concat!(
+                r#"{"type":"Program","body":[{"type":"ExpressionStmt","span":[0,5],"expr":"#,
+                r#"{"type":"BinaryExpr","span":[0,5],"op":"+","op_span":[2,3],"#,
+                r#""lhs":{"type":"Integer","span":[0,1],"value":1},"#,
+                r#""rhs":{"type":"Integer","span":[4,5],"value":2}}}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn variable_declaration() {
+        assert_eq!(
+            json("var a"),
+            concat!(
+                r#"{"type":"Program","body":[{"type":"VarDeclaration","span":[0,5],"#,
+                r#""kind":"var","name":{"type":"Identifier","span":[4,5],"name":"a"},"init":null}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(string("a\"b\\\n\u{1}"), r#""a\"b\\\n\u0001""#);
+    }
+}
@@ -0,0 +1,327 @@
+//! Checks for code that is valid, but probably not what was meant. Unlike the
+//! other passes these never fail; they produce warnings instead.
+//!
+//! * Local variables that are never used.
+//! * `while` loops whose condition is the constant `false`, so the body never
+//!   runs, or `true` with nothing that leaves the loop.
+//! * Number literals whose value can't be stored exactly, because they're too
+//!   large or too small for a float.
+
+use std::collections::HashSet;
+
+use crate::common::Span;
+use crate::error::{Diagnostic, Label};
+
+use super::fold::constant_bool;
+use super::visitor::{walk_expr, VisitorResult};
+use super::{ClassDecl, Expr, ForStmt, FunctionDecl, Ident, Stmt, VarKind, Visitor, AST};
+
+/// Collect the warnings for `ast`, in the order they were found.
+pub fn lint(ast: &mut AST) -> Vec<Diagnostic> {
+    let mut linter = Linter::new();
+
+    for stmt in ast.items.iter_mut() {
+        // the linter itself never fails.
+        let _ = linter.visit_stmt(stmt);
+    }
+
+    linter.warnings
+}
+
+/// The local variables declared in a block, and every name used inside it.
+#[derive(Default)]
+struct Block {
+    declared: Vec<(String, Span)>,
+    used: HashSet<String>,
+}
+
+struct Linter {
+    /// The enclosing blocks, the first of which is the global scope.
+    blocks: Vec<Block>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn new() -> Self {
+        Self {
+            blocks: vec![Block::default()],
+            warnings: vec![],
+        }
+    }
+
+    fn current(&mut self) -> &mut Block {
+        self.blocks.last_mut().expect("the global block is never popped")
+    }
+
+    /// Leave the current block, warning about the variables declared in it
+    /// that were never mentioned. A name counts as mentioned if it appears
+    /// anywhere in the block, so shadowing can hide an unused variable.
+    fn end_block(&mut self) {
+        let block = self.blocks.pop().expect("the global block is never popped");
+
+        for (name, span) in block.declared {
+            if !block.used.contains(&name) && !name.starts_with('_') {
+                self.warnings.push(
+                    Diagnostic::warning()
+                        .with_message(format!("unused variable `{}`", name))
+                        .with_labels(vec![Label::primary(span).with_message("never used")])
+                        .with_notes(vec![format!(
+                            "if this is intentional, prefix it with an underscore: `_{}`",
+                            name
+                        )]),
+                );
+            }
+        }
+
+        self.current().used.extend(block.used);
+    }
+}
+
+impl<'a> Visitor<'a> for Linter {
+    fn visit_block_stmt(&mut self, block: &mut Vec<Stmt>) -> VisitorResult {
+        self.blocks.push(Block::default());
+
+        for stmt in block.iter_mut() {
+            self.visit_stmt(stmt)?;
+        }
+
+        self.end_block();
+
+        Ok(())
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) -> VisitorResult {
+        // integer literals are parsed exactly, or not at all.
+        let (value, span) = match expr {
+            Expr::Number(value, span) => (*value, span),
+            _ => return walk_expr(self, expr),
+        };
+
+        if !is_exact(span.text(), value) {
+            self.warnings.push(
+                Diagnostic::warning()
+                    .with_message("numeric literal is not representable exactly")
+                    .with_labels(vec![Label::primary(span.clone())
+                        .with_message(format!("`{}` is stored as `{}`", span.text(), value))]),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn visit_var_decl(
+        &mut self,
+        id: &mut Ident,
+        expr: &mut Option<Expr>,
+        _kind: VarKind,
+    ) -> VisitorResult {
+        if let Some(init) = expr {
+            self.visit_expr(init)?;
+        }
+
+        // globals may be used by code that isn't part of this AST, such as
+        // the REPL's next line or a module importing this one.
+        if self.blocks.len() > 1 {
+            self.current().declared.push((id.name.clone(), id.pos.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn visit_while_stmt(&mut self, condition: &mut Expr, body: &mut Vec<Stmt>) -> VisitorResult {
+        let warning = match constant_bool(condition) {
+            Some(false) => Some(("loop body never executes", "this condition is always false")),
+            Some(true) if !exits_loop(body) => {
+                Some(("infinite loop with no break", "this condition is always true"))
+            }
+            _ => None,
+        };
+
+        if let Some((message, label)) = warning {
+            self.warnings.push(
+                Diagnostic::warning()
+                    .with_message(message)
+                    .with_labels(vec![Label::primary(condition.position()).with_message(label)]),
+            );
+        }
+
+        self.visit_expr(condition)?;
+        self.visit_block_stmt(body)
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &mut ForStmt) -> VisitorResult {
+        // the loop variable is often only there to count iterations.
+        self.visit_expr(&mut stmt.iterable)?;
+        self.visit_block_stmt(&mut stmt.body)
+    }
+
+    fn visit_ident(&mut self, ident: &mut Ident) -> VisitorResult {
+        self.current().used.insert(ident.name.clone());
+        Ok(())
+    }
+}
+
+/// Whether the float literal `literal` has the value `value` exactly. Only
+/// literals whose digits were lost are inexact: ones too large or too small
+/// for a float. A fraction like `0.1` can't be stored exactly either, but is
+/// as close as a float can get.
+fn is_exact(literal: &str, value: f64) -> bool {
+    if !value.is_finite() {
+        return false;
+    }
+
+    // the mantissa had a non-zero digit, but the exponent took the value
+    // below the smallest float.
+    let mantissa = literal.split(['e', 'E']).next().unwrap_or(literal);
+    value != 0.0 || !mantissa.bytes().any(|b| (b'1'..=b'9').contains(&b))
+}
+
+/// Whether `body` contains a `break` or `return` that leaves the loop it's the
+/// body of. Those inside nested loops and functions don't count.
+fn exits_loop(body: &mut [Stmt]) -> bool {
+    let mut finder = ExitFinder { found: false };
+
+    for stmt in body.iter_mut() {
+        // the finder never fails.
+        let _ = finder.visit_stmt(stmt);
+    }
+
+    finder.found
+}
+
+struct ExitFinder {
+    found: bool,
+}
+
+impl<'a> Visitor<'a> for ExitFinder {
+    fn visit_break_stmt(&mut self, _value: &mut Option<Expr>) -> VisitorResult {
+        self.found = true;
+        Ok(())
+    }
+
+    fn visit_return_stmt(&mut self, _return_expr: &mut Option<Expr>) -> VisitorResult {
+        self.found = true;
+        Ok(())
+    }
+
+    fn visit_loop_stmt(&mut self, _body: &mut Vec<Stmt>) -> VisitorResult {
+        Ok(())
+    }
+
+    fn visit_while_stmt(&mut self, _condition: &mut Expr, _body: &mut Vec<Stmt>) -> VisitorResult {
+        Ok(())
+    }
+
+    fn visit_for_stmt(&mut self, _stmt: &mut ForStmt) -> VisitorResult {
+        Ok(())
+    }
+
+    fn visit_fun_decl(&mut self, _fun: &mut FunctionDecl) -> VisitorResult {
+        Ok(())
+    }
+
+    fn visit_lambda(&mut self, _fun: &mut FunctionDecl) -> VisitorResult {
+        Ok(())
+    }
+
+    fn visit_class_decl(&mut self, _class: &mut ClassDecl) -> VisitorResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::source::Source;
+    use crate::compiler::{pipeline::PipelineSettings, Parser};
+    use crate::error::diagnostic::Severity;
+
+    use super::*;
+
+    fn warnings(src: &str) -> Vec<Diagnostic> {
+        let mut ast = Parser::new(Source::new(src, "")).parse().unwrap();
+        lint(&mut ast)
+    }
+
+    #[test]
+    fn unused_local_variable() {
+        let warnings = warnings("fun f() {\n    var unused = 1\n    var used = 2\n    return used\n}\n");
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, Severity::Warning);
+        assert_eq!(warnings[0].message, "unused variable `unused`");
+        assert_eq!(warnings[0].labels[0].span.text(), "unused");
+    }
+
+    #[test]
+    fn no_warning_for_globals_captures_or_underscores() {
+        let src = "var global = 1\nfun f() {\n    var a = 1\n    var _b = 2\n    fun g() {\n        return a\n    }\n    return g\n}\n";
+
+        assert!(warnings(src).is_empty());
+    }
+
+    #[test]
+    fn while_false_never_executes() {
+        let warnings = warnings("while false loop\n    print 1\nendloop\n");
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "loop body never executes");
+        assert_eq!(warnings[0].labels[0].span.text(), "false");
+    }
+
+    #[test]
+    fn while_true_without_break() {
+        let src = "while true loop\n    print 1\n    while x loop\n        break\n    endloop\nendloop\n";
+        let warnings = warnings(src);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "infinite loop with no break");
+        assert_eq!(warnings[0].labels[0].span.text(), "true");
+    }
+
+    #[test]
+    fn while_true_with_a_way_out() {
+        let with_break = "while true loop\n    if x then\n        break\n    endif\nendloop\n";
+        let with_return = "fun f() {\n    while true loop\n        return 1\n    endloop\n}\n";
+
+        assert!(warnings(with_break).is_empty());
+        assert!(warnings(with_return).is_empty());
+    }
+
+    #[test]
+    fn no_warning_for_variable_condition() {
+        assert!(warnings("var i = 0\nwhile i < 3 loop\n    i += 1\nendloop\n").is_empty());
+    }
+
+    #[test]
+    fn inexact_number_literals() {
+        let src = "print 1e400\nprint -1e309\nprint 1e-400\n";
+        let warnings = warnings(src);
+
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0].message, "numeric literal is not representable exactly");
+        assert_eq!(warnings[0].labels[0].span.text(), "1e400");
+        assert_eq!(warnings[0].labels[0].message, "`1e400` is stored as `inf`");
+        assert_eq!(warnings[1].labels[0].span.text(), "1e309");
+        assert_eq!(warnings[2].labels[0].span.text(), "1e-400");
+    }
+
+    #[test]
+    fn number_literals_in_the_default_radix() {
+        let settings = PipelineSettings {
+            default_radix: 16,
+            ..PipelineSettings::default()
+        };
+        let mut ast = Parser::with_config(Source::new("print 10\nprint 0b11\n", ""), &settings)
+            .parse()
+            .unwrap();
+
+        assert!(lint(&mut ast).is_empty());
+    }
+
+    #[test]
+    fn no_warning_for_exact_number_literals() {
+        let src = "print 9007199254740993\nprint 0.1\nprint 1e300\nprint 0e-400\nprint 0x7fffffff\nprint 007\n";
+
+        assert!(warnings(src).is_empty());
+    }
+}
@@ -4,10 +4,15 @@ mod analysis;
 pub mod ast;
 pub mod codegen;
 pub mod error;
+mod fold;
+pub mod format;
+pub mod json;
+mod lint;
 pub mod parser;
 pub mod pipeline;
 pub mod scanner;
 pub mod scope;
+pub mod stream;
 pub mod token;
 mod validator;
 pub mod visitor;
@@ -23,5 +28,6 @@ pub use visitor::Visitor;
 pub use std::cell::RefCell;
 pub use std::rc::Rc;
 
-pub use analysis::resolve_symbols;
-pub use validator::validate_ast;
+pub use analysis::{resolve_symbols, resolve_symbols_without_builtins};
+pub use fold::{explain_fold_constants, Transformation};
+pub use validator::{validate_ast, validate_ast_strict};
@@ -12,14 +12,25 @@ use crate::common::{source::Source, span::Span};
 use crate::error::Item;
 
 use super::pipeline::PipelineSettings;
+use crate::config::{DEFAULT_MAX_NESTING_DEPTH, DEFAULT_MAX_NUMBER_LENGTH, DEFAULT_MAX_STATEMENTS};
 
 struct ParserSettings {
     pub dump_ast: bool,
+    pub max_number_length: usize,
+    pub default_radix: u32,
+    pub max_nesting_depth: usize,
+    pub max_statements: usize,
 }
 
 impl ParserSettings {
     pub fn new() -> Self {
-        Self { dump_ast: false }
+        Self {
+            dump_ast: false,
+            max_number_length: DEFAULT_MAX_NUMBER_LENGTH,
+            default_radix: 10,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_statements: DEFAULT_MAX_STATEMENTS,
+        }
     }
 }
 
@@ -33,6 +44,10 @@ impl From<&PipelineSettings> for ParserSettings {
     fn from(pipeline: &PipelineSettings) -> Self {
         Self {
             dump_ast: pipeline.dump_ast,
+            max_number_length: pipeline.max_number_length,
+            default_radix: pipeline.default_radix,
+            max_nesting_depth: pipeline.max_nesting_depth,
+            max_statements: pipeline.max_statements,
         }
     }
 }
@@ -43,6 +58,13 @@ pub struct Parser {
     scanner: Scanner,
     previous: Token,
     current: Token,
+    /// How many blocks and factors are currently being parsed inside each other.
+    depth: usize,
+    /// Errors reported by the scanner, in the order they were found.
+    errors: Vec<SyntaxError>,
+    /// The brackets, parentheses and braces of expressions that haven't been
+    /// closed yet, innermost last.
+    delimiters: Vec<Token>,
 }
 
 impl Parser {
@@ -53,6 +75,9 @@ impl Parser {
             scanner: Scanner::new(source),
             previous: Token::empty(),
             current: Token::empty(),
+            depth: 0,
+            errors: Vec::new(),
+            delimiters: Vec::new(),
         }
     }
 
@@ -60,16 +85,27 @@ impl Parser {
         Self {
             settings: ParserSettings::from(settings),
             source: Rc::clone(&source),
-            scanner: Scanner::new(source),
+            scanner: Scanner::new(source).with_unicode_identifiers(settings.unicode_identifiers),
             previous: Token::empty(),
             current: Token::empty(),
+            depth: 0,
+            errors: Vec::new(),
+            delimiters: Vec::new(),
         }
     }
 
     pub fn parse(&mut self) -> Result<AST, SyntaxError> {
         self.advance();
 
-        match self.parse_body() {
+        let result = self.parse_body();
+
+        // an invalid token is likely the cause of any other error, so it's
+        // reported first.
+        if let Some(err) = self.errors.first() {
+            return Err(err.clone());
+        }
+
+        match result {
             Ok(items) => {
                 if self.settings.dump_ast {
                     println!("{:#?}", &items);
@@ -80,8 +116,83 @@ impl Parser {
         }
     }
 
+    /// Parse `source` as a single expression, such as an argument given on
+    /// the command line. Surrounding blank lines and comments are allowed,
+    /// anything else after the expression is an error.
+    pub fn parse_expression(source: Rc<Source>) -> Result<Expr, Vec<SyntaxError>> {
+        let mut parser = Parser::new(source);
+        parser.advance();
+
+        parser.skip_blank_lines();
+
+        let result = parser.parse_expr().and_then(|expr| {
+            parser.skip_blank_lines();
+
+            if parser.check(&TokenType::Eof) {
+                Ok(expr)
+            } else {
+                let actual = Item::new(&parser.current.span, parser.current.syntax());
+                Err(parser.error(SyntaxErrorKind::ExpectedEof { actual }))
+            }
+        });
+
+        // an invalid token is likely the cause of any other error.
+        if !parser.errors.is_empty() {
+            return Err(parser.errors);
+        }
+
+        result.map_err(|err| vec![err])
+    }
+
+    /// Whether `source` is valid code that has been cut short, such as an
+    /// unclosed paren or a trailing operator, as opposed to code with a syntax
+    /// error that more input can't fix. Complete code isn't incomplete.
+    pub fn is_incomplete(source: &str) -> bool {
+        match Parser::new(Source::new(source, "")).parse() {
+            Ok(_) => false,
+            Err(err) => err.is_unexpected_eof(),
+        }
+    }
+
+    /// Errors found by the scanner so far.
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
     fn advance(&mut self) {
-        self.previous = mem::replace(&mut self.current, self.scanner.scan_token());
+        let mut token = self.scanner.scan_token();
+
+        // record invalid tokens and skip over them, so that parsing can carry on.
+        while let TokenType::Error(message) = &token.token_type {
+            let text = token.span.text();
+
+            let message = if **message == *text {
+                format!("unexpected character `{}`", text)
+            } else {
+                // the scanner's messages are sentences, diagnostics start in
+                // lower case. The rest is left alone, since it can quote source.
+                lowercase_first(message)
+            };
+
+            self.errors.push(SyntaxError::new(SyntaxErrorKind::InvalidToken {
+                item: Item::new(&token.span, text),
+                message,
+            }));
+
+            token = self.scanner.scan_token();
+        }
+
+        self.previous = mem::replace(&mut self.current, token);
+    }
+
+    /// Skip newlines and single-line comments.
+    fn skip_blank_lines(&mut self) {
+        while matches!(
+            self.current.token_type,
+            TokenType::Newline | TokenType::Comment(_, false)
+        ) {
+            self.advance();
+        }
     }
 
     fn check(&self, token_type: &TokenType) -> bool {
@@ -110,11 +221,25 @@ impl Parser {
     }
 
     fn error(&mut self, err_kind: SyntaxErrorKind) -> SyntaxError {
-        SyntaxError::new(err_kind)
+        let err = SyntaxError::new(err_kind);
+
+        // an error at the end of the input means that the input was cut short,
+        // rather than it being wrong.
+        if self.current.token_type == TokenType::Eof {
+            err.set_cause(SyntaxError::new(SyntaxErrorKind::UnexpectedEof {
+                location: self.current.span.clone(),
+            }))
+        } else {
+            err
+        }
     }
 
     fn expect(&mut self, expected: TokenType) -> Result<(), SyntaxError> {
         if !self.match_token(&expected) {
+            if let Some(err) = self.mismatched_delimiter() {
+                return Err(err);
+            }
+
             let actual = self.current.clone();
 
             let err_kind = SyntaxErrorKind::Expected {
@@ -122,14 +247,63 @@ impl Parser {
                 actual: Item::new(&actual.span, actual.syntax()),
             };
 
-            let err = SyntaxError::new(err_kind);
-
-            Err(err)
+            Err(self.error(err_kind))
         } else {
             Ok(())
         }
     }
 
+    /// Expect an opening delimiter, remembering it until the matching
+    /// [`close_delimiter()`](Self::close_delimiter).
+    fn open_delimiter(&mut self, opening: TokenType) -> Result<(), SyntaxError> {
+        self.expect(opening)?;
+        self.delimiters.push(self.previous.clone());
+
+        Ok(())
+    }
+
+    /// Expect the delimiter that closes the innermost open one.
+    fn close_delimiter(&mut self, closing: TokenType) -> Result<(), SyntaxError> {
+        self.expect(closing)?;
+        self.delimiters.pop();
+
+        Ok(())
+    }
+
+    /// The error for the current token if it's a closing delimiter that
+    /// doesn't match the innermost open one, e.g. the `}` in `(1 }`.
+    fn mismatched_delimiter(&mut self) -> Option<SyntaxError> {
+        if !matches!(
+            self.current.token_type,
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace
+        ) {
+            return None;
+        }
+
+        let opening = self.delimiters.last()?.clone();
+        let expected = opening.token_type.closing_delimiter()?;
+
+        if expected == self.current.token_type {
+            return None;
+        }
+
+        let closing = self.current.clone();
+        Some(self.mismatched_delimiter_error(&opening, &closing, &expected))
+    }
+
+    fn mismatched_delimiter_error(
+        &mut self,
+        opening: &Token,
+        closing: &Token,
+        expected: &TokenType,
+    ) -> SyntaxError {
+        self.error(SyntaxErrorKind::MismatchedDelimiter {
+            first: Item::new(&opening.span, opening.syntax()),
+            second: Item::new(&closing.span, closing.syntax()),
+            expected: expected.literal_syntax(),
+        })
+    }
+
     fn parse_body(&mut self) -> Result<Vec<Stmt>, SyntaxError> {
         let mut items = vec![];
 
@@ -147,13 +321,56 @@ impl Parser {
                     continue;
                 }
                 // ...
+                _ if items.len() >= self.settings.max_statements => {
+                    let current = self.current.clone();
+
+                    return Err(self.error(SyntaxErrorKind::TooManyStatements {
+                        item: Item::new(&current.span, current.syntax()),
+                        max: self.settings.max_statements,
+                    }));
+                }
                 _ => items.push(self.parse_statement()?),
             }
         }
     }
 
+    /// Run `parse` one level deeper, failing once the configured maximum
+    /// nesting depth is reached rather than overflowing the stack.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, SyntaxError>) -> Result<T, SyntaxError> {
+        let depth = self.depth;
+
+        self.descend()?;
+        let result = parse(self);
+        self.depth = depth;
+
+        result
+    }
+
+    /// Go one level deeper, failing once the configured maximum nesting depth
+    /// is reached. Syntax that is parsed in a loop but nests in the AST, which
+    /// later passes recurse over, descends once per iteration and restores
+    /// the depth when it's done.
+    fn descend(&mut self) -> Result<(), SyntaxError> {
+        if self.depth >= self.settings.max_nesting_depth {
+            let current = self.current.clone();
+
+            return Err(self.error(SyntaxErrorKind::NestingTooDeep {
+                item: Item::new(&current.span, current.syntax()),
+                max: self.settings.max_nesting_depth,
+            }));
+        }
+
+        self.depth += 1;
+
+        Ok(())
+    }
+
     /// Parse everything up to, but not including, a delimiter.
     fn parse_block(&mut self) -> Result<Vec<Stmt>, SyntaxError> {
+        self.nested(Self::parse_nested_block)
+    }
+
+    fn parse_nested_block(&mut self) -> Result<Vec<Stmt>, SyntaxError> {
         let opening_delimiter = self.previous.clone();
 
         let mut body = vec![];
@@ -177,18 +394,14 @@ impl Parser {
             (TokenType::LeftBrace, TokenType::RightBrace)
             | (TokenType::Then, TokenType::EndIf)
             | (TokenType::Then, TokenType::Else)
+            | (TokenType::Then, TokenType::Elif)
             | (TokenType::Else, TokenType::EndIf)
             | (TokenType::Loop, TokenType::EndLoop)
             | (TokenType::LeftParen, TokenType::RightParen) => Ok(body),
-            _ => {
-                let err_kind = SyntaxErrorKind::MismatchedDelimiter {
-                    first: Item::new(&opening_delimiter.span, opening_delimiter.syntax()),
-                    second: Item::new(&closing_delimiter.span, closing_delimiter.syntax()),
-                };
-
-                let err = self.error(err_kind);
+            (opening, _) => {
+                let expected = opening.closing_delimiter().unwrap_or(TokenType::Eof);
 
-                Err(err)
+                Err(self.mismatched_delimiter_error(&opening_delimiter, &closing_delimiter, &expected))
             }
         }
     }
@@ -222,6 +435,8 @@ impl Parser {
             TokenType::Loop => self.parse_loop_statement(),
             // while
             TokenType::While => self.parse_while_statement(),
+            // for
+            TokenType::For => self.parse_for_statement(),
             // import
             TokenType::Import => self.parse_import_statement(),
             // return
@@ -245,6 +460,14 @@ impl Parser {
         // fun ...
         self.consume(TokenType::Fun);
 
+        // fun '(' ... is an anonymous function used as a statement.
+        if self.check(&TokenType::LeftParen) {
+            let lambda = self.parse_lambda_body(start)?;
+            let expr = self.parse_member_tail(lambda)?;
+
+            return Ok(AST::expr_stmt(Box::new(expr)));
+        }
+
         // fun id ...
         let id = self.parse_identifier()?;
 
@@ -335,15 +558,15 @@ impl Parser {
             self.consume(TokenType::Var);
         }
 
-        // var|fin id ...
-        let id = self.parse_identifier()?;
+        // var|fin <pattern> ...
+        let target = self.parse_pattern()?;
 
         let current = &self.current;
 
         let (init, span) = match current.token_type {
             TokenType::Equals => {
                 self.consume(TokenType::Equals);
-                let init = self.parse_expression()?;
+                let init = self.parse_expr()?;
                 let span = Span::combine(&start, &init.position());
                 (Some(init), span)
             }
@@ -352,49 +575,120 @@ impl Parser {
 
         let var_kind = if constant { VarKind::Fin } else { VarKind::Var };
 
-        Ok(AST::var_decl(id, init, var_kind, span))
+        Ok(AST::var_decl(target, init, var_kind, span))
     }
 
+    /// Parse the target of a variable declaration, either a name or a
+    /// bracketed list of patterns.
+    fn parse_pattern(&mut self) -> Result<Pattern, SyntaxError> {
+        if !self.check(&TokenType::LeftBracket) {
+            return Ok(Pattern::Name(self.parse_identifier()?));
+        }
+
+        let start = self.current.span.clone();
+        let mut items = vec![];
+
+        // [ ...
+        self.open_delimiter(TokenType::LeftBracket)?;
+
+        // [ a, [b, c] ...
+        if !self.check(&TokenType::RightBracket) {
+            loop {
+                items.push(self.parse_pattern()?);
+
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        // [ ... ]
+        self.close_delimiter(TokenType::RightBracket)?;
+
+        Ok(Pattern::List(items, Span::combine(&start, &self.previous.span)))
+    }
+
+    /// Parse an `if` along with any `else if` (or `elif`) branches that follow
+    /// it. The chain is parsed in a loop rather than recursively, and each
+    /// branch becomes an `IfStmt` directly in the else branch of the one
+    /// before it, spanning from its own `if` to the shared `endif`. Since the
+    /// branches nest, each one counts as a level of nesting.
     fn parse_if_statement(&mut self) -> Result<Stmt, SyntaxError> {
-        // if ...
-        let start = Span::from(&self.current.span);
-        self.consume(TokenType::If);
+        let depth = self.depth;
+        let stmt = self.parse_if_chain();
+        self.depth = depth;
 
-        // if <expr> ...
-        let expr = self.parse_expression()?;
+        stmt
+    }
 
-        // if <expr> then ...
-        self.expect(TokenType::Then)?;
+    fn parse_if_chain(&mut self) -> Result<Stmt, SyntaxError> {
+        let mut branches = vec![self.parse_if_branch()?];
+
+        let alt = loop {
+            if self.check(&TokenType::Elif) {
+                // ... elif ...
+                self.descend()?;
+                branches.push(self.parse_if_branch()?);
+            } else if self.check(&TokenType::Else) {
+                let else_span = Span::from(&self.current.span);
+                self.consume(TokenType::Else);
+
+                if self.check(&TokenType::If) {
+                    // ... else if ...
+                    self.descend()?;
+                    branches.push(self.parse_if_branch()?);
+                    continue;
+                }
 
-        // if <expr> then <block> ...
-        let block = self.parse_block()?;
+                // ... else <block> endif
+                let alternate = self.parse_block()?;
+                self.expect(TokenType::EndIf)?;
 
-        let alt = if self.match_token(&TokenType::Else) {
-            // if <expr> then <block> else ...
-            if self.check(&TokenType::If) {
-                // if <expr> then <block> else if ...
-                Some(Box::new(self.parse_if_statement()?))
+                let span = Span::combine(&else_span, &self.current.span);
+                break Some(Box::new(AST::block_stmt(alternate, span)));
             } else {
-                // if <expr> then <block> else <block> endif
-                let alternate = self.parse_block()?;
+                // ... endif
                 self.expect(TokenType::EndIf)?;
-                Some(Box::new(AST::block_stmt(
-                    alternate,
-                    Span::combine(&start, &self.current.span),
-                )))
+                break None;
             }
-        } else {
-            // if <expr> then <block> endif
-            self.expect(TokenType::EndIf)?;
-            None
         };
 
-        Ok(AST::if_stmt(
-            expr,
-            block,
-            alt,
-            Span::combine(&start, &self.current.span),
-        ))
+        let end = self.current.span.clone();
+
+        let chain = branches.into_iter().rev().fold(alt, |alt, (start, expr, block)| {
+            Some(Box::new(AST::if_stmt(expr, block, alt, Span::combine(&start, &end))))
+        });
+
+        Ok(*chain.expect("an if statement has at least one branch"))
+    }
+
+    /// Parse `if <expr> then <block>`, or the same starting with `elif`,
+    /// returning where it starts, the condition and the block.
+    fn parse_if_branch(&mut self) -> Result<(Span, Expr, Vec<Stmt>), SyntaxError> {
+        let start = Span::from(&self.current.span);
+        self.consume(self.current.token_type.clone());
+
+        let expr = self.parse_condition()?;
+        self.expect(TokenType::Then)?;
+        let block = self.parse_block()?;
+
+        Ok((start, expr, block))
+    }
+
+    /// Parse the condition of an `if` or `while`. An `=` following it is
+    /// reported as such, since it's almost certainly a mistyped `==`.
+    fn parse_condition(&mut self) -> Result<Expr, SyntaxError> {
+        let condition = self.parse_expr()?;
+
+        if self.check(&TokenType::Equals) {
+            let equals = self.current.clone();
+
+            return Err(self.error(SyntaxErrorKind::AssignmentInCondition {
+                equals: Item::new(&equals.span, equals.syntax()),
+            }));
+        }
+
+        Ok(condition)
     }
 
     fn parse_loop_statement(&mut self) -> Result<Stmt, SyntaxError> {
@@ -422,7 +716,7 @@ impl Parser {
         self.consume(TokenType::While);
 
         // while <expr> ...
-        let condition = self.parse_expression()?;
+        let condition = self.parse_condition()?;
 
         // while <expr> loop ...
         self.expect(TokenType::Loop)?;
@@ -440,6 +734,36 @@ impl Parser {
         ))
     }
 
+    fn parse_for_statement(&mut self) -> Result<Stmt, SyntaxError> {
+        let start = self.current.span.clone();
+
+        // for ...
+        self.consume(TokenType::For);
+
+        // for <ident> ...
+        let var = self.parse_identifier()?;
+
+        // for <ident> in <expr> ...
+        self.expect(TokenType::In)?;
+        let iterable = self.parse_expr()?;
+
+        // for <ident> in <expr> loop ...
+        self.expect(TokenType::Loop)?;
+
+        // for <ident> in <expr> loop <body> ...
+        let loop_body = self.parse_block()?;
+
+        // for <ident> in <expr> loop <body> endloop
+        self.expect(TokenType::EndLoop)?;
+
+        Ok(AST::for_stmt(
+            var,
+            iterable,
+            loop_body,
+            Span::combine(&start, &self.current.span),
+        ))
+    }
+
     fn parse_import_statement(&mut self) -> Result<Stmt, SyntaxError> {
         let start = self.current.span.clone();
 
@@ -475,7 +799,22 @@ impl Parser {
         // Todo: should be able to break to a label.
         self.consume(TokenType::Break);
 
-        Ok(AST::break_stmt(Span::from(&self.previous.span)))
+        let start = Span::from(&self.previous.span);
+
+        let (value, span) = match self.current.token_type {
+            // break '\n'
+            TokenType::Newline | TokenType::Eof | TokenType::Comment(..) => (None, start),
+            // break 'endloop'
+            _ if self.current.is_delimiter() => (None, start),
+            // break <expr>
+            _ => {
+                let val = self.parse_expr()?;
+                let span = Span::combine(&start, &val.position());
+                (Some(val), span)
+            }
+        };
+
+        Ok(AST::break_stmt(value, span))
     }
 
     fn parse_continue_statement(&mut self) -> Result<Stmt, SyntaxError> {
@@ -496,7 +835,7 @@ impl Parser {
             TokenType::Newline => (None, start),
             // return <expr>
             _ => {
-                let val = self.parse_expression()?;
+                let val = self.parse_expr()?;
                 let span = Span::combine(&start, &val.position());
                 (Some(val), span)
             }
@@ -511,18 +850,18 @@ impl Parser {
         self.consume(TokenType::Print);
 
         // "print" <expr>
-        let expr = self.parse_expression()?;
+        let expr = self.parse_expr()?;
         let end = &expr.position();
 
         Ok(AST::print_stmt(expr, Span::combine(&start, end)))
     }
 
     fn parse_expression_statement(&mut self) -> Result<Stmt, SyntaxError> {
-        Ok(AST::expr_stmt(Box::new(self.parse_expression()?)))
+        Ok(AST::expr_stmt(Box::new(self.parse_expr()?)))
     }
 
     fn parse_assignment_statement(&mut self) -> Result<Stmt, SyntaxError> {
-        let lhs = self.parse_expression()?;
+        let lhs = self.parse_expr()?;
 
         let op = match self.current.token_type {
             // expr = ...
@@ -560,7 +899,7 @@ impl Parser {
         };
 
         // id op ....
-        let rhs = self.parse_expression()?;
+        let rhs = self.parse_expr()?;
 
         let span = Span::combine(&lhs.position(), &rhs.position());
         
@@ -569,8 +908,8 @@ impl Parser {
         Ok(AST::assignment(stmt, span))
     }
 
-    fn parse_expression(&mut self) -> Result<Expr, SyntaxError> {
-        match self.parse_boolean_expression() {
+    fn parse_expr(&mut self) -> Result<Expr, SyntaxError> {
+        match self.parse_ternary() {
             Ok(expr) => Ok(expr),
             Err(err) => match err.kind {
                 SyntaxErrorKind::Unexpected { found } => {
@@ -590,16 +929,75 @@ impl Parser {
         }
     }
 
+    fn parse_ternary(&mut self) -> Result<Expr, SyntaxError> {
+        let condition = self.parse_pipe()?;
+
+        if !self.match_token(&TokenType::Question) {
+            return Ok(condition);
+        }
+
+        // cond ? ...
+        let then = self.nested(Self::parse_ternary)?;
+
+        self.expect(TokenType::Colon)?;
+
+        // cond ? expr : ...
+        let alt = self.nested(Self::parse_ternary)?;
+
+        let span = Span::combine(&condition.position(), &alt.position());
+        Ok(AST::ternary_expr(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(alt),
+            span,
+        ))
+    }
+
+    /// Parse a pipe, which binds more loosely than any other binary operator
+    /// and is left-associative. A pipe is just another way to write a call:
+    /// `x |> f` is `f(x)`, and when the right side is already a call the
+    /// value is passed as its first argument, so `x |> f(y)` is `f(x, y)`.
+    fn parse_pipe(&mut self) -> Result<Expr, SyntaxError> {
+        let depth = self.depth;
+        let expr = self.parse_pipe_chain();
+        self.depth = depth;
+
+        expr
+    }
+
+    fn parse_pipe_chain(&mut self) -> Result<Expr, SyntaxError> {
+        let mut node = self.parse_boolean_expression()?;
+
+        while self.match_token(&TokenType::Pipe) {
+            // each stage nests the chain so far one level deeper.
+            self.descend()?;
+
+            let right = self.parse_boolean_expression()?;
+            let span = Span::combine(&node.position(), &right.position());
+
+            node = match right {
+                Expr::CallExpr(callee, mut args, _) => {
+                    args.insert(0, node);
+                    AST::call_expr(callee, args, span)
+                }
+                callee => AST::call_expr(Box::new(callee), vec![node], span),
+            };
+        }
+
+        Ok(node)
+    }
+
     fn parse_boolean_expression(&mut self) -> Result<Expr, SyntaxError> {
         let mut node = self.parse_boolean_term()?;
 
         while let TokenType::Or = self.current.token_type {
             self.consume(TokenType::Or);
+            let op_span = Span::from(&self.previous.span);
 
             let right = self.parse_boolean_term()?;
 
             let span = Span::combine(&node.position(), &right.position());
-            node = AST::logical_expr(Box::new(BinaryExpr::new(Op::Or, node, right)), span)
+            node = AST::logical_expr(Box::new(BinaryExpr::new(Op::Or, op_span, node, right)), span)
         }
 
         Ok(node)
@@ -610,11 +1008,12 @@ impl Parser {
 
         while let TokenType::And = self.current.token_type {
             self.consume(TokenType::And);
+            let op_span = Span::from(&self.previous.span);
 
             let right = self.parse_boolean_factor()?;
 
             let span = Span::combine(&node.position(), &right.position());
-            node = AST::logical_expr(Box::new(BinaryExpr::new(Op::And, node, right)), span)
+            node = AST::logical_expr(Box::new(BinaryExpr::new(Op::And, op_span, node, right)), span)
         }
 
         Ok(node)
@@ -628,67 +1027,73 @@ impl Parser {
                 // expr < ...
                 TokenType::LessThan => {
                     self.consume(TokenType::LessThan);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::LessThan, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::LessThan, op_span, node, right)), span)
                 }
                 // expr <= ...
                 TokenType::LessThanEquals => {
                     self.consume(TokenType::LessThanEquals);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node = AST::binary_expr(
-                        Box::new(BinaryExpr::new(Op::LessThanEquals, node, right)),
+                        Box::new(BinaryExpr::new(Op::LessThanEquals, op_span, node, right)),
                         span,
                     )
                 }
                 // expr > ...
                 TokenType::GreaterThan => {
                     self.consume(TokenType::GreaterThan);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node = AST::binary_expr(
-                        Box::new(BinaryExpr::new(Op::GreaterThan, node, right)),
+                        Box::new(BinaryExpr::new(Op::GreaterThan, op_span, node, right)),
                         span,
                     )
                 }
                 // expr >= ...
                 TokenType::GreaterThanEquals => {
                     self.consume(TokenType::GreaterThanEquals);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node = AST::binary_expr(
-                        Box::new(BinaryExpr::new(Op::GreaterThanEquals, node, right)),
+                        Box::new(BinaryExpr::new(Op::GreaterThanEquals, op_span, node, right)),
                         span,
                     )
                 }
                 // expr == ...
                 TokenType::EqualsTo => {
                     self.consume(TokenType::EqualsTo);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
                     let span = Span::combine(&node.position(), &right.position());
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::EqualsTo, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::EqualsTo, op_span, node, right)), span)
                 }
                 // expr != ...
                 TokenType::NotEqual => {
                     self.consume(TokenType::NotEqual);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_sum()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::NotEqual, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::NotEqual, op_span, node, right)), span)
                 }
                 _ => break,
             }
@@ -706,22 +1111,24 @@ impl Parser {
                 // expr + ...
                 TokenType::Plus => {
                     self.consume(TokenType::Plus);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_term()?;
 
                     let span = Span::combine(&node.position(), &right.position());
-                    node = AST::binary_expr(Box::new(BinaryExpr::new(Op::Add, node, right)), span)
+                    node = AST::binary_expr(Box::new(BinaryExpr::new(Op::Add, op_span, node, right)), span)
                 }
                 // expr - ...
                 TokenType::Minus => {
                     self.consume(TokenType::Minus);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_term()?;
 
                     let span = Span::combine(&node.position(), &right.position());
 
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Subtract, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Subtract, op_span, node, right)), span)
                 }
                 _ => break,
             }
@@ -739,32 +1146,35 @@ impl Parser {
                 // expr * ...
                 TokenType::Star => {
                     self.consume(TokenType::Star);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_member()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Multiply, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Multiply, op_span, node, right)), span)
                 }
                 // expr / ...
                 TokenType::Slash => {
                     self.consume(TokenType::Slash);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_member()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node =
-                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Divide, node, right)), span)
+                        AST::binary_expr(Box::new(BinaryExpr::new(Op::Divide, op_span, node, right)), span)
                 }
                 // expr % ...
                 TokenType::Percent => {
                     self.consume(TokenType::Percent);
+                    let op_span = Span::from(&self.previous.span);
 
                     let right = self.parse_member()?;
 
                     let span = Span::combine(&node.position(), &right.position());
                     node = AST::binary_expr(
-                        Box::new(BinaryExpr::new(Op::Remainder, node, right)),
+                        Box::new(BinaryExpr::new(Op::Remainder, op_span, node, right)),
                         span,
                     )
                 }
@@ -776,29 +1186,36 @@ impl Parser {
     }
 
     fn parse_member(&mut self) -> Result<Expr, SyntaxError> {
-        let mut node = self.parse_factor()?;
+        let node = self.parse_factor()?;
+
+        self.parse_member_tail(node)
+    }
 
+    /// Parse any calls, field accesses, or indexes following `node`.
+    fn parse_member_tail(&mut self, mut node: Expr) -> Result<Expr, SyntaxError> {
         loop {
             match &self.current.token_type {
                 // <expr> '(' ...
                 TokenType::LeftParen => {
                     let args = self.parse_arg_list()?;
-                    let span = Span::combine(&node.position(), &self.current.span);
+                    let span = Span::combine(&node.position(), &self.previous.span);
                     node = AST::call_expr(Box::new(node), args, span)
                 }
                 // <expr> '.' ...
                 TokenType::Dot => {
                     self.consume(TokenType::Dot);
-                    let property = Box::new(self.parse_factor()?);
-                    let span = Span::combine(&node.position(), &self.current.span);
+                    // the property is a name, not a variable to be resolved.
+                    let id = self.parse_identifier()?;
+                    let property = Box::new(AST::string(id.name, id.pos));
+                    let span = Span::combine(&node.position(), &self.previous.span);
                     node = AST::member_expr(Box::new(node), property, span)
                 }
                 // <expr> '[' ...
                 TokenType::LeftBracket => {
-                    self.consume(TokenType::LeftBracket);
+                    self.open_delimiter(TokenType::LeftBracket)?;
                     let property = Box::new(self.parse_sum()?);
-                    self.consume(TokenType::RightBracket);
-                    let span = Span::combine(&node.position(), &self.current.span);
+                    self.close_delimiter(TokenType::RightBracket)?;
+                    let span = Span::combine(&node.position(), &self.previous.span);
                     node = AST::member_expr(Box::new(node), property, span)
                 }
                 _ => break,
@@ -809,6 +1226,10 @@ impl Parser {
     }
 
     fn parse_factor(&mut self) -> Result<Expr, SyntaxError> {
+        self.nested(Self::parse_nested_factor)
+    }
+
+    fn parse_nested_factor(&mut self) -> Result<Expr, SyntaxError> {
         loop {
             let current = self.current.clone();
 
@@ -816,7 +1237,18 @@ impl Parser {
                 // <number>
                 TokenType::Number(val) => {
                     let span = Span::from(&current.span);
-                    let node = AST::number(val, span);
+
+                    if span.end - span.start > self.settings.max_number_length {
+                        return Err(self.error(SyntaxErrorKind::NumberTooLong {
+                            literal: Item::new(&span, span.text()),
+                            max: self.settings.max_number_length,
+                        }));
+                    }
+
+                    let node = match self.integer_literal(&span)? {
+                        Some(value) => AST::integer(value, span),
+                        None => AST::number(val, span),
+                    };
                     self.consume(TokenType::Number(val));
                     return Ok(node);
                 }
@@ -827,8 +1259,31 @@ impl Parser {
                     self.consume(TokenType::String(val));
                     return Ok(node);
                 }
+                // "...{ <expr> }..."
+                TokenType::InterpolationStart(_) => return self.parse_interpolation(),
+                // <bytes>
+                TokenType::Bytes(val) => {
+                    let span = Span::from(&current.span);
+                    let node = AST::bytes(val.to_vec(), span);
+                    self.consume(TokenType::Bytes(val));
+                    return Ok(node);
+                }
                 // ( ...
                 TokenType::LeftParen => return self.parse_paren(),
+                // fun ...
+                TokenType::Fun => return self.parse_lambda(),
+                // loop ...
+                TokenType::Loop => {
+                    let stmt = self.parse_loop_statement()?;
+                    let span = stmt.position();
+                    return Ok(AST::loop_expr(stmt, span));
+                }
+                // while ...
+                TokenType::While => {
+                    let stmt = self.parse_while_statement()?;
+                    let span = stmt.position();
+                    return Ok(AST::loop_expr(stmt, span));
+                }
                 // [
                 TokenType::LeftBracket => return self.parse_array_literal(),
                 // {
@@ -836,20 +1291,24 @@ impl Parser {
                 // - ...
                 TokenType::Minus => {
                     self.consume(TokenType::Minus);
-                    let arg = self.parse_factor()?;
+                    let op_span = Span::from(&current.span);
+                    // the operand includes any calls or indexes, so `-a[0]`
+                    // is `-(a[0])`.
+                    let arg = self.parse_member()?;
                     let span = Span::combine(&current.span, &arg.position());
 
-                    let node = AST::unary_expr(Op::Subtract, Box::new(arg), span);
+                    let node = AST::unary_expr(Op::Subtract, op_span, Box::new(arg), span);
                     return Ok(node);
                 }
-                // ! ...
-                TokenType::Bang => {
-                    self.consume(TokenType::Bang);
+                // ! ... | not ...
+                TokenType::Bang | TokenType::Not => {
+                    self.consume(current.token_type.clone());
+                    let op_span = Span::from(&current.span);
 
-                    let arg = self.parse_factor()?;
+                    let arg = self.parse_member()?;
                     let span = Span::combine(&current.span, &arg.position());
 
-                    let node = AST::unary_expr(Op::Bang, Box::new(arg), span);
+                    let node = AST::unary_expr(Op::Bang, op_span, Box::new(arg), span);
                     return Ok(node);
                 }
                 // "true"
@@ -884,8 +1343,8 @@ impl Parser {
                 }
                 // <eof>
                 TokenType::Eof => {
-                    // if an <eof> token is found here, it has to be an error.
-                    return Err(self.error(SyntaxErrorKind::UnexpectedEof {
+                    // if an <eof> token is found here, the input ended mid-expression.
+                    return Err(self.error(SyntaxErrorKind::ExpectedExpressionAtEof {
                         location: self.current.span.clone(),
                     }));
                 }
@@ -899,12 +1358,6 @@ impl Parser {
                     self.advance();
                     continue;
                 }
-                // lexer error
-                TokenType::Error(err) => {
-                    let message = err.to_string();
-                    let err = self.error(SyntaxErrorKind::Custom { message });
-                    return Err(err);
-                }
                 _ => {
                     let current = self.current.clone();
                     let err_kind = SyntaxErrorKind::Unexpected {
@@ -917,6 +1370,37 @@ impl Parser {
         }
     }
 
+    /// The value of the number literal `literal` if it was written as an
+    /// integer, rather than with a decimal point or an exponent. Integers
+    /// without a radix prefix are read in the default radix, and the digits
+    /// are parsed directly, so that they aren't rounded through a float.
+    fn integer_literal(&mut self, literal: &Span) -> Result<Option<i64>, SyntaxError> {
+        let text = literal.text();
+
+        let (radix, digits) = match text.get(..2) {
+            Some("0b") | Some("0B") => (2, &text[2..]),
+            Some("0o") | Some("0O") => (8, &text[2..]),
+            Some("0x") | Some("0X") => (16, &text[2..]),
+            _ if text.bytes().all(|b| b.is_ascii_digit()) => (self.settings.default_radix, text),
+            _ => return Ok(None),
+        };
+
+        match i64::from_str_radix(digits, radix) {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => {
+                let message = match digits.find(|c: char| !c.is_digit(radix)) {
+                    Some(offset) => format!("invalid digit `{}` in a base {} literal", &digits[offset..offset + 1], radix),
+                    None => format!("number too large for a base {} literal", radix),
+                };
+
+                Err(self.error(SyntaxErrorKind::InvalidToken {
+                    item: Item::new(literal, text),
+                    message,
+                }))
+            }
+        }
+    }
+
     fn parse_identifier(&mut self) -> Result<Ident, SyntaxError> {
         let token = self.current.clone();
 
@@ -932,33 +1416,70 @@ impl Parser {
                 let err_kind = SyntaxErrorKind::ExpectedIdent {
                     actual: Item::new(&self.current.span, token.syntax()),
                 };
-                let err = SyntaxError::new(err_kind);
-                Err(err)
+                Err(self.error(err_kind))
             }
         }
     }
 
     fn parse_arg_list(&mut self) -> Result<Vec<Expr>, SyntaxError> {
-        self.consume(TokenType::LeftParen);
+        self.open_delimiter(TokenType::LeftParen)?;
 
         let mut args = vec![];
 
         if !self.check(&TokenType::RightParen) {
             loop {
-                args.push(self.parse_expression()?);
+                args.push(self.parse_expr()?);
                 if !self.match_token(&TokenType::Comma) {
                     break;
                 }
             }
         }
 
-        self.expect(TokenType::RightParen)?;
+        self.close_delimiter(TokenType::RightParen)?;
 
         Ok(args)
     }
 
+    fn parse_lambda(&mut self) -> Result<Expr, SyntaxError> {
+        let start = self.current.span.clone();
+
+        // fun ...
+        self.consume(TokenType::Fun);
+
+        self.parse_lambda_body(start)
+    }
+
+    /// Parse an anonymous function after the `fun` keyword. A trailing
+    /// expression in the body is the function's return value.
+    fn parse_lambda_body(&mut self, start: Span) -> Result<Expr, SyntaxError> {
+        // fun '(' <params> ')' ...
+        let params = self.parse_params()?;
+
+        // fun '(' <params> ')' '{' ...
+        self.expect(TokenType::LeftBrace)?;
+
+        // fun '(' <params> ')' '{' <body> ...
+        let mut body = self.parse_block()?;
+
+        // fun '(' <params> ')' '{' <body> '}'
+        self.expect(TokenType::RightBrace)?;
+
+        if let Some(Stmt::ExpressionStmt(_)) = body.last() {
+            if let Some(Stmt::ExpressionStmt(expr)) = body.pop() {
+                let pos = expr.position();
+                body.push(AST::return_stmt(Some(*expr), pos));
+            }
+        }
+
+        let span = Span::combine(&start, &self.previous.span);
+
+        let id = Ident::new(String::new(), span.clone());
+
+        Ok(AST::lambda(FunctionDecl::new(id, params, body), span))
+    }
+
     fn parse_params(&mut self) -> Result<Vec<Ident>, SyntaxError> {
-        self.expect(TokenType::LeftParen)?;
+        self.open_delimiter(TokenType::LeftParen)?;
 
         let mut args = vec![];
 
@@ -971,7 +1492,7 @@ impl Parser {
             }
         }
 
-        self.expect(TokenType::RightParen)?;
+        self.close_delimiter(TokenType::RightParen)?;
 
         Ok(args)
     }
@@ -980,12 +1501,12 @@ impl Parser {
         let start = self.current.span.clone();
 
         // ( ...
-        self.consume(TokenType::LeftParen);
+        self.open_delimiter(TokenType::LeftParen)?;
 
         let expr = self.parse_sum()?;
 
         // ( expr )
-        self.expect(TokenType::RightParen)?;
+        self.close_delimiter(TokenType::RightParen)?;
 
         let span = Span::new(
             Rc::clone(&self.source),
@@ -996,6 +1517,46 @@ impl Parser {
         Ok(AST::paren_expr(Box::new(expr), span))
     }
 
+    fn parse_interpolation(&mut self) -> Result<Expr, SyntaxError> {
+        let start = self.current.span.clone();
+        let mut parts = vec![];
+
+        loop {
+            let (text, last) = match self.current.token_type.clone() {
+                TokenType::InterpolationStart(text) | TokenType::InterpolationMid(text) => {
+                    (text, false)
+                }
+                TokenType::InterpolationEnd(text) => (text, true),
+                // an embedded expression has to be followed by its closing `}`.
+                _ => {
+                    let actual = self.current.clone();
+
+                    return Err(self.error(SyntaxErrorKind::Expected {
+                        expected: Item::new(&Span::empty(), "}"),
+                        actual: Item::new(&actual.span, actual.syntax()),
+                    }));
+                }
+            };
+
+            if !text.is_empty() {
+                parts.push(AST::string(text.to_string(), Span::from(&self.current.span)));
+            }
+
+            self.advance();
+
+            if last {
+                break;
+            }
+
+            // "...{ <expr>
+            parts.push(self.parse_expr()?);
+        }
+
+        let span = Span::combine(&start, &self.previous.span);
+
+        Ok(AST::interpolation(parts, span))
+    }
+
     fn parse_array_literal(&mut self) -> Result<Expr, SyntaxError> {
         // TODO: trailing comma?
 
@@ -1003,7 +1564,7 @@ impl Parser {
         let mut elements = vec![];
 
         // [ ...
-        self.consume(TokenType::LeftBracket);
+        self.open_delimiter(TokenType::LeftBracket)?;
 
         // [ x, y, z ...
         if !self.check(&TokenType::RightBracket) {
@@ -1018,7 +1579,7 @@ impl Parser {
         }
 
         // [ ... ]
-        self.expect(TokenType::RightBracket)?;
+        self.close_delimiter(TokenType::RightBracket)?;
 
         let span = Span::combine(
             &start,
@@ -1035,7 +1596,7 @@ impl Parser {
         let mut elements = vec![];
 
         // { ...
-        self.consume(TokenType::LeftBrace);
+        self.open_delimiter(TokenType::LeftBrace)?;
 
         // { x: a, y: b, z: c ...
         if !self.check(&TokenType::RightBrace) {
@@ -1053,7 +1614,7 @@ impl Parser {
         }
 
         // { ... }
-        self.expect(TokenType::RightBrace)?;
+        self.close_delimiter(TokenType::RightBrace)?;
 
         let span = Span::combine(
             &start,
@@ -1063,3 +1624,784 @@ impl Parser {
         Ok(AST::map(elements, span))
     }
 }
+
+/// `message` with its first character in lower case.
+fn lowercase_first(message: &str) -> String {
+    let mut chars = message.chars();
+
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_expr(src: &str) -> Expr {
+        let mut ast = Parser::new(Source::new(src, "")).parse().unwrap();
+
+        match ast.items.remove(0) {
+            Stmt::ExpressionStmt(expr) => *expr,
+            stmt => panic!("expected an expression statement, got {:?}", stmt),
+        }
+    }
+
+    fn parse_with_max_number_length(src: &str, max: usize) -> Result<AST, SyntaxError> {
+        let settings = PipelineSettings {
+            max_number_length: max,
+            ..PipelineSettings::default()
+        };
+
+        Parser::with_config(Source::new(src, ""), &settings).parse()
+    }
+
+    #[test]
+    fn assignment_in_condition() {
+        for src in ["if x = 5 then\nendif\n", "if a then\nelif x = 5 then\nendif\n", "while x = 5 loop\nendloop\n"] {
+            let err = Parser::new(Source::new(src, "")).parse().unwrap_err();
+
+            match err.kind {
+                SyntaxErrorKind::AssignmentInCondition { equals } => assert_eq!(equals.span.text(), "="),
+                kind => panic!("expected an assignment in condition error, got {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn comparison_in_condition() {
+        assert!(Parser::new(Source::new("if x == 5 then\nendif\n", "")).parse().is_ok());
+        assert!(Parser::new(Source::new("while x == 5 loop\nendloop\n", "")).parse().is_ok());
+    }
+
+    fn parse_in_radix(src: &str, radix: u32) -> Result<AST, SyntaxError> {
+        let settings = PipelineSettings {
+            default_radix: radix,
+            ..PipelineSettings::default()
+        };
+
+        Parser::with_config(Source::new(src, ""), &settings).parse()
+    }
+
+    fn first_expr(ast: AST) -> Expr {
+        match ast.items.into_iter().next() {
+            Some(Stmt::ExpressionStmt(expr)) => *expr,
+            stmt => panic!("expected an expression statement, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn default_radix_reads_bare_integers() {
+        let ten = first_expr(parse_in_radix("10", 10).unwrap());
+        assert!(matches!(ten, Expr::Integer(10, _)));
+
+        let sixteen = first_expr(parse_in_radix("10", 16).unwrap());
+        assert!(matches!(sixteen, Expr::Integer(16, _)));
+
+        let five = first_expr(parse_in_radix("101", 2).unwrap());
+        assert!(matches!(five, Expr::Integer(5, _)));
+    }
+
+    #[test]
+    fn default_radix_leaves_prefixes_and_fractions_alone() {
+        let hex = first_expr(parse_in_radix("0x10", 16).unwrap());
+        assert!(matches!(hex, Expr::Integer(16, _)));
+
+        let binary = first_expr(parse_in_radix("0b10", 16).unwrap());
+        assert!(matches!(binary, Expr::Integer(2, _)));
+
+        let fraction = first_expr(parse_in_radix("1.5", 16).unwrap());
+        assert!(matches!(fraction, Expr::Number(n, _) if n == 1.5));
+    }
+
+    #[test]
+    fn default_radix_rejects_digits_outside_it() {
+        let err = parse_in_radix("print 19", 8).unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::InvalidToken { item, message } => {
+                assert_eq!(item.content, "19");
+                assert_eq!(message, "invalid digit `9` in a base 8 literal");
+            }
+            kind => panic!("expected an invalid token error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn integer_literals_are_exact() {
+        let large = first_expr(parse_in_radix("9007199254740993", 10).unwrap());
+        assert!(matches!(large, Expr::Integer(9007199254740993, _)));
+
+        let max = first_expr(parse_in_radix("9223372036854775807", 10).unwrap());
+        assert!(matches!(max, Expr::Integer(i64::MAX, _)));
+    }
+
+    #[test]
+    fn integer_literal_too_large() {
+        let err = parse_in_radix("print 9223372036854775808", 10).unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::InvalidToken { item, message } => {
+                assert_eq!(item.content, "9223372036854775808");
+                assert_eq!(message, "number too large for a base 10 literal");
+            }
+            kind => panic!("expected an invalid token error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn invalid_digit_keeps_its_case() {
+        let err = Parser::new(Source::new("print 0xZ1\n", "")).parse().unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::InvalidToken { item, message } => {
+                assert_eq!(item.content, "Z");
+                assert_eq!(message, "invalid digit `Z` in hexadecimal literal");
+            }
+            kind => panic!("expected an invalid token error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn invalid_token_is_collected() {
+        let mut parser = Parser::new(Source::new("var a = 1 $ 2\nprint a\n", ""));
+        let err = parser.parse().unwrap_err();
+
+        assert_eq!(parser.errors().len(), 1);
+        assert_eq!(err, parser.errors()[0]);
+
+        match &err.kind {
+            SyntaxErrorKind::InvalidToken { item, message } => {
+                assert_eq!((item.span.start, item.span.end), (10, 11));
+                assert_eq!(item.content, "$");
+                assert_eq!(message, "unexpected character `$`");
+            }
+            kind => panic!("expected an invalid token error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn unterminated_string_is_collected() {
+        let mut parser = Parser::new(Source::new("print \"abc", ""));
+
+        assert!(parser.parse().is_err());
+        assert!(matches!(
+            &parser.errors()[0].kind,
+            SyntaxErrorKind::InvalidToken { message, .. } if message == "unterminated string"
+        ));
+    }
+
+    #[test]
+    fn unicode_identifiers_setting() {
+        let src = "var café = 1\n";
+        assert!(Parser::new(Source::new(src, "")).parse().is_err());
+
+        let settings = PipelineSettings {
+            unicode_identifiers: true,
+            ..PipelineSettings::default()
+        };
+        let ast = Parser::with_config(Source::new(src, ""), &settings).parse().unwrap();
+
+        match &ast.items[0] {
+            Stmt::VarDeclaration(Pattern::Name(id), ..) => assert_eq!(id.name, "café"),
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn number_within_length_cap() {
+        assert!(parse_with_max_number_length("12", 5).is_ok());
+        assert!(parse_with_max_number_length("12345", 5).is_ok());
+    }
+
+    fn nested_parens(depth: usize) -> String {
+        format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+    }
+
+    fn parse_with_max_nesting_depth(src: &str, max: usize) -> Result<AST, SyntaxError> {
+        let settings = PipelineSettings {
+            max_nesting_depth: max,
+            ..PipelineSettings::default()
+        };
+
+        Parser::with_config(Source::new(src, ""), &settings).parse()
+    }
+
+    #[test]
+    fn nesting_within_depth_limit() {
+        assert!(parse_with_max_nesting_depth(&nested_parens(10), 16).is_ok());
+        assert!(Parser::new(Source::new(&nested_parens(DEFAULT_MAX_NESTING_DEPTH - 1), "")).parse().is_ok());
+    }
+
+    #[test]
+    fn nesting_past_depth_limit() {
+        let src = nested_parens(100_000);
+        let err = Parser::new(Source::new(&src, "")).parse().unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::NestingTooDeep { item, max } => {
+                assert_eq!(item.span.start, DEFAULT_MAX_NESTING_DEPTH);
+                assert_eq!(max, DEFAULT_MAX_NESTING_DEPTH);
+            }
+            kind => panic!("expected `NestingTooDeep`, got {:?}", kind),
+        }
+    }
+
+    fn nested_ifs(depth: usize) -> String {
+        format!("{}print 1\n{}", "if true then\n".repeat(depth), "endif\n".repeat(depth))
+    }
+
+    #[test]
+    fn statement_nesting_within_depth_limit() {
+        assert!(parse_with_max_nesting_depth(&nested_ifs(10), 16).is_ok());
+    }
+
+    #[test]
+    fn statement_nesting_past_depth_limit() {
+        let err = Parser::new(Source::new(&nested_ifs(100_000), "")).parse().unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            SyntaxErrorKind::NestingTooDeep { max: DEFAULT_MAX_NESTING_DEPTH, .. }
+        ));
+    }
+
+    #[test]
+    fn ternary_nesting_past_depth_limit() {
+        let src = format!("print {}1\n", "true ? 1 : ".repeat(100_000));
+        let err = Parser::new(Source::new(&src, "")).parse().unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            SyntaxErrorKind::NestingTooDeep { max: DEFAULT_MAX_NESTING_DEPTH, .. }
+        ));
+    }
+
+    #[test]
+    fn elif_chain_past_depth_limit() {
+        let src = format!("if false then\n{}endif\n", "elif false then\n".repeat(100_000));
+        let err = Parser::new(Source::new(&src, "")).parse().unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            SyntaxErrorKind::NestingTooDeep { max: DEFAULT_MAX_NESTING_DEPTH, .. }
+        ));
+
+        let src = format!("if false then\n{}endif\n", "elif false then\n".repeat(10));
+        assert!(parse_with_max_nesting_depth(&src, 16).is_ok());
+    }
+
+    #[test]
+    fn pipe_chain_past_depth_limit() {
+        let src = format!("var a = 1{}\n", " |> f".repeat(100_000));
+        let err = Parser::new(Source::new(&src, "")).parse().unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            SyntaxErrorKind::NestingTooDeep { max: DEFAULT_MAX_NESTING_DEPTH, .. }
+        ));
+
+        let src = format!("var a = 1{}\n", " |> f".repeat(10));
+        assert!(parse_with_max_nesting_depth(&src, 16).is_ok());
+    }
+
+    #[test]
+    fn unary_nesting_past_depth_limit() {
+        let err = parse_with_max_nesting_depth(&"-".repeat(20), 8).unwrap_err();
+
+        assert!(matches!(err.kind, SyntaxErrorKind::NestingTooDeep { max: 8, .. }));
+    }
+
+    fn parse_with_max_statements(src: &str, max: usize) -> Result<AST, SyntaxError> {
+        let settings = PipelineSettings {
+            max_statements: max,
+            ..PipelineSettings::default()
+        };
+
+        Parser::with_config(Source::new(src, ""), &settings).parse()
+    }
+
+    #[test]
+    fn statements_within_limit() {
+        let ast = parse_with_max_statements("print 1\n\n// comment\nprint 2\n", 2).unwrap();
+        assert_eq!(ast.items.len(), 2);
+
+        // statements in blocks don't count.
+        let src = "fun f() {\n    print 1\n    print 2\n}\n";
+        assert!(parse_with_max_statements(src, 1).is_ok());
+    }
+
+    #[test]
+    fn statements_past_limit() {
+        let err = parse_with_max_statements("print 1\nprint 2\nprint 3\n", 2).unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::TooManyStatements { item, max } => {
+                assert_eq!(item.span.start, 16);
+                assert_eq!(item.content, "print");
+                assert_eq!(max, 2);
+            }
+            kind => panic!("expected `TooManyStatements`, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn number_over_length_cap() {
+        let err = parse_with_max_number_length("123456", 5).unwrap_err();
+
+        match err.kind {
+            SyntaxErrorKind::NumberTooLong { literal, max } => {
+                assert_eq!(literal.content, "123456");
+                assert_eq!((literal.span.start, literal.span.end), (0, 6));
+                assert_eq!(max, 5);
+            }
+            kind => panic!("expected `NumberTooLong`, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn ternary_is_right_associative() {
+        let expr = parse_expr("a ? b : c ? d : e");
+
+        match expr {
+            Expr::TernaryExpr(condition, then, alt, _) => {
+                assert!(matches!(*condition, Expr::Identifier(ref id) if id.name == "a"));
+                assert!(matches!(*then, Expr::Identifier(ref id) if id.name == "b"));
+                assert!(matches!(*alt, Expr::TernaryExpr(..)));
+            }
+            expr => panic!("expected a ternary expression, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn nested_ternary_in_then_branch() {
+        let expr = parse_expr("a ? b ? c : d : e");
+
+        match expr {
+            Expr::TernaryExpr(_, then, alt, _) => {
+                assert!(matches!(*then, Expr::TernaryExpr(..)));
+                assert!(matches!(*alt, Expr::Identifier(ref id) if id.name == "e"));
+            }
+            expr => panic!("expected a ternary expression, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn ternary_binds_looser_than_or() {
+        let expr = parse_expr("a or b ? c : d");
+
+        match expr {
+            Expr::TernaryExpr(condition, _, _, _) => {
+                assert!(matches!(*condition, Expr::LogicalExpr(..)));
+            }
+            expr => panic!("expected a ternary expression, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn integer_literal() {
+        assert!(matches!(parse_expr("5"), Expr::Integer(5, _)));
+        assert!(matches!(parse_expr("0x2f"), Expr::Integer(47, _)));
+        assert!(matches!(parse_expr("0b1010"), Expr::Integer(10, _)));
+        assert!(matches!(parse_expr("0o17"), Expr::Integer(15, _)));
+    }
+
+    #[test]
+    fn float_literal() {
+        assert!(matches!(parse_expr("5.0"), Expr::Number(n, _) if n == 5.0));
+        assert!(matches!(parse_expr("5e2"), Expr::Number(n, _) if n == 500.0));
+    }
+
+    fn assert_expected_expression_at_eof(result: Result<Expr, SyntaxError>) {
+        match result {
+            Err(err) => {
+                assert!(matches!(err.kind, SyntaxErrorKind::ExpectedExpressionAtEof { .. }));
+                assert!(err.is_unexpected_eof());
+            }
+            Ok(expr) => panic!("expected an error, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn incomplete_input() {
+        assert!(Parser::is_incomplete("1 +"));
+        assert!(Parser::is_incomplete("(1"));
+        assert!(Parser::is_incomplete("[1, 2"));
+        assert!(Parser::is_incomplete("a[1"));
+        assert!(Parser::is_incomplete("f(1,"));
+        assert!(Parser::is_incomplete("fun f() {\n"));
+        assert!(Parser::is_incomplete("if a then\n"));
+    }
+
+    #[test]
+    fn syntax_error_is_not_incomplete() {
+        assert!(!Parser::is_incomplete("1 + )"));
+        assert!(!Parser::is_incomplete(")"));
+        assert!(!Parser::is_incomplete("1 + 2"));
+    }
+
+    #[test]
+    fn eof_after_operator() {
+        let mut parser = Parser::new(Source::new("1 +", ""));
+        parser.advance();
+
+        assert_expected_expression_at_eof(parser.parse_expr());
+    }
+
+    #[test]
+    fn eof_after_paren() {
+        let mut parser = Parser::new(Source::new("(", ""));
+        parser.advance();
+
+        assert_expected_expression_at_eof(parser.parse_expr());
+    }
+
+    #[test]
+    fn eof_on_empty_input() {
+        let mut parser = Parser::new(Source::new("", ""));
+        parser.advance();
+
+        assert_expected_expression_at_eof(parser.parse_expr());
+    }
+
+    fn loop_body(src: &str) -> Vec<Stmt> {
+        let mut ast = Parser::new(Source::new(src, "")).parse().unwrap();
+
+        match ast.items.remove(0) {
+            Stmt::LoopStmt(body, _) => body,
+            stmt => panic!("expected a loop statement, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn loop_as_expression() {
+        let mut ast = Parser::new(Source::new("var a = loop\nbreak\nendloop", ""))
+            .parse()
+            .unwrap();
+
+        match ast.items.remove(0) {
+            Stmt::VarDeclaration(_, Some(init), _, _) => {
+                assert!(matches!(init, Expr::LoopExpr(..)))
+            }
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn break_with_value() {
+        let body = loop_body("loop\nbreak 42\nendloop");
+
+        match &body[0] {
+            Stmt::BreakStmt(Some(Expr::Integer(42, _)), _) => {}
+            stmt => panic!("expected `break 42`, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn bare_break() {
+        let body = loop_body("loop\nbreak\nendloop");
+
+        assert!(matches!(body[0], Stmt::BreakStmt(None, _)));
+    }
+
+    #[test]
+    fn lambda_literal() {
+        match parse_expr("fun(a, b) { a + b }") {
+            Expr::Lambda(fun, _) => {
+                assert_eq!(fun.params.len(), 2);
+                assert!(fun.id.name.is_empty());
+                assert!(matches!(fun.body[..], [Stmt::ReturnStmt(Some(Expr::BinaryExpr(..)), _)]));
+            }
+            expr => panic!("expected a lambda, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn immediately_invoked_lambda() {
+        match parse_expr("fun(x) { x }(5)") {
+            Expr::CallExpr(callee, args, _) => {
+                assert!(matches!(*callee, Expr::Lambda(..)));
+                assert_eq!(args.len(), 1);
+            }
+            expr => panic!("expected a call, got {:?}", expr),
+        }
+    }
+
+    fn assert_parses_like(src: &str, desugared: &str) {
+        let (expr, expected) = (parse_expr(src), parse_expr(desugared));
+        assert!(expr.structurally_eq(&expected), "{:?} != {:?}", expr, expected);
+    }
+
+    #[test]
+    fn pipe_calls_the_right_side() {
+        assert_parses_like("x |> f", "f(x)");
+        assert_parses_like("x |> f |> g", "g(f(x))");
+        assert_parses_like("x |> obj.method", "obj.method(x)");
+    }
+
+    #[test]
+    fn pipe_passes_the_first_argument() {
+        assert_parses_like("x |> f(y)", "f(x, y)");
+        assert_parses_like("x |> f()", "f(x)");
+        assert_parses_like("x |> f(y) |> g(z)", "g(f(x, y), z)");
+    }
+
+    #[test]
+    fn pipe_binds_loosest() {
+        assert_parses_like("1 + 2 |> f", "f(1 + 2)");
+
+        // the whole right side is called, even if it ends in a call.
+        match parse_expr("x |> f(y) * 2") {
+            Expr::CallExpr(callee, args, _) => {
+                assert!(matches!(*callee, Expr::BinaryExpr(..)));
+                assert_eq!(args.len(), 1);
+            }
+            expr => panic!("expected a call, got {:?}", expr),
+        }
+
+        assert_parses_like("a < b or c |> f", "f(a < b or c)");
+        assert_parses_like("x |> f ? a : b", "f(x) ? a : b");
+    }
+
+    #[test]
+    fn assign_lambda_to_variable() {
+        let mut ast = Parser::new(Source::new("var f = fun(x) { x }", ""))
+            .parse()
+            .unwrap();
+
+        match ast.items.remove(0) {
+            Stmt::VarDeclaration(Pattern::Name(id), Some(Expr::Lambda(..)), _, _) => assert_eq!(id.name, "f"),
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn binary_operator_span() {
+        match parse_expr("1 + 2") {
+            Expr::BinaryExpr(expr, _) => {
+                assert_eq!((expr.op_span.start, expr.op_span.end), (2, 3));
+            }
+            expr => panic!("expected a binary expression, got {:?}", expr),
+        }
+    }
+
+    fn parse_ast(src: &str) -> AST {
+        Parser::new(Source::new(src, "")).parse().unwrap()
+    }
+
+    #[test]
+    fn structural_equality_ignores_spans() {
+        let a = parse_ast("var x = 1 + f(2, [3])\nprint -x\n");
+        let b = parse_ast("var  x =  1+f( 2,[3] )\n\nprint   -x\n");
+
+        assert_ne!(a, b);
+        assert!(a.structurally_eq(&b));
+    }
+
+    #[test]
+    fn structural_equality_compares_values() {
+        assert!(!parse_ast("1 + 2").structurally_eq(&parse_ast("1 - 2")));
+        assert!(!parse_ast("1 + 2").structurally_eq(&parse_ast("1 + 3")));
+        assert!(!parse_ast("f(1)").structurally_eq(&parse_ast("g(1)")));
+        assert!(!parse_ast("print 1").structurally_eq(&parse_ast("print 1\nprint 1")));
+    }
+
+    #[test]
+    fn unary_span_covers_operand() {
+        for (src, end) in &[("-5", 2), ("- 5", 3), ("-(5)", 4), ("-a[0]", 5), ("-f(1)", 5), ("!a.b", 4)] {
+            match parse_expr(src) {
+                Expr::UnaryExpr(_, op_span, arg, span) => {
+                    assert_eq!((op_span.start, op_span.end), (0, 1), "{}", src);
+                    assert_eq!((span.start, span.end), (0, *end), "{}", src);
+                    assert_eq!(span.end, arg.position().end, "{}", src);
+                }
+                expr => panic!("expected a unary expression, got {:?}", expr),
+            }
+        }
+    }
+
+    #[test]
+    fn unary_operator_span() {
+        match parse_expr("-x") {
+            Expr::UnaryExpr(Op::Subtract, op_span, _, span) => {
+                assert_eq!((op_span.start, op_span.end), (0, 1));
+                assert_eq!((span.start, span.end), (0, 2));
+            }
+            expr => panic!("expected a unary expression, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn word_operators() {
+        assert!(parse_ast("not a == b").structurally_eq(&parse_ast("!a == b")));
+        assert!(parse_ast("not f(x)[0]").structurally_eq(&parse_ast("!f(x)[0]")));
+
+        match parse_expr("a and not b or c") {
+            Expr::LogicalExpr(or, _) => {
+                assert_eq!(or.op, Op::Or);
+                assert!(matches!(&or.lhs, Expr::LogicalExpr(and, _) if and.op == Op::And));
+                assert!(matches!(&or.rhs, Expr::Identifier(id) if id.name == "c"));
+            }
+            expr => panic!("expected a logical expression, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn var_declaration_name_pattern() {
+        let mut ast = parse_ast("var x = 1\n");
+
+        match ast.items.remove(0) {
+            Stmt::VarDeclaration(Pattern::Name(id), Some(Expr::Integer(1, _)), VarKind::Var, _) => {
+                assert_eq!(id.name, "x");
+                assert_eq!(id.pos.text(), "x");
+            }
+            stmt => panic!("expected a variable declaration, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn var_declaration_list_pattern() {
+        let mut ast = parse_ast("var [a, b] = xs\n");
+
+        match ast.items.remove(0) {
+            Stmt::VarDeclaration(Pattern::List(items, span), Some(Expr::Identifier(xs)), _, _) => {
+                assert_eq!(span.text(), "[a, b]");
+                assert_eq!(xs.name, "xs");
+
+                let names: Vec<_> = items
+                    .iter()
+                    .map(|item| match item {
+                        Pattern::Name(id) => id.name.as_str(),
+                        Pattern::List(..) => panic!("expected a name, got {:?}", item),
+                    })
+                    .collect();
+                assert_eq!(names, ["a", "b"]);
+            }
+            stmt => panic!("expected a destructuring declaration, got {:?}", stmt),
+        }
+
+        let nested = parse_ast("fin [a, [b, c]] = xs\n");
+        assert!(nested.structurally_eq(&parse_ast("fin [a,[b,c]]=xs\n")));
+        assert!(!nested.structurally_eq(&parse_ast("fin [a, b, c] = xs\n")));
+    }
+
+    #[test]
+    fn else_if_chain() {
+        let src = "if a then\n    print 1\nelse if b then\n    print 2\nelse\n    print 3\nendif\n";
+        let mut ast = parse_ast(src);
+
+        let (alt, span) = match ast.items.remove(0) {
+            Stmt::IfStmt(Expr::Identifier(a), _, Some(alt), span) if a.name == "a" => (alt, span),
+            stmt => panic!("expected an if statement, got {:?}", stmt),
+        };
+        assert_eq!(span.start, 0);
+
+        match *alt {
+            Stmt::IfStmt(Expr::Identifier(b), body, Some(alt), span) => {
+                assert_eq!(b.name, "b");
+                assert_eq!((b.pos.start, b.pos.end), (30, 31));
+                assert_eq!(b.pos.text(), "b");
+                assert_eq!(span.start, 27);
+                assert_eq!(span.text().lines().next(), Some("if b then"));
+                assert!(matches!(body[..], [Stmt::PrintStmt(..)]));
+                assert!(matches!(*alt, Stmt::BlockStmt(ref body, _) if body.len() == 1));
+            }
+            stmt => panic!("expected the else branch to be an if statement, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn elif_is_else_if() {
+        let else_if = parse_ast("if a then\nprint 1\nelse if b then\nprint 2\nelse if c then\nprint 3\nendif\n");
+        let elif = parse_ast("if a then\nprint 1\nelif b then\nprint 2\nelif c then\nprint 3\nendif\n");
+
+        assert!(else_if.structurally_eq(&elif));
+    }
+
+    #[test]
+    fn for_in_loop() {
+        let mut ast = Parser::new(Source::new("for x in xs loop\nprint x\nendloop", ""))
+            .parse()
+            .unwrap();
+
+        match ast.items.remove(0) {
+            Stmt::ForStmt(stmt, _) => {
+                assert_eq!(stmt.var.name, "x");
+                assert!(matches!(&stmt.iterable, Expr::Identifier(id) if id.name == "xs"));
+                assert!(matches!(stmt.body[..], [Stmt::PrintStmt(..)]));
+            }
+            stmt => panic!("expected a for loop, got {:?}", stmt),
+        }
+    }
+
+    fn parse_single(src: &str) -> Result<Expr, Vec<SyntaxError>> {
+        Parser::parse_expression(Source::new(src, ""))
+    }
+
+    #[test]
+    fn parse_single_expression() {
+        let expr = parse_single("\n1 + 2 * f(x) // sum\n").unwrap();
+
+        match &parse_ast("1 + 2 * f(x)\n").items[0] {
+            Stmt::ExpressionStmt(stmt) => assert!(expr.structurally_eq(stmt)),
+            stmt => panic!("expected an expression, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn parse_single_expression_rejects_trailing_tokens() {
+        let errors = parse_single("1 + 2 3").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0].kind,
+            SyntaxErrorKind::ExpectedEof { actual } if actual.content == "3"
+        ));
+
+        assert!(parse_single("1\nprint 2\n").is_err());
+        assert!(parse_single("").is_err());
+        assert!(matches!(
+            &parse_single("1 + $").unwrap_err()[0].kind,
+            SyntaxErrorKind::InvalidToken { .. }
+        ));
+    }
+
+    fn mismatched_delimiter(src: &str) -> (String, String, String, String) {
+        let err = Parser::new(Source::new(src, "")).parse().unwrap_err();
+        let message = err.report().message;
+
+        match err.kind {
+            SyntaxErrorKind::MismatchedDelimiter { first, second, expected } => {
+                (first.content, second.content, expected.to_string(), message)
+            }
+            kind => panic!("expected a mismatched delimiter error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn mismatched_closing_brace() {
+        let (first, second, expected, message) = mismatched_delimiter("fun f() { 1 )\n");
+        assert_eq!((first.as_str(), second.as_str(), expected.as_str()), ("{", ")", "}"));
+        assert_eq!(
+            message,
+            "mismatched closing delimiter: expected `}` to match `{` at 1:9, found `)`"
+        );
+
+        let (first, second, _, _) = mismatched_delimiter("print { 1 )\n");
+        assert_eq!((first.as_str(), second.as_str()), ("{", ")"));
+    }
+
+    #[test]
+    fn mismatched_closing_paren() {
+        let (first, second, expected, message) = mismatched_delimiter("print ( 1 }\n");
+        assert_eq!((first.as_str(), second.as_str(), expected.as_str()), ("(", "}", ")"));
+        assert_eq!(
+            message,
+            "mismatched closing delimiter: expected `)` to match `(` at 1:7, found `}`"
+        );
+
+        // only the innermost opener is blamed.
+        let (first, second, _, _) = mismatched_delimiter("print [(1]\n");
+        assert_eq!((first.as_str(), second.as_str()), ("(", "]"));
+    }
+}
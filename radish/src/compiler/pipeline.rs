@@ -1,17 +1,32 @@
-use std::fmt;
+use std::{convert::TryFrom, fmt};
 
 use crate::{
     common::{source::Source, CompiledModule},
-    compiler::Compiler, compiler::Parser, compiler::SyntaxError, compiler::AST, config::Config,
+    compiler::Compiler, compiler::Parser, compiler::SyntaxError, compiler::AST,
+    config::{
+        Config, DEFAULT_MAX_CONSTANTS, DEFAULT_MAX_NESTING_DEPTH, DEFAULT_MAX_NUMBER_LENGTH,
+        DEFAULT_MAX_STATEMENTS,
+    },
+    error::{AsDiagnostic, Diagnostic},
+    RadishError,
 };
 
-use super::{validate_ast, resolve_symbols, hoist::hoist};
+use super::{validate_ast, validate_ast_strict, resolve_symbols, resolve_symbols_without_builtins, fold::fold_constants, hoist::hoist, lint::lint};
 
 type ASTPass = Box<dyn FnMut(&mut AST) -> Result<(), SyntaxError> + 'static>;
 
 pub struct PipelineSettings {
     pub dump_bytecode: bool,
     pub dump_ast: bool,
+    pub repl: bool,
+    pub max_number_length: usize,
+    pub default_radix: u32,
+    pub max_nesting_depth: usize,
+    pub max_statements: usize,
+    pub max_constants: usize,
+    pub unicode_identifiers: bool,
+    pub strict: bool,
+    pub no_builtins: bool,
 }
 
 impl PipelineSettings {
@@ -19,6 +34,15 @@ impl PipelineSettings {
         Self {
             dump_bytecode: false,
             dump_ast: false,
+            repl: false,
+            max_number_length: DEFAULT_MAX_NUMBER_LENGTH,
+            default_radix: 10,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_statements: DEFAULT_MAX_STATEMENTS,
+            max_constants: DEFAULT_MAX_CONSTANTS,
+            unicode_identifiers: false,
+            strict: false,
+            no_builtins: false,
         }
     }
 }
@@ -29,12 +53,32 @@ impl Default for PipelineSettings {
     }
 }
 
-impl From<&Config> for PipelineSettings {
-    fn from(config: &Config) -> Self {
-        Self {
+/// Build the settings for `config`, rejecting values the pipeline can't work
+/// with, such as a default radix `from_str_radix` doesn't support.
+impl TryFrom<&Config> for PipelineSettings {
+    type Error = RadishError;
+
+    fn try_from(config: &Config) -> Result<Self, Self::Error> {
+        if !(2..=36).contains(&config.default_radix) {
+            return Err(RadishError::Other(format!(
+                "default radix must be between 2 and 36, got {}",
+                config.default_radix
+            )));
+        }
+
+        Ok(Self {
             dump_bytecode: config.dump_bytecode,
             dump_ast: config.dump_ast,
-        }
+            repl: config.repl,
+            max_number_length: config.max_number_length,
+            default_radix: config.default_radix,
+            max_nesting_depth: config.max_nesting_depth,
+            max_statements: config.max_statements,
+            max_constants: config.max_constants,
+            unicode_identifiers: config.unicode_identifiers,
+            strict: config.strict,
+            no_builtins: config.no_builtins,
+        })
     }
 }
 
@@ -45,51 +89,158 @@ pub struct CompilerPipeLine {
 }
 
 impl CompilerPipeLine {
-    pub fn new(config: &Config) -> Self {
-        let settings = PipelineSettings::from(config);
+    /// Create a pipeline for `config`, failing if the config is invalid.
+    pub fn new(config: &Config) -> Result<Self, RadishError> {
+        let settings = PipelineSettings::try_from(config)?;
         let compiler = Compiler::new(&settings);
 
-        Self {
+        Ok(Self {
             settings,
             passes: vec![],
             compiler,
-        }
+        })
     }
 
     pub fn with_default_passes(mut self) -> Self {
-        self.register_pass(resolve_symbols);
-        self.register_pass(validate_ast);
+        if self.settings.no_builtins {
+            self.register_pass(resolve_symbols_without_builtins);
+        } else {
+            self.register_pass(resolve_symbols);
+        }
+
+        if self.settings.strict {
+            self.register_pass(validate_ast_strict);
+        } else {
+            self.register_pass(validate_ast);
+        }
+
+        self.register_pass(fold_constants);
         self.register_pass(hoist);
         
         self
     }
 
-    pub fn register_pass<F: 'static>(&mut self, pass: F) -> &mut Self
+    pub fn register_pass<F>(&mut self, pass: F) -> &mut Self
     where
-        F: FnMut(&mut AST) -> Result<(), SyntaxError>,
+        F: 'static + FnMut(&mut AST) -> Result<(), SyntaxError>,
     {
         self.passes.push(Box::new(pass));
         self
     }
 
+    /// Forget the globals of previously compiled code, keeping the passes.
+    pub fn reset(&mut self) {
+        self.compiler.reset();
+    }
+
     pub fn compile(&mut self, file_name: &str, src: &str) -> Result<CompiledModule, SyntaxError> {
         self._compile(file_name, src)
     }
 
-    fn _compile(&mut self, file_name: &str, src: &str) -> Result<CompiledModule, SyntaxError> {
-        let source = Source::new(src, &file_name);
+    /// Compile a source string that uses the globals defined by previously
+    /// compiled code, without that code being compiled again. This is how the
+    /// REPL evaluates a line against the state of its session.
+    pub fn compile_in_session(&mut self, file_name: &str, src: &str) -> Result<CompiledModule, SyntaxError> {
+        let source = Source::new(src, file_name);
+
+        let mut ast = Parser::with_config(source, &self.settings).parse()?;
+        ast.outer_globals = self.compiler.global_names();
+
+        self.run_passes(&mut ast)?;
+
+        self.compile_ast(file_name, &ast)
+    }
+
+    /// Compile a file into a new module, such as one being imported.
+    pub fn compile_module(&mut self, file_name: &str, src: &str) -> Result<CompiledModule, SyntaxError> {
+        let ast = self.parse(file_name, src)?;
+
+        self.compile_module_ast(file_name, &ast)
+    }
+
+    /// Parse a source string and run the registered passes over the resulting AST.
+    pub fn parse(&mut self, file_name: &str, src: &str) -> Result<AST, SyntaxError> {
+        let source = Source::new(src, file_name);
 
         let mut parser = Parser::with_config(source, &self.settings);
 
         let mut ast = parser.parse()?;
 
+        self.run_passes(&mut ast)?;
+
+        Ok(ast)
+    }
+
+    /// Parse a source string like [`parse()`](Self::parse), also returning
+    /// the warnings about it.
+    pub fn parse_with_warnings(
+        &mut self,
+        file_name: &str,
+        src: &str,
+    ) -> Result<(AST, Vec<Diagnostic>), SyntaxError> {
+        let source = Source::new(src, file_name);
+
+        let mut ast = Parser::with_config(source, &self.settings).parse()?;
+
+        let warnings = lint(&mut ast);
+
+        self.run_passes(&mut ast)?;
+
+        Ok((ast, warnings))
+    }
+
+    /// Compile a source string like [`compile()`](Self::compile), also
+    /// returning the warnings about it. On failure every invalid token the
+    /// parser came across is reported, not just the first error.
+    pub fn compile_with_warnings(
+        &mut self,
+        file_name: &str,
+        src: &str,
+    ) -> Result<(CompiledModule, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let source = Source::new(src, file_name);
+
+        let mut parser = Parser::with_config(source, &self.settings);
+
+        let mut ast = match parser.parse() {
+            Ok(ast) => ast,
+            Err(err) if parser.errors().is_empty() => return Err(vec![err.diagnostic()]),
+            Err(_) => return Err(parser.errors().iter().map(AsDiagnostic::diagnostic).collect()),
+        };
+
+        let warnings = lint(&mut ast);
+
+        self.run_passes(&mut ast).map_err(|err| vec![err.diagnostic()])?;
+
+        let module = self
+            .compile_ast(file_name, &ast)
+            .map_err(|err| vec![err.diagnostic()])?;
+
+        Ok((module, warnings))
+    }
+
+    fn run_passes(&mut self, ast: &mut AST) -> Result<(), SyntaxError> {
         for callback in self.passes.iter_mut() {
             ast.visit(callback)?;
         }
 
-        let module = self.compiler.compile(file_name, &ast)?;
+        Ok(())
+    }
 
-        Ok(module)
+    /// Generate a module from an AST that has already been through [`parse()`](Self::parse).
+    pub fn compile_ast(&mut self, file_name: &str, ast: &AST) -> Result<CompiledModule, SyntaxError> {
+        self.compiler.compile(file_name, ast)
+    }
+
+    /// Generate a new module, like [`compile_module()`](Self::compile_module),
+    /// from an AST that has already been through [`parse()`](Self::parse).
+    pub fn compile_module_ast(&mut self, file_name: &str, ast: &AST) -> Result<CompiledModule, SyntaxError> {
+        self.compiler.compile_module(file_name, ast)
+    }
+
+    fn _compile(&mut self, file_name: &str, src: &str) -> Result<CompiledModule, SyntaxError> {
+        let ast = self.parse(file_name, src)?;
+
+        self.compile_ast(file_name, &ast)
     }
 }
 
@@ -98,3 +249,114 @@ impl fmt::Debug for CompilerPipeLine {
         write!(f, "CompilerPipeLine {{ .. }}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::error::SyntaxErrorKind;
+    use crate::Value;
+
+    #[test]
+    fn compile_returns_warnings() {
+        let mut pipeline = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes();
+
+        let src = "fun f() {\n    var unused = 1\n    return 2\n}\nprint f()\n";
+        let (_, warnings) = pipeline.compile_with_warnings("main", src).unwrap();
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "unused variable `unused`");
+    }
+
+    #[test]
+    fn parse_returns_warnings() {
+        let mut pipeline = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes();
+
+        let src = "fun f() {\n    var unused = 1\n}\nf()\n";
+        let (ast, warnings) = pipeline.parse_with_warnings("main", src).unwrap();
+
+        assert_eq!(ast.items.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "unused variable `unused`");
+
+        assert!(pipeline.compile_module_ast("main", &ast).is_ok());
+    }
+
+    #[test]
+    fn compile_errors_are_diagnostics() {
+        let mut pipeline = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes();
+
+        let errors = pipeline.compile_with_warnings("main", "var a = 1 $ 2\nvar b = 3 $ 4\n").unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn common_constants_skip_the_pool() {
+        let mut pipeline = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes();
+
+        let module = pipeline.compile("main", "var a = true\nvar b = nil\nvar c = 0\nvar d = 1\n").unwrap();
+        let entry = module.borrow().entry().unwrap();
+        assert!(entry.chunk.constants.is_empty(), "{:?}", entry.chunk.constants);
+
+        let module = pipeline.compile("main", "var e = 2\n").unwrap();
+        let entry = module.borrow().entry().unwrap();
+        assert_eq!(entry.chunk.constants, [Value::Integer(2)]);
+    }
+
+    #[test]
+    fn instructions_map_to_source_spans() {
+        let mut pipeline = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes();
+
+        let module = pipeline.compile("main", "var a = 1\nprint a + 22\n").unwrap();
+        let entry = module.borrow().entry().unwrap();
+        let chunk = &entry.chunk;
+
+        let span_at = |offset| chunk.span_at(offset).text().to_string();
+
+        // `One`, then `DefGlobal` and its operand.
+        assert_eq!(span_at(0), "1");
+        assert_eq!(span_at(1), "var a = 1");
+        assert_eq!(span_at(3), "var a = 1");
+        // `LoadGlobal` and its operand.
+        assert_eq!(span_at(6), "a");
+        assert_eq!(span_at(10), "a");
+        // `LoadConst` and its operand.
+        assert_eq!(span_at(11), "22");
+        assert_eq!(span_at(12), "22");
+        // `Add` is the operator's, so an error from it points there.
+        assert_eq!(span_at(13), "+");
+        assert_eq!(span_at(14), "print a + 22");
+    }
+
+    #[test]
+    fn invalid_default_radix() {
+        for radix in [0, 1, 37] {
+            let mut config = Config::new();
+            config.default_radix = radix;
+
+            match CompilerPipeLine::new(&config) {
+                Err(RadishError::Other(msg)) => {
+                    assert_eq!(msg, format!("default radix must be between 2 and 36, got {}", radix))
+                }
+                Err(err) => panic!("expected an invalid config error, got {:?}", err),
+                Ok(_) => panic!("radix {} was accepted", radix),
+            }
+        }
+
+        let mut config = Config::new();
+        config.default_radix = 36;
+        assert!(CompilerPipeLine::new(&config).is_ok());
+    }
+
+    #[test]
+    fn destructuring_not_yet_supported() {
+        let src = "var [a, b] = [1, 2]\n";
+
+        let result = CompilerPipeLine::new(&Config::new()).unwrap().with_default_passes().compile("main", src);
+
+        match result.map(|_| ()).unwrap_err().kind {
+            SyntaxErrorKind::UnsupportedDestructuring { pattern } => assert_eq!(pattern.content, "[a, b]"),
+            kind => panic!("expected `UnsupportedDestructuring`, got {:?}", kind),
+        }
+    }
+}
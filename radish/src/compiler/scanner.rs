@@ -1,12 +1,40 @@
+use std::fmt;
 use std::rc::Rc;
 
-use crate::common::{source::Source, span::Span};
+use crate::common::{
+    source::{line_break_at, Source},
+    span::Span,
+};
 use crate::compiler::token::{Token, TokenType};
 
+/// Returned when asked to scan from an offset that isn't a char boundary of the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidOffset(pub usize);
+
+impl fmt::Display for InvalidOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "offset {} is not a char boundary of the source", self.0)
+    }
+}
+
+impl std::error::Error for InvalidOffset {}
+
 pub struct Scanner {
     pub source: Rc<Source>,
-    pub current: usize,
-    pub previous: usize,
+    /// The byte offset of the next character to be scanned.
+    current: usize,
+    /// The byte offset at which the lexeme being scanned starts. Between
+    /// tokens it's always equal to `current`: every token, error tokens
+    /// included, starts where the one before it ended.
+    start: usize,
+    /// Emit whitespace as tokens instead of skipping it.
+    trivia: bool,
+    /// Allow non-ASCII letters and digits in identifiers.
+    unicode_identifiers: bool,
+    /// How many braces are open in each string interpolation being scanned,
+    /// innermost last. The `}` that closes an interpolation resumes scanning
+    /// its string.
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
@@ -14,12 +42,95 @@ impl Scanner {
         Scanner {
             source: Rc::clone(&source),
             current: 0,
-            previous: 0,
+            start: 0,
+            trivia: false,
+            unicode_identifiers: false,
+            interpolations: vec![],
+        }
+    }
+
+    /// Create a scanner that keeps trivia: runs of whitespace are scanned as
+    /// `Whitespace` tokens alongside the comments, so that the source can be
+    /// rebuilt from its tokens. See [`Token::is_trivia`].
+    pub fn with_trivia(source: Rc<Source>) -> Scanner {
+        Scanner {
+            trivia: true,
+            ..Scanner::new(source)
         }
     }
 
+    /// Allow identifiers to contain Unicode letters and digits, such as
+    /// `café`, besides ASCII letters, digits and underscores.
+    pub fn with_unicode_identifiers(mut self, allowed: bool) -> Scanner {
+        self.unicode_identifiers = allowed;
+        self
+    }
+
+    /// The byte offset at which each line of the source starts, as cached by
+    /// the source.
+    pub fn line_map(&self) -> &[usize] {
+        self.source.line_starts()
+    }
+
+    /// Create a scanner that starts at `offset` instead of the beginning of
+    /// the source. The offset should be a token boundary for the tokens to
+    /// match a scan of the whole file.
+    pub fn at(source: Rc<Source>, offset: usize) -> Result<Scanner, InvalidOffset> {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_from(offset)?;
+        Ok(scanner)
+    }
+
+    /// Move the scanner to `offset`, so the next call to `scan_token`
+    /// re-scans the source from there.
+    pub fn scan_from(&mut self, offset: usize) -> Result<(), InvalidOffset> {
+        if !self.source.contents.is_char_boundary(offset) {
+            return Err(InvalidOffset(offset));
+        }
+
+        self.current = offset;
+        self.start = offset;
+        self.interpolations.clear();
+        Ok(())
+    }
+
+    /// The byte offset that the next call to [`scan_token`](Scanner::scan_token)
+    /// scans from: the end of the last token scanned.
+    pub fn offset(&self) -> usize {
+        self.current
+    }
+
+    /// Whether the scanner is inside the braces of a string interpolation, so
+    /// that it couldn't restart from its offset with a fresh state.
+    pub(crate) fn in_interpolation(&self) -> bool {
+        !self.interpolations.is_empty()
+    }
+
     pub fn scan_token(&mut self) -> Token {
+        debug_assert_eq!(self.start, self.current, "a token was left unfinished");
+
+        if self.current == 0 {
+            self.skip_shebang();
+        }
+
+        let start = self.current;
         self.skip_whitespace();
+
+        if self.trivia && self.current > start {
+            self.start = start;
+            return self.make_token(TokenType::Whitespace);
+        }
+
+        if let Some(len) = line_break_at(self.remaining()) {
+            self.current += len;
+            return self.make_token(TokenType::Newline);
+        }
+
+        if self.remaining().starts_with("b\"") {
+            return self.scan_bytes();
+        }
+
+        let unicode = self.unicode_identifiers;
         let c = self.advance();
 
         match c {
@@ -88,19 +199,42 @@ impl Scanner {
                     self.make_token(TokenType::Equals)
                 }
             }
-            Some("\n") => self.make_token(TokenType::Newline),
             Some("(") => self.make_token(TokenType::LeftParen),
             Some(")") => self.make_token(TokenType::RightParen),
-            Some("{") => self.make_token(TokenType::LeftBrace),
-            Some("}") => self.make_token(TokenType::RightBrace),
+            Some("{") => {
+                if let Some(open) = self.interpolations.last_mut() {
+                    *open += 1;
+                }
+
+                self.make_token(TokenType::LeftBrace)
+            }
+            Some("}") => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.scan_string(true)
+                }
+                Some(open) => {
+                    *open -= 1;
+                    self.make_token(TokenType::RightBrace)
+                }
+                None => self.make_token(TokenType::RightBrace),
+            },
             Some("[") => self.make_token(TokenType::LeftBracket),
             Some("]") => self.make_token(TokenType::RightBracket),
             Some(",") => self.make_token(TokenType::Comma),
             Some(".") => self.make_token(TokenType::Dot),
             Some(":") => self.make_token(TokenType::Colon),
-            Some("\"") => self.scan_string(),
+            Some("?") => self.make_token(TokenType::Question),
+            Some("|") => {
+                if self.match_(">") {
+                    self.make_token(TokenType::Pipe)
+                } else {
+                    self.make_error_token("|")
+                }
+            }
+            Some("\"") => self.scan_string(false),
             None => self.make_token(TokenType::Eof),
-            _ if is_alpha(c.unwrap()) => self.identifier(),
+            _ if is_identifier_start(c.unwrap(), unicode) => self.identifier(),
             _ if is_digit(c.unwrap()) => self.number(),
             _ => {
                 let msg = c.unwrap().to_string();
@@ -112,14 +246,15 @@ impl Scanner {
     fn make_token(&mut self, token_type: TokenType) -> Token {
         let token = Token::new(
             token_type,
-            Span::new(Rc::clone(&self.source), self.previous, self.current),
+            Span::new(Rc::clone(&self.source), self.start, self.current),
         );
-        self.previous = self.current;
+        self.start = self.current;
         token
     }
 
     fn make_error_token(&mut self, msg: &str) -> Token {
-        let span = Span::new(self.source.clone(), self.previous, self.current);
+        let span = Span::new(self.source.clone(), self.start, self.current);
+        self.start = self.current;
         Token::new(TokenType::Error(msg.to_string().into_boxed_str()), span)
     }
 
@@ -161,7 +296,7 @@ impl Scanner {
         if self.remaining().is_empty() {
             None
         } else {
-            let source = &self.source.contents[self.previous..];
+            let source = &self.source.contents[self.start..];
             let mut end = 1;
             while !source.is_char_boundary(end) {
                 end += 1;
@@ -171,8 +306,9 @@ impl Scanner {
         }
     }
 
+    /// Drop the characters scanned so far from the current lexeme.
     fn skip_next(&mut self) {
-        self.previous = self.current;
+        self.start = self.current;
     }
 
     fn match_(&mut self, check: &str) -> bool {
@@ -187,70 +323,21 @@ impl Scanner {
     fn number(&mut self) -> Token {
         if self.current_token() == Some("0") {
             match self.peek() {
-                Some("b") | Some("B") => {
-                    self.advance();
-
-                    while self.peek() != None && self.peek() == Some("0")
-                        || self.peek() == Some("1")
-                    {
-                        self.advance();
-                    }
-
-                    let string_value = &self.source.contents[self.previous + 2..self.current];
-
-                    // Todo: if the string fails to parse, should report an error.
-                    let parse_value = match isize::from_str_radix(string_value, 2) {
-                        Ok(val) => val,
-                        Err(err) => panic!("{}", err),
-                    };
-                    return self.make_token(TokenType::Number(parse_value as f64));
-                }
-                Some("o") | Some("O") => {
-                    self.advance();
-
-                    while self.peek() != None && is_digit(self.peek().unwrap()) {
-                        if !is_octal(self.advance().unwrap()) {
-                            panic!("Expected digits between 0 and 7");
-                        }
-                    }
-
-                    let string_value = &self.source.contents[self.previous + 2..self.current];
-
-                    // Todo: if the string fails to parse, should report an error.
-                    let parse_value = match isize::from_str_radix(string_value, 8) {
-                        Ok(val) => val,
-                        Err(err) => panic!("{}", err),
-                    };
-                    return self.make_token(TokenType::Number(parse_value as f64));
-                }
-                Some("x") | Some("X") => {
-                    self.advance();
-
-                    while self.peek() != None && is_hex(self.peek().unwrap()) {
-                        self.advance();
-                    }
-
-                    let string_value = &self.source.contents[self.previous + 2..self.current];
-
-                    // Todo: if the string fails to parse, should report an error.
-                    let parse_value = match isize::from_str_radix(string_value, 16) {
-                        Ok(val) => val,
-                        Err(err) => panic!("{}", err),
-                    };
-                    return self.make_token(TokenType::Number(parse_value as f64));
-                }
+                Some("b") | Some("B") => return self.radix_number(2, "binary"),
+                Some("o") | Some("O") => return self.radix_number(8, "octal"),
+                Some("x") | Some("X") => return self.radix_number(16, "hexadecimal"),
                 _ => {}
             };
         }
 
-        while self.peek() != None && is_digit(self.peek().unwrap()) {
+        while self.peek().is_some() && is_digit(self.peek().unwrap()) {
             self.advance();
         }
 
         // check if this is a floating point number.
         if self.peek() == Some(".") {
             self.advance();
-            while self.peek() != None && is_digit(self.peek().unwrap()) {
+            while self.peek().is_some() && is_digit(self.peek().unwrap()) {
                 self.advance();
             }
         }
@@ -263,12 +350,12 @@ impl Scanner {
                 self.advance();
             }
 
-            while self.peek() != None && is_digit(self.peek().unwrap()) {
+            while self.peek().is_some() && is_digit(self.peek().unwrap()) {
                 self.advance();
             }
         }
 
-        let string_value = &self.source.contents[self.previous..self.current];
+        let string_value = &self.source.contents[self.start..self.current];
 
         // Todo: if the string fails to parse, should report an error.
         let parse_value = match string_value.parse::<f64>() {
@@ -278,8 +365,47 @@ impl Scanner {
         self.make_token(TokenType::Number(parse_value))
     }
 
+    /// Scan an integer literal with a radix prefix, such as `0b1010`. The
+    /// literal's alphanumeric characters are all scanned, so that a digit
+    /// outside the radix is reported, with a span of just that digit, instead
+    /// of starting a new token.
+    fn radix_number(&mut self, radix: u32, name: &str) -> Token {
+        // skip the prefix.
+        self.advance();
+
+        let digits_start = self.current;
+
+        while self.peek().is_some() && is_alphanumeric(self.peek().unwrap()) {
+            self.advance();
+        }
+
+        let digits = &self.source.contents[digits_start..self.current];
+
+        if let Some(offset) = digits.find(|c: char| !c.is_digit(radix)) {
+            let bad_digit = &digits[offset..];
+            let bad_digit = &bad_digit[..bad_digit.chars().next().unwrap().len_utf8()];
+            let msg = format!("Invalid digit `{}` in {} literal", bad_digit, name);
+
+            let start = digits_start + offset;
+            let span = Span::new(Rc::clone(&self.source), start, start + bad_digit.len());
+            self.skip_next();
+
+            return Token::new(TokenType::Error(msg.into_boxed_str()), span);
+        }
+
+        let msg = match i64::from_str_radix(digits, radix) {
+            Ok(val) => return self.make_token(TokenType::Number(val as f64)),
+            Err(_) if digits.is_empty() => format!("Expected digits after the {} prefix", name),
+            Err(_) => format!("Number too large for a {} literal", name),
+        };
+
+        self.make_error_token(&msg)
+    }
+
     fn identifier(&mut self) -> Token {
-        while self.peek() != None && is_alpha(self.peek().unwrap()) {
+        let unicode = self.unicode_identifiers;
+
+        while self.peek().is_some() && is_identifier_part(self.peek().unwrap(), unicode) {
             self.advance();
         }
 
@@ -288,69 +414,142 @@ impl Scanner {
     }
 
     fn identifier_type(&mut self) -> TokenType {
-        let value = &self.source.contents[self.previous..self.current];
-        match value {
-            "true" => TokenType::True,
-            "false" => TokenType::False,
-            "nil" => TokenType::Nil,
-            "var" => TokenType::Var,
-            "fin" => TokenType::Fin,
-            "print" => TokenType::Print,
-            "and" => TokenType::And,
-            "or" => TokenType::Or,
-            "if" => TokenType::If,
-            "then" => TokenType::Then,
-            "else" => TokenType::Else,
-            "endif" => TokenType::EndIf,
-            "loop" => TokenType::Loop,
-            "while" => TokenType::While,
-            "endloop" => TokenType::EndLoop,
-            "break" => TokenType::Break,
-            "continue" => TokenType::Continue,
-            "fun" => TokenType::Fun,
-            "class" => TokenType::Class,
-            "con" => TokenType::Con,
-            "return" => TokenType::Return,
-            "for" => TokenType::For,
-            "import" => TokenType::Import,
-            _ => TokenType::Ident(value.to_string().into_boxed_str()),
-        }
+        let value = &self.source.contents[self.start..self.current];
+        TokenType::keyword(value)
+            .unwrap_or_else(|| TokenType::Ident(value.to_string().into_boxed_str()))
     }
 
     fn single_line_comment(&mut self) -> Token {
-        while self.peek() != None && self.peek() != Some("\n") {
+        while self.peek().is_some() && line_break_at(self.remaining()).is_none() {
             self.advance();
         }
 
-        // incerment previous by two so that the comment message doesn't contain the starting '//'.
-        let value = self.source.contents[self.previous + 2..self.current].to_string();
+        // skip the starting '//' so that the comment message doesn't contain it.
+        let value = self.source.contents[self.start + 2..self.current].to_string();
         self.make_token(TokenType::Comment(value.into_boxed_str(), false))
     }
 
-    fn scan_string(&mut self) -> Token {
-        // first quote
-        self.skip_next();
+    /// Scan the text of a string up to its closing quote, or up to the `{`
+    /// that starts an interpolation. `continued` is whether the text follows
+    /// the `}` that ends an interpolation, rather than the opening quote.
+    /// `{{` and `}}` stand for literal braces.
+    fn scan_string(&mut self, continued: bool) -> Token {
+        // the opening quote or brace has already been consumed, the token's
+        // span includes it.
+        let mut value = String::new();
+
+        loop {
+            let ch = match self.remaining().chars().next() {
+                Some(ch) => ch,
+                None => return self.make_error_token("Unterminated string"),
+            };
+            self.current += ch.len_utf8();
+
+            match ch {
+                '"' => break,
+                '{' | '}' if self.remaining().starts_with(ch) => {
+                    self.current += 1;
+                    value.push(ch);
+                }
+                '{' => {
+                    self.interpolations.push(0);
 
-        while self.peek() != Some("\"") {
-            if self.peek() == None {
-                return self.make_error_token("Unterminated string");
+                    let value = value.into_boxed_str();
+                    return match continued {
+                        true => self.make_token(TokenType::InterpolationMid(value)),
+                        false => self.make_token(TokenType::InterpolationStart(value)),
+                    };
+                }
+                ch => value.push(ch),
             }
+        }
+
+        let value = value.into_boxed_str();
+        match continued {
+            true => self.make_token(TokenType::InterpolationEnd(value)),
+            false => self.make_token(TokenType::String(value)),
+        }
+    }
+
+    /// Scan a `b"..."` literal. Every character stands for its UTF-8 bytes,
+    /// except for the escapes `\xNN`, `\\` and `\"`.
+    fn scan_bytes(&mut self) -> Token {
+        // `b` and the opening quote
+        self.current += 2;
+
+        let mut bytes = vec![];
+        let mut error = None;
+
+        loop {
+            let ch = match self.remaining().chars().next() {
+                Some(ch) => ch,
+                None => return self.make_error_token("Unterminated byte string"),
+            };
+            self.current += ch.len_utf8();
+
+            match ch {
+                '"' => break,
+                '\\' => match self.remaining().chars().next() {
+                    Some(escaped @ '\\') | Some(escaped @ '"') => {
+                        self.current += 1;
+                        bytes.push(escaped as u8);
+                    }
+                    Some('x') => {
+                        self.current += 1;
+                        let digits = self.remaining().get(..2).unwrap_or("");
+
+                        match u8::from_str_radix(digits, 16) {
+                            Ok(byte) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                                self.current += 2;
+                                bytes.push(byte);
+                            }
+                            _ => {
+                                error.get_or_insert("Invalid hex escape in byte string");
+                            }
+                        }
+                    }
+                    _ => {
+                        error.get_or_insert("Invalid escape in byte string");
+                    }
+                },
+                ch => {
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+
+        // the whole literal is skipped, so scanning continues after it.
+        match error {
+            Some(msg) => {
+                self.make_error_token(msg)
+            }
+            None => self.make_token(TokenType::Bytes(bytes.into_boxed_slice())),
+        }
+    }
+
+    /// Skip a `#!` line at the very start of the source, so that scripts can
+    /// be made executable. The line break after it is still scanned.
+    fn skip_shebang(&mut self) {
+        if !self.remaining().starts_with("#!") {
+            return;
+        }
+
+        while self.peek().is_some() && line_break_at(self.remaining()).is_none() {
             self.advance();
         }
 
-        let value = self.source.contents[self.previous..self.current].to_string();
-        // closing quote
-        self.advance();
         self.skip_next();
-
-        self.make_token(TokenType::String(value.into_boxed_str()))
     }
 
     fn skip_whitespace(&mut self) -> &mut Self {
-        while self.peek() != None && is_whitespace(self.peek().unwrap()) {
+        while self.peek().is_some()
+            && is_whitespace(self.peek().unwrap())
+            && line_break_at(self.remaining()).is_none()
+        {
             self.advance();
         }
-        self.previous = self.current;
+        self.start = self.current;
         self
     }
 }
@@ -384,24 +583,31 @@ fn is_alpha(string: &str) -> bool {
         .all(|b| matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'_'))
 }
 
-fn is_digit(string: &str) -> bool {
-    string.as_bytes()[0].is_ascii_digit()
+/// Whether an identifier can start with `string`: a letter or an underscore.
+fn is_identifier_start(string: &str, unicode: bool) -> bool {
+    is_alpha(string) || unicode && string.chars().all(char::is_alphabetic)
 }
 
-fn is_octal(string: &str) -> bool {
-    // Todo: isn't there a better way to do this?
-    matches!(string, "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7")
+/// Whether `string` can follow the start of an identifier: a letter, a digit
+/// or an underscore.
+fn is_identifier_part(string: &str, unicode: bool) -> bool {
+    is_alpha(string)
+        || is_digit(string)
+        || unicode && string.chars().all(char::is_alphanumeric)
 }
 
-fn is_hex(string: &str) -> bool {
-    is_digit(string) || string
-        .bytes()
-        .all(|b| matches!(b, b'a'..=b'f' | b'A'..=b'F'))
+fn is_digit(string: &str) -> bool {
+    string.as_bytes()[0].is_ascii_digit()
+}
+
+fn is_alphanumeric(string: &str) -> bool {
+    string.bytes().all(|b| b.is_ascii_alphanumeric())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::compiler::token::KEYWORDS;
 
     #[test]
     fn scan_tokens() {
@@ -433,6 +639,8 @@ mod tests {
             (",", TokenType::Comma),
             (".", TokenType::Dot),
             (":", TokenType::Colon),
+            ("?", TokenType::Question),
+            ("|>", TokenType::Pipe),
             ("true", TokenType::True),
             ("false", TokenType::False),
             ("nil", TokenType::Nil),
@@ -441,9 +649,11 @@ mod tests {
             ("print", TokenType::Print),
             ("and", TokenType::And),
             ("or", TokenType::Or),
+            ("not", TokenType::Not),
             ("if", TokenType::If),
             ("then", TokenType::Then),
             ("else", TokenType::Else),
+            ("elif", TokenType::Elif),
             ("endif", TokenType::EndIf),
             ("loop", TokenType::Loop),
             ("while", TokenType::While),
@@ -455,6 +665,7 @@ mod tests {
             ("con", TokenType::Con),
             ("return", TokenType::Return),
             ("for", TokenType::For),
+            ("in", TokenType::In),
             ("import", TokenType::Import),
         ];
 
@@ -469,6 +680,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scan_keywords() {
+        for (keyword, token_type) in KEYWORDS {
+            let source = Source::new(keyword, "");
+            let mut scanner = Scanner::new(source);
+
+            let token = scanner.scan_token();
+
+            assert_eq!(&token.token_type, token_type);
+            assert_eq!(token.syntax(), *keyword);
+        }
+    }
+
     #[test]
     fn scan_number_token() {
         let tests = vec![
@@ -497,6 +721,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scan_binary_and_octal() {
+        for (src, num) in [("0b1010", 10.0), ("0B0", 0.0), ("0o17", 15.0), ("0O777", 511.0)] {
+            let token = Scanner::new(Source::new(src, "")).scan_token();
+
+            assert_eq!(token.token_type, TokenType::Number(num));
+            assert_eq!(token.lexeme(), src);
+        }
+    }
+
+    #[test]
+    fn digits_outside_radix() {
+        let tests = [
+            ("0b12 1", "Invalid digit `2` in binary literal", (3, 4)),
+            ("0b1a 1", "Invalid digit `a` in binary literal", (3, 4)),
+            ("0o178 1", "Invalid digit `8` in octal literal", (4, 5)),
+            ("0o9 1", "Invalid digit `9` in octal literal", (2, 3)),
+            ("0x2g 1", "Invalid digit `g` in hexadecimal literal", (3, 4)),
+            ("0b 1", "Expected digits after the binary prefix", (0, 2)),
+        ];
+
+        for (src, msg, (start, end)) in tests {
+            let mut scanner = Scanner::new(Source::new(src, ""));
+            let token = scanner.scan_token();
+
+            assert_eq!(token.token_type, TokenType::Error(msg.into()), "{}", src);
+            assert_eq!((token.span.start, token.span.end), (start, end), "{}", src);
+            // scanning carries on after the literal.
+            assert_eq!(scanner.scan_token().token_type, TokenType::Number(1.0));
+        }
+    }
+
     #[test]
     fn skip_whitespace() {
         let tests = vec![
@@ -518,6 +774,111 @@ mod tests {
         }
     }
 
+    fn tokens_of(mut scanner: Scanner) -> Vec<Token> {
+        let mut tokens = vec![];
+
+        loop {
+            let token = scanner.scan_token();
+            if token.token_type == TokenType::Eof {
+                return tokens;
+            }
+            tokens.push(token);
+        }
+    }
+
+    #[test]
+    fn lexeme_keeps_source_text() {
+        let tokens = tokens_of(Scanner::new(Source::new(r#"print "a\tb" 0x2F"#, "")));
+
+        // strings don't have escapes, so only the quotes differ.
+        assert_eq!(tokens[1].token_type, TokenType::String(r"a\tb".into()));
+        assert_eq!(tokens[1].lexeme(), r#""a\tb""#);
+
+        assert_eq!(tokens[2].token_type, TokenType::Number(47.0));
+        assert_eq!(tokens[2].lexeme(), "0x2F");
+
+        assert_eq!(tokens[0].lexeme(), "print");
+    }
+
+    #[test]
+    fn trivia_is_kept_in_trivia_mode() {
+        let src = "var a  = 1 // one\n\tprint a";
+        let tokens = tokens_of(Scanner::with_trivia(Source::new(src, "")));
+
+        let text: String = tokens.iter().map(|token| token.span.text()).collect();
+        assert_eq!(text, src);
+
+        let trivia: Vec<_> = tokens.iter().filter(|token| token.is_trivia()).map(|token| token.span.text()).collect();
+        assert_eq!(trivia, vec![" ", "  ", " ", " ", "// one", "\t", " "]);
+    }
+
+    #[test]
+    fn whitespace_is_skipped_by_default() {
+        let src = "var a  = 1 // one\n\tprint a";
+        let tokens = tokens_of(Scanner::new(Source::new(src, "")));
+
+        assert!(tokens.iter().all(|token| token.token_type != TokenType::Whitespace));
+        assert_eq!(tokens.iter().filter(|token| token.is_trivia()).count(), 1);
+
+        let with_trivia = tokens_of(Scanner::with_trivia(Source::new(src, "")));
+        let without: Vec<_> = with_trivia.into_iter().filter(|token| token.token_type != TokenType::Whitespace).collect();
+        assert_eq!(without, tokens);
+    }
+
+    #[test]
+    fn scan_bytes_token() {
+        let tests: Vec<(&str, &[u8])> = vec![
+            ("b\"abc\"", b"abc"),
+            ("b\"\"", b""),
+            ("b\"\\x00\\x7F\\xff\"", &[0x00, 0x7f, 0xff]),
+            ("b\"\\\"\\\\\"", b"\"\\"),
+            ("b\"猫\"", "猫".as_bytes()),
+        ];
+
+        for (src, bytes) in tests {
+            let mut scanner = Scanner::new(Source::new(src, ""));
+            let token = scanner.scan_token();
+
+            assert_eq!(token.token_type, TokenType::Bytes(bytes.into()));
+            assert_eq!(token.span.text(), src);
+            assert_eq!(scanner.scan_token().token_type, TokenType::Eof);
+        }
+    }
+
+    #[test]
+    fn b_identifier_is_not_bytes() {
+        let mut scanner = Scanner::new(Source::new("b \"\"", ""));
+
+        assert_eq!(scanner.scan_token().token_type, TokenType::Ident("b".into()));
+        assert_eq!(scanner.scan_token().token_type, TokenType::String("".into()));
+    }
+
+    #[test]
+    fn invalid_byte_escapes() {
+        let tests = vec![
+            ("b\"\\xZZ\" 1", "Invalid hex escape in byte string"),
+            ("b\"\\x4\" 1", "Invalid hex escape in byte string"),
+            ("b\"\\x+f\" 1", "Invalid hex escape in byte string"),
+            ("b\"\\n\" 1", "Invalid escape in byte string"),
+        ];
+
+        for (src, msg) in tests {
+            let mut scanner = Scanner::new(Source::new(src, ""));
+            let token = scanner.scan_token();
+
+            assert_eq!(token.token_type, TokenType::Error(msg.into()));
+            assert_eq!(token.span.text(), &src[..src.len() - 2]);
+            // scanning carries on after the literal.
+            assert_eq!(scanner.scan_token().token_type, TokenType::Number(1.0));
+        }
+
+        let mut scanner = Scanner::new(Source::new("b\"abc", ""));
+        assert_eq!(
+            scanner.scan_token().token_type,
+            TokenType::Error("Unterminated byte string".into())
+        );
+    }
+
     #[test]
     fn scan_string_token() {
         let tests = vec![
@@ -535,6 +896,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scan_interpolated_string() {
+        let tokens = tokens_of(Scanner::new(Source::new(r#""a {x} b { {1: 2} }!" "{{c}}""#, "")));
+        let types: Vec<_> = tokens.iter().map(|token| token.token_type.clone()).collect();
+
+        assert_eq!(
+            types,
+            [
+                TokenType::InterpolationStart("a ".into()),
+                TokenType::Ident("x".into()),
+                TokenType::InterpolationMid(" b ".into()),
+                TokenType::LeftBrace,
+                TokenType::Number(1.0),
+                TokenType::Colon,
+                TokenType::Number(2.0),
+                TokenType::RightBrace,
+                TokenType::InterpolationEnd("!".into()),
+                TokenType::String("{c}".into()),
+            ]
+        );
+
+        let lexemes: Vec<_> = tokens.iter().map(Token::lexeme).collect();
+        assert_eq!(lexemes[0], "\"a {");
+        assert_eq!(lexemes[2], "} b {");
+        assert_eq!(lexemes[8], "}!\"");
+    }
+
     #[test]
     fn token_span() {
         let src = Source::new("123 val 猫", "");
@@ -561,6 +949,19 @@ mod tests {
         assert_eq!(token.syntax(), "radishes");
     }
 
+    #[test]
+    fn word_operators_inside_identifiers() {
+        for ident in &["android", "order", "nothing", "notable", "band", "_or"] {
+            let mut scanner = Scanner::new(Source::new(ident, ""));
+            assert_eq!(scanner.scan_token().token_type, TokenType::Ident((*ident).into()));
+            assert_eq!(scanner.scan_token().token_type, TokenType::Eof);
+        }
+
+        let mut scanner = Scanner::new(Source::new("not x", ""));
+        assert_eq!(scanner.scan_token().token_type, TokenType::Not);
+        assert_eq!(scanner.scan_token().token_type, TokenType::Ident("x".into()));
+    }
+
     #[test]
     fn scan_newline_token() {
         let src = Source::new("\n", "");
@@ -570,6 +971,126 @@ mod tests {
         assert_eq!(token.syntax(), "\\n");
     }
 
+    fn scan_all(scanner: &mut Scanner) -> Vec<(TokenType, usize, usize)> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = scanner.scan_token();
+            let eof = token.token_type == TokenType::Eof;
+            tokens.push((token.token_type, token.span.start, token.span.end));
+
+            if eof {
+                return tokens;
+            }
+        }
+    }
+
+    #[test]
+    fn scan_from_mid_file() {
+        let src = Source::new("var a = 1\nprint a + 2 // done\nvar b = \"é\"\n", "");
+        let full = scan_all(&mut Scanner::new(Rc::clone(&src)));
+
+        let offset = src.contents.find("print").unwrap();
+        let rest: Vec<_> = full.iter().skip_while(|t| t.1 < offset).cloned().collect();
+
+        assert_eq!(scan_all(&mut Scanner::at(Rc::clone(&src), offset).unwrap()), rest);
+
+        let mut scanner = Scanner::new(Rc::clone(&src));
+        scan_all(&mut scanner);
+        scanner.scan_from(offset).unwrap();
+        assert_eq!(scan_all(&mut scanner), rest);
+    }
+
+    #[test]
+    fn scan_from_invalid_offset() {
+        let src = Source::new("\"é\"", "");
+        let mut scanner = Scanner::new(Rc::clone(&src));
+
+        assert_eq!(scanner.scan_from(2), Err(InvalidOffset(2)));
+        assert_eq!(scanner.scan_from(100), Err(InvalidOffset(100)));
+        assert!(Scanner::at(src, 4).is_ok());
+    }
+
+    #[test]
+    fn lexeme_starts_where_last_token_ended() {
+        let src = Source::new("  var\ta = 0b // one\n\t\"x\"  ~ 猫 b\"\\q\"\n  0x  3 // two", "");
+        let mut scanner = Scanner::new(Rc::clone(&src));
+        let mut kinds = vec![];
+
+        loop {
+            let token = scanner.scan_token();
+
+            assert_eq!(scanner.start, scanner.current);
+            assert_eq!(scanner.offset(), token.span.end);
+
+            kinds.push(token.token_type.clone());
+
+            if token.token_type == TokenType::Eof {
+                break;
+            }
+        }
+
+        // the scan covered error tokens too.
+        let errors = kinds.iter().filter(|kind| matches!(kind, TokenType::Error(_))).count();
+        assert_eq!(errors, 5);
+
+        // trivia tokens meet end to end, so they cover the whole source.
+        let mut scanner = Scanner::with_trivia(Rc::clone(&src));
+        let mut end = 0;
+
+        loop {
+            let token = scanner.scan_token();
+
+            assert_eq!(token.span.start, end);
+            assert_eq!(scanner.start, scanner.current);
+            end = token.span.end;
+
+            if token.token_type == TokenType::Eof {
+                break;
+            }
+        }
+
+        assert_eq!(end, src.contents.len());
+    }
+
+    #[test]
+    fn scan_crlf_newline_token() {
+        let src = Source::new("\r\n", "");
+        let mut scanner = Scanner::new(src);
+        let token = scanner.scan_token();
+
+        assert_eq!(token.token_type, TokenType::Newline);
+        assert_eq!((token.span.start, token.span.end), (0, 2));
+        assert_eq!(scanner.scan_token().token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn crlf_tokens_match_lf() {
+        let lf = "var a = 1 // one\nprint a\n\nprint \"x\"\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let mut lf_scanner = Scanner::new(Source::new(lf, ""));
+        let mut crlf_scanner = Scanner::new(Source::new(&crlf, ""));
+
+        loop {
+            let lf_token = lf_scanner.scan_token();
+            let crlf_token = crlf_scanner.scan_token();
+
+            assert_eq!(lf_token.token_type, crlf_token.token_type);
+            assert_eq!(
+                lf_token.span.source.line_col(lf_token.span.start),
+                crlf_token.span.source.line_col(crlf_token.span.start),
+            );
+
+            let lines = crlf_token.span.text().matches('\n').count();
+            assert_eq!(lf_token.span.end - lf_token.span.start + lines, crlf_token.span.end - crlf_token.span.start);
+
+            if lf_token.token_type == TokenType::Eof {
+                break;
+            }
+        }
+    }
+
     #[test]
     fn scan_single_line_comment() {
         let src = Source::new("//this is a comment", "");
@@ -593,6 +1114,36 @@ mod tests {
         assert_eq!(token.syntax(), "猫");
     }
 
+    #[test]
+    fn identifiers_contain_digits() {
+        for name in ["x1", "_tmp2", "count2", "x1y2", "_"] {
+            let token = Scanner::new(Source::new(name, "")).scan_token();
+            assert_eq!(token.token_type, TokenType::Ident(name.into()));
+        }
+
+        let tokens = tokens_of(Scanner::new(Source::new("1x", "")));
+        let types: Vec<_> = tokens.into_iter().map(|token| token.token_type).collect();
+        assert_eq!(types, [TokenType::Number(1.0), TokenType::Ident("x".into())]);
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        let token = Scanner::new(Source::new("café", ""))
+            .with_unicode_identifiers(true)
+            .scan_token();
+        assert_eq!(token.token_type, TokenType::Ident("café".into()));
+
+        let token = Scanner::new(Source::new("猫2", ""))
+            .with_unicode_identifiers(true)
+            .scan_token();
+        assert_eq!(token.token_type, TokenType::Ident("猫2".into()));
+
+        // off by default, so the accent starts an invalid token.
+        let tokens = tokens_of(Scanner::new(Source::new("café", "")));
+        assert_eq!(tokens[0].token_type, TokenType::Ident("caf".into()));
+        assert_eq!(tokens[1].token_type, TokenType::Error("é".into()));
+    }
+
     #[test]
     fn scan_empty_file() {
         let src = Source::new("", "");
@@ -601,4 +1152,53 @@ mod tests {
         assert_eq!(token.token_type, TokenType::Eof);
         assert_eq!(token.syntax(), "<Eof>");
     }
+
+    #[test]
+    fn skip_leading_shebang() {
+        let src = Source::new("#!/usr/bin/env radish\nprint 1\n", "");
+        let mut scanner = Scanner::new(src);
+
+        assert_eq!(scanner.scan_token().token_type, TokenType::Newline);
+
+        let print = scanner.scan_token();
+        assert_eq!(print.token_type, TokenType::Print);
+        assert_eq!(print.span.source.line_col(print.span.start), (1, 0));
+        assert_eq!(print.syntax(), "print");
+    }
+
+    #[test]
+    fn hash_elsewhere_is_an_error() {
+        for src in [" #!/usr/bin/env radish", "print 1 # 2", "print 1\n#!"] {
+            let mut scanner = Scanner::new(Source::new(src, ""));
+
+            let mut token = scanner.scan_token();
+            while token.token_type != TokenType::Eof && !matches!(token.token_type, TokenType::Error(_)) {
+                token = scanner.scan_token();
+            }
+
+            assert_eq!(token.syntax(), "#", "in {:?}", src);
+        }
+    }
+
+    #[test]
+    fn leading_bom_scans_cleanly() {
+        let mut scanner = Scanner::new(Source::new("\u{FEFF}print 1", ""));
+
+        let print = scanner.scan_token();
+        assert_eq!(print.token_type, TokenType::Print);
+        assert_eq!((print.span.start, print.span.end), (0, 5));
+        assert_eq!(scanner.scan_token().token_type, TokenType::Number(1.0));
+    }
+
+    #[test]
+    fn bom_mid_file_is_an_error() {
+        let mut scanner = Scanner::new(Source::new("print\n\u{FEFF}1", ""));
+
+        scanner.scan_token();
+        scanner.scan_token();
+
+        let token = scanner.scan_token();
+        assert!(matches!(token.token_type, TokenType::Error(_)));
+        assert_eq!(token.syntax(), "\u{FEFF}");
+    }
 }
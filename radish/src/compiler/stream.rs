@@ -0,0 +1,261 @@
+//! Scanning a source as it's read, for sources too large to comfortably hold
+//! in memory all at once.
+//!
+//! The text is read a window at a time and each window is scanned with a
+//! regular [`Scanner`]. A window is only cut after a line break outside any
+//! string or interpolation, which no token can continue past, so the tokens
+//! are the same as those of scanning the whole source.
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+
+use crate::common::source::Source;
+use crate::compiler::{scanner::Scanner, token::TokenType};
+
+/// How much text to read before scanning it.
+const WINDOW_SIZE: usize = 64 * 1024;
+
+/// A token scanned from a stream. As the text it was scanned from may be gone
+/// by the time it's used, it has the offsets of its lexeme instead of a span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamToken {
+    pub token_type: TokenType,
+    /// The byte offset into the stream at which the token starts. Like the
+    /// offsets into a [`Source`], these don't count a leading byte order mark.
+    pub start: usize,
+    /// The byte offset into the stream at which the token ends.
+    pub end: usize,
+}
+
+/// Scans the tokens of a source from a reader, up to and including `Eof`.
+pub struct StreamScanner<R> {
+    reader: R,
+    /// The text that hasn't been scanned for good yet, preceded by the line
+    /// break ending the text that has, if there is any.
+    window: String,
+    /// The stream offset of the window's first byte.
+    window_start: usize,
+    /// The offset into the window at which scanning resumes.
+    resume: usize,
+    /// Tokens scanned but not yet returned.
+    tokens: VecDeque<StreamToken>,
+    /// Whether the reader has run out.
+    read_all: bool,
+    /// Whether every token has been scanned, or reading failed.
+    finished: bool,
+    trivia: bool,
+    unicode_identifiers: bool,
+}
+
+impl<R: BufRead> StreamScanner<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            window: String::new(),
+            window_start: 0,
+            resume: 0,
+            tokens: VecDeque::new(),
+            read_all: false,
+            finished: false,
+            trivia: false,
+            unicode_identifiers: false,
+        }
+    }
+
+    /// Keep trivia, like [`Scanner::with_trivia`].
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    /// Allow Unicode identifiers, like [`Scanner::with_unicode_identifiers`].
+    pub fn with_unicode_identifiers(mut self, allowed: bool) -> Self {
+        self.unicode_identifiers = allowed;
+        self
+    }
+
+    /// Read whole lines onto the window until it has grown by about
+    /// [`WINDOW_SIZE`], or the reader runs out.
+    fn fill(&mut self) -> io::Result<()> {
+        let target = self.window.len() + WINDOW_SIZE;
+
+        while self.window.len() < target {
+            if self.reader.read_line(&mut self.window)? == 0 {
+                self.read_all = true;
+                break;
+            }
+        }
+
+        // offsets are into the text after a byte order mark, as in a `Source`.
+        if self.window_start == 0 && self.window.starts_with('\u{feff}') {
+            self.window.drain(..'\u{feff}'.len_utf8());
+        }
+
+        Ok(())
+    }
+
+    /// Scan the window up to its last line break that ends a token for good,
+    /// or to the end once everything has been read, reading more text until
+    /// there is such a line break.
+    fn scan_window(&mut self) -> io::Result<()> {
+        loop {
+            self.fill()?;
+
+            let source = Source::new(&self.window, "");
+            let scanner = match self.trivia {
+                true => Scanner::with_trivia(source),
+                false => Scanner::new(source),
+            };
+            let mut scanner = scanner.with_unicode_identifiers(self.unicode_identifiers);
+            scanner
+                .scan_from(self.resume)
+                .expect("scanning resumes after a line break");
+
+            let mut tokens = vec![];
+            let mut cut = None;
+
+            loop {
+                let token = scanner.scan_token();
+                let token_type = token.token_type.clone();
+
+                tokens.push(token);
+
+                if token_type == TokenType::Newline && !scanner.in_interpolation() {
+                    cut = Some(tokens.len());
+                } else if token_type == TokenType::Eof {
+                    break;
+                }
+            }
+
+            // a token ending with the window may go on in the text after it.
+            let cut = match (self.read_all, cut) {
+                (true, _) => tokens.len(),
+                (false, Some(cut)) => cut,
+                (false, None) => continue,
+            };
+
+            let window_start = self.window_start;
+            self.tokens.extend(tokens.drain(..cut).map(|token| StreamToken {
+                token_type: token.token_type,
+                start: window_start + token.span.start,
+                end: window_start + token.span.end,
+            }));
+
+            if self.read_all {
+                self.finished = true;
+            } else {
+                // keep the line break, so that the next scan doesn't start at
+                // offset 0 and take a `#!` there for a shebang.
+                let line_break = self.tokens.back().expect("the window was cut after a line break");
+                let line_break = (line_break.start - window_start, line_break.end - window_start);
+
+                self.window.drain(..line_break.0);
+                self.window_start += line_break.0;
+                self.resume = line_break.1 - line_break.0;
+            }
+
+            return Ok(());
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StreamScanner<R> {
+    type Item = io::Result<StreamToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tokens.is_empty() && !self.finished {
+            if let Err(err) = self.scan_window() {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        }
+
+        self.tokens.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::compiler::token::Token;
+
+    fn stream_tokens(src: &str) -> Vec<StreamToken> {
+        StreamScanner::new(Cursor::new(src.as_bytes()))
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    fn memory_tokens(src: &str) -> Vec<StreamToken> {
+        let mut scanner = Scanner::new(Source::new(src, ""));
+        let mut tokens = vec![];
+
+        loop {
+            let Token { token_type, span } = scanner.scan_token();
+            let eof = token_type == TokenType::Eof;
+
+            tokens.push(StreamToken { token_type, start: span.start, end: span.end });
+
+            if eof {
+                return tokens;
+            }
+        }
+    }
+
+    #[test]
+    fn large_source_scans_like_in_memory() {
+        let lines = [
+            "var count = 0x1F + 2.5e3 // a comment",
+            "fun greet(name) {",
+            "    print \"hello {name}, {{not}} {\"nested {count}\"}!\"",
+            "}",
+            "var long = \"a string",
+            "over two lines\"",
+            "var total = [1, 2, 3] |> sort",
+            "var bytes = b\"\\x00\\\"\"",
+            "print \"{",
+            "    count",
+            "}\" + \"猫\"",
+        ];
+
+        let mut src = String::new();
+        while src.len() < 4 * WINDOW_SIZE {
+            for line in &lines {
+                src.push_str(line);
+                src.push('\n');
+            }
+        }
+        // an unterminated string runs to the end of the last window.
+        src.push_str("print \"never closed\n\n");
+
+        let streamed = stream_tokens(&src);
+
+        assert_eq!(streamed, memory_tokens(&src));
+        assert_eq!(streamed.last().unwrap().token_type, TokenType::Eof);
+    }
+
+    #[test]
+    fn offsets_are_into_the_stream() {
+        let tokens = stream_tokens("\u{feff}#!/usr/bin/env radish\nprint 1\n#!");
+
+        assert_eq!(tokens, memory_tokens("\u{feff}#!/usr/bin/env radish\nprint 1\n#!"));
+        assert_eq!((tokens[1].start, tokens[1].end), (22, 27));
+        assert!(matches!(tokens.last().unwrap().token_type, TokenType::Eof));
+    }
+
+    #[test]
+    fn empty_stream() {
+        assert_eq!(stream_tokens(""), [StreamToken { token_type: TokenType::Eof, start: 0, end: 0 }]);
+    }
+
+    #[test]
+    fn read_errors_end_the_stream() {
+        let mut scanner = StreamScanner::new(Cursor::new(&[0xff, b'\n'][..]));
+
+        assert!(matches!(scanner.next(), Some(Err(_))));
+        assert!(scanner.next().is_none());
+    }
+}
@@ -4,6 +4,7 @@ use std::{
 };
 
 use crate::common::span::Span;
+use crate::vm::value::escape_bytes;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
@@ -63,6 +64,10 @@ pub enum TokenType {
     Dot,
     // :
     Colon,
+    // ?
+    Question,
+    // |>
+    Pipe,
     // true
     True,
     // false
@@ -79,12 +84,16 @@ pub enum TokenType {
     And,
     // or
     Or,
+    // not
+    Not,
     // if
     If,
     // then
     Then,
     // else
     Else,
+    // elif
+    Elif,
     // endif
     EndIf,
     // loop
@@ -107,6 +116,8 @@ pub enum TokenType {
     Return,
     // for
     For,
+    // in
+    In,
     // import
     Import,
     // number
@@ -115,8 +126,18 @@ pub enum TokenType {
     Ident(Box<str>),
     // bool is for if the comment is multiline.
     Comment(Box<str>, bool),
+    // spaces and tabs, only scanned when keeping trivia.
+    Whitespace,
     // string
     String(Box<str>),
+    // "text{ the text of a string up to its first interpolation.
+    InterpolationStart(Box<str>),
+    // }text{ the text of a string between two interpolations.
+    InterpolationMid(Box<str>),
+    // }text" the text of a string after its last interpolation.
+    InterpolationEnd(Box<str>),
+    // b"bytes"
+    Bytes(Box<[u8]>),
 
     Error(Box<str>),
     // <Eof>
@@ -125,8 +146,48 @@ pub enum TokenType {
     Empty,
 }
 
+/// Reserved words and the token type each one scans to.
+///
+/// Adding a keyword only requires a new entry here (and its `TokenType` variant).
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("true", TokenType::True),
+    ("false", TokenType::False),
+    ("nil", TokenType::Nil),
+    ("var", TokenType::Var),
+    ("fin", TokenType::Fin),
+    ("print", TokenType::Print),
+    ("and", TokenType::And),
+    ("or", TokenType::Or),
+    ("not", TokenType::Not),
+    ("if", TokenType::If),
+    ("then", TokenType::Then),
+    ("else", TokenType::Else),
+    ("elif", TokenType::Elif),
+    ("endif", TokenType::EndIf),
+    ("loop", TokenType::Loop),
+    ("while", TokenType::While),
+    ("endloop", TokenType::EndLoop),
+    ("break", TokenType::Break),
+    ("continue", TokenType::Continue),
+    ("fun", TokenType::Fun),
+    ("class", TokenType::Class),
+    ("con", TokenType::Con),
+    ("return", TokenType::Return),
+    ("for", TokenType::For),
+    ("in", TokenType::In),
+    ("import", TokenType::Import),
+];
+
 impl TokenType {
-    fn literal_syntax(&self) -> &'static str {
+    /// Look up the token type of a reserved word, if `ident` is one.
+    pub fn keyword(ident: &str) -> Option<TokenType> {
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == ident)
+            .map(|(_, token_type)| token_type.clone())
+    }
+
+    pub(crate) fn literal_syntax(&self) -> &'static str {
         use TokenType::*;
 
         match self {
@@ -158,6 +219,8 @@ impl TokenType {
             Comma => ",",
             Dot => ".",
             Colon => ":",
+            Question => "?",
+            Pipe => "|>",
             True => "true",
             False => "false",
             Nil => "nil",
@@ -166,9 +229,11 @@ impl TokenType {
             Print => "print",
             And => "and",
             Or => "or",
+            Not => "not",
             If => "if",
             Then => "then",
             Else => "else",
+            Elif => "elif",
             EndIf => "endif",
             Loop => "loop",
             While => "while",
@@ -180,14 +245,30 @@ impl TokenType {
             Con => "con",
             Return => "return",
             For => "for",
+            In => "in",
             Import => "import",
 
             Eof => "<Eof>",
+            Whitespace => "<whitespace>",
 
             _ => "ERROR",
         }
     }
 
+    /// The token that closes a block or group opened by this one, if this is
+    /// an opening delimiter. A `then` block can also be closed by `else` or
+    /// `elif`, but `endif` is what ends the whole statement.
+    pub fn closing_delimiter(&self) -> Option<TokenType> {
+        Some(match self {
+            TokenType::LeftParen => TokenType::RightParen,
+            TokenType::LeftBracket => TokenType::RightBracket,
+            TokenType::LeftBrace => TokenType::RightBrace,
+            TokenType::Then | TokenType::Else => TokenType::EndIf,
+            TokenType::Loop => TokenType::EndLoop,
+            _ => return None,
+        })
+    }
+
     pub fn syntax(&self) -> Cow<'static, str> {
         use TokenType::*;
 
@@ -196,6 +277,10 @@ impl TokenType {
             Ident(id) => id.to_string().into(),
             Comment(msg, _) => msg.to_string().into(),
             String(val) => val.to_string().into(),
+            InterpolationStart(val) => format!("\"{}{{", val).into(),
+            InterpolationMid(val) => format!("}}{}{{", val).into(),
+            InterpolationEnd(val) => format!("}}{}\"", val).into(),
+            Bytes(val) => format!("b\"{}\"", escape_bytes(val)).into(),
             Error(err) => err.to_string().into(),
 
             _ => self.literal_syntax().into(),
@@ -222,9 +307,22 @@ impl Token {
         self.token_type.syntax()
     }
 
+    /// The token's exact text in the source. Unlike the value of a literal,
+    /// this keeps any quotes, escapes and prefixes, e.g. `"a"` for the string
+    /// `a` or `0x2F` for the number `47`.
+    pub fn lexeme(&self) -> &str {
+        self.span.text()
+    }
+
+    /// Whether the token only matters for formatting, so the parser can skip it.
+    pub fn is_trivia(&self) -> bool {
+        matches!(&self.token_type, TokenType::Whitespace | TokenType::Comment(..))
+    }
+
     pub fn is_delimiter(&self) -> bool {
         matches!(&self.token_type, TokenType::RightBrace
             | TokenType::Else
+            | TokenType::Elif
             | TokenType::EndLoop
             | TokenType::EndIf
             | TokenType::RightParen)
@@ -260,6 +358,8 @@ impl Token {
             "," => TokenType::Comma,
             "." => TokenType::Dot,
             ":" => TokenType::Colon,
+            "?" => TokenType::Question,
+            "|>" => TokenType::Pipe,
             "true" => TokenType::True,
             "false" => TokenType::False,
             "nil" => TokenType::Nil,
@@ -268,9 +368,11 @@ impl Token {
             "print" => TokenType::Print,
             "and" => TokenType::And,
             "or" => TokenType::Or,
+            "not" => TokenType::Not,
             "if" => TokenType::If,
             "then" => TokenType::Then,
             "else" => TokenType::Else,
+            "elif" => TokenType::Elif,
             "endif" => TokenType::EndIf,
             "loop" => TokenType::Loop,
             "while" => TokenType::While,
@@ -282,6 +384,7 @@ impl Token {
             "con" => TokenType::Con,
             "return" => TokenType::Return,
             "for" => TokenType::For,
+            "in" => TokenType::In,
             "import" => TokenType::Import,
             "<Eof>" => TokenType::Eof,
 
@@ -290,6 +393,41 @@ impl Token {
     }
 }
 
+/// How a token type is described to people, e.g. in error messages. Tokens
+/// with a fixed spelling are shown as that spelling, the others by what kind
+/// of token they are. The match is deliberately exhaustive, so a new token
+/// type can't be added without a description.
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use TokenType::*;
+
+        let description = match self {
+            Number(_) => "number",
+            Ident(_) => "identifier",
+            Comment(..) => "comment",
+            Whitespace => "whitespace",
+            Newline => "newline",
+            String(_) | InterpolationStart(_) | InterpolationMid(_) | InterpolationEnd(_) => {
+                "string"
+            }
+            Bytes(_) => "byte string",
+            Error(_) => "invalid token",
+            Eof => "end of input",
+            Empty => "nothing",
+
+            Plus | Slash | Star | Minus | Percent | Bang | Equals | LessThan | LessThanEquals
+            | GreaterThan | GreaterThanEquals | EqualsTo | NotEqual | PlusEquals | MinusEquals
+            | MultiplyEquals | DivideEquals | ModuloEquals | LeftParen | RightParen | LeftBrace
+            | RightBrace | LeftBracket | RightBracket | Comma | Dot | Colon | Question | Pipe
+            | True | False | Nil | Var | Fin | Print | And | Or | Not | If | Then | Else | Elif
+            | EndIf | Loop | While | EndLoop | Break | Continue | Fun | Class | Con | Return
+            | For | In | Import => return write!(f, "`{}`", self.literal_syntax()),
+        };
+
+        f.write_str(description)
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -299,3 +437,61 @@ impl fmt::Display for Token {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One of every token type.
+    fn all_token_types() -> Vec<TokenType> {
+        use TokenType::*;
+
+        let mut types = vec![
+            Plus, Slash, Star, Minus, Percent, Bang, Equals, LessThan, LessThanEquals,
+            GreaterThan, GreaterThanEquals, EqualsTo, NotEqual, PlusEquals, MinusEquals,
+            MultiplyEquals, DivideEquals, ModuloEquals, Newline, LeftParen, RightParen,
+            LeftBrace, RightBrace, LeftBracket, RightBracket, Comma, Dot, Colon, Question, Pipe,
+            Number(1.0),
+            Ident("x".into()),
+            Comment("note".into(), false),
+            Whitespace,
+            String("text".into()),
+            InterpolationStart("a".into()),
+            InterpolationMid("b".into()),
+            InterpolationEnd("c".into()),
+            Bytes(Box::new([1])),
+            Error("Unexpected".into()),
+            Eof,
+            Empty,
+        ];
+        types.extend(KEYWORDS.iter().map(|(_, token_type)| token_type.clone()));
+
+        types
+    }
+
+    #[test]
+    fn every_token_type_displays_for_people() {
+        for token_type in all_token_types() {
+            let shown = token_type.to_string();
+
+            assert!(!shown.is_empty(), "{:?} displays as nothing", token_type);
+            assert_ne!(shown, format!("{:?}", token_type));
+            assert!(
+                !shown.contains(char::is_uppercase) && !shown.contains("ERROR"),
+                "{:?} displays as {}",
+                token_type,
+                shown
+            );
+        }
+    }
+
+    #[test]
+    fn token_type_display() {
+        assert_eq!(TokenType::Plus.to_string(), "`+`");
+        assert_eq!(TokenType::Pipe.to_string(), "`|>`");
+        assert_eq!(TokenType::EndLoop.to_string(), "`endloop`");
+        assert_eq!(TokenType::Number(2.5).to_string(), "number");
+        assert_eq!(TokenType::Ident("name".into()).to_string(), "identifier");
+        assert_eq!(TokenType::Eof.to_string(), "end of input");
+    }
+}
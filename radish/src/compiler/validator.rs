@@ -3,7 +3,7 @@
 //! handles simple checks, not more compilcated things like name resolution.
 
 use crate::common::Span;
-use crate::compiler::ast::{Expr, Stmt, VarKind, AST};
+use crate::compiler::ast::{Expr, ForStmt, Stmt, VarKind, AST};
 
 use crate::compiler::{visitor::VisitorResult, Visitor};
 
@@ -12,7 +12,13 @@ use crate::compiler::error::{SyntaxError, SyntaxErrorKind};
 use crate::error::Item;
 
 pub fn validate_ast(ast: &mut AST) -> Result<(), SyntaxError> {
-    AstValidator::validate_ast(ast)
+    AstValidator::validate_ast(ast, false)
+}
+
+/// Validate an AST with the extra checks enabled by `--strict`: variables must
+/// be initialized and functions must return a value on every path.
+pub fn validate_ast_strict(ast: &mut AST) -> Result<(), SyntaxError> {
+    AstValidator::validate_ast(ast, true)
 }
 
 // TODO: rename this.
@@ -25,11 +31,16 @@ enum State {
 struct AstValidator {
     /// Track the AST validator's state.
     state: Vec<State>,
+    /// Whether to run the strict mode checks.
+    strict: bool,
 }
 
 impl AstValidator {
-    pub fn validate_ast(ast: &mut AST) -> Result<(), SyntaxError> {
-        let mut validator = AstValidator { state: Vec::new() };
+    pub fn validate_ast(ast: &mut AST, strict: bool) -> Result<(), SyntaxError> {
+        let mut validator = AstValidator {
+            state: Vec::new(),
+            strict,
+        };
 
         for stmt in ast.items.iter_mut() {
             validator.visit_stmt(stmt)?;
@@ -55,9 +66,27 @@ impl Visitor<'_> for AstValidator {
             .pop()
             .expect("expected validator state not to be empty");
 
+        if self.strict && !always_returns(&fun.body) {
+            let name = if fun.id.name.is_empty() {
+                "anonymous"
+            } else {
+                &fun.id.name
+            };
+
+            let err_kind = SyntaxErrorKind::MissingReturn {
+                item: Item::new(&fun.id.pos, name),
+            };
+
+            return Err(SyntaxError::new(err_kind));
+        }
+
         Ok(())
     }
 
+    fn visit_lambda(&mut self, fun: &mut super::FunctionDecl) -> VisitorResult {
+        self.visit_fun_decl(fun)
+    }
+
     /// Validate the variable's inital value. If it doesn't have an
     /// inital value and its a constant (final) var declaration, then
     /// report an error.
@@ -76,6 +105,13 @@ impl Visitor<'_> for AstValidator {
 
                 Err(SyntaxError::new(err_kind))
             }
+            None if self.strict => {
+                let err_kind = SyntaxErrorKind::MissingVarInit {
+                    item: Item::new(&id.pos, &id.name),
+                };
+
+                Err(SyntaxError::new(err_kind))
+            }
             _ => Ok(()),
         }
     }
@@ -108,6 +144,21 @@ impl Visitor<'_> for AstValidator {
         Ok(())
     }
 
+    /// Mark that we've entered a loop.
+    fn visit_for_stmt(&mut self, stmt: &mut ForStmt) -> VisitorResult {
+        self.visit_expr(&mut stmt.iterable)?;
+
+        self.state.push(State::Loop);
+
+        self.visit_block_stmt(&mut stmt.body)?;
+
+        self.state
+            .pop()
+            .expect("expected validator state not to be empty");
+
+        Ok(())
+    }
+
     /// Verify that the `continue` statement is inside of a loop.
     fn visit_continue_stmt(&mut self) -> VisitorResult {
         if self.state.last() != Some(&State::Loop) {
@@ -125,7 +176,11 @@ impl Visitor<'_> for AstValidator {
     }
 
     /// Verify that the `break` statement is inside of a loop.
-    fn visit_break_stmt(&mut self) -> VisitorResult {
+    fn visit_break_stmt(&mut self, value: &mut Option<Expr>) -> VisitorResult {
+        if let Some(expr) = value {
+            self.visit_expr(expr)?;
+        }
+
         if self.state.last() != Some(&State::Loop) {
             let err_kind = SyntaxErrorKind::BreakOutsideLoop {
                 item: crate::error::Item {
@@ -166,6 +221,32 @@ impl Visitor<'_> for AstValidator {
     }
 }
 
+/// Whether every path through `body` ends in a `return`, or never ends at all.
+fn always_returns(body: &[Stmt]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        Stmt::ReturnStmt(..) => true,
+        Stmt::BlockStmt(body, _) => always_returns(body),
+        Stmt::IfStmt(_, body, Some(alt), _) => {
+            always_returns(body) && always_returns(std::slice::from_ref(alt))
+        }
+        // an endless loop can only be left by returning.
+        Stmt::LoopStmt(body, _) => !breaks(body),
+        _ => false,
+    })
+}
+
+/// Whether `body` contains a `break` out of the loop it belongs to.
+fn breaks(body: &[Stmt]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        Stmt::BreakStmt(..) => true,
+        Stmt::BlockStmt(body, _) => breaks(body),
+        Stmt::IfStmt(_, body, alt, _) => {
+            breaks(body) || alt.as_ref().is_some_and(|alt| breaks(std::slice::from_ref(alt)))
+        }
+        _ => false,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{common::source::Source, compiler::parser::Parser};
@@ -1,5 +1,6 @@
 //! Module containing an AST visitor.
 
+use crate::common::Span;
 use crate::compiler::ast::*;
 
 use super::SyntaxError;
@@ -11,23 +12,7 @@ pub type VisitorResult = Result<(), SyntaxError>;
 /// tree and does absolutely nothing.
 pub trait Visitor<'a>: Sized {
     fn visit_stmt(&mut self, stmt: &mut Stmt) -> VisitorResult {
-        match stmt {
-            Stmt::BlockStmt(block, _) => self.visit_block_stmt(block),
-            Stmt::ExpressionStmt(expr) => self.visit_expr_stmt(expr),
-            Stmt::FunDeclaration(fun, _) => self.visit_fun_decl(fun),
-            Stmt::ConDeclaration(con, _) => self.visit_con_decl(con),
-            Stmt::ClassDeclaration(class, _) => self.visit_class_decl(class),
-            Stmt::VarDeclaration(id, expr, kind, _) => self.visit_var_decl(id, expr, *kind),
-            Stmt::AssignmentStmt(stmt, _) => self.visit_assignment(stmt),
-            Stmt::IfStmt(condition, body, alt, _) => self.visit_if_stmt(condition, body, alt),
-            Stmt::LoopStmt(body, _) => self.visit_loop_stmt(body),
-            Stmt::WhileStmt(condition, body, _) => self.visit_while_stmt(condition, body),
-            Stmt::ImportStmt(stmt) => self.visit_import_stmt(stmt),
-            Stmt::BreakStmt(_) => self.visit_break_stmt(),
-            Stmt::ContinueStmt(_) => self.visit_continue_stmt(),
-            Stmt::ReturnStmt(return_expr, _) => self.visit_return_stmt(return_expr),
-            Stmt::PrintStmt(expr, _) => self.visit_print_stmt(expr),
-        }
+        walk_stmt(self, stmt)
     }
 
     fn visit_fun_decl(&mut self, fun: &mut FunctionDecl) -> VisitorResult {
@@ -40,6 +25,16 @@ pub trait Visitor<'a>: Sized {
         self.visit_block_stmt(&mut fun.body)
     }
 
+    /// Visit an anonymous function. Unlike a function declaration, it doesn't
+    /// bind a name.
+    fn visit_lambda(&mut self, fun: &mut FunctionDecl) -> VisitorResult {
+        for param in fun.params.iter_mut() {
+            self.visit_ident(param)?;
+        }
+
+        self.visit_block_stmt(&mut fun.body)
+    }
+
     fn visit_class_decl(&mut self, class: &mut ClassDecl) -> VisitorResult {
         self.visit_name(&mut class.id)?;
 
@@ -75,6 +70,21 @@ pub trait Visitor<'a>: Sized {
         Ok(())
     }
 
+    /// Visit a declaration whose target is a list pattern. The names in the
+    /// pattern aren't visited, as no pass handles them yet.
+    fn visit_destructure(
+        &mut self,
+        _target: &mut Pattern,
+        expr: &mut Option<Expr>,
+        _kind: VarKind,
+    ) -> VisitorResult {
+        if let Some(init) = expr {
+            self.visit_expr(init)?;
+        }
+
+        Ok(())
+    }
+
     fn visit_block_stmt(&mut self, block: &mut Vec<Stmt>) -> VisitorResult {
         for stmt in block.iter_mut() {
             self.visit_stmt(stmt)?;
@@ -108,14 +118,23 @@ pub trait Visitor<'a>: Sized {
         self.visit_block_stmt(body)
     }
 
+    fn visit_for_stmt(&mut self, stmt: &mut ForStmt) -> VisitorResult {
+        self.visit_expr(&mut stmt.iterable)?;
+        self.visit_ident(&mut stmt.var)?;
+        self.visit_block_stmt(&mut stmt.body)
+    }
+
     fn visit_import_stmt(&mut self, _import_stmt: &mut ImportStatement) -> VisitorResult {
         // Nothing to do (for now).
         Ok(())
     }
 
-    fn visit_break_stmt(&mut self) -> VisitorResult {
-        // Nothing to do.
-        Ok(())
+    fn visit_break_stmt(&mut self, value: &mut Option<Expr>) -> VisitorResult {
+        if let Some(expr) = value {
+            self.visit_expr(expr)
+        } else {
+            Ok(())
+        }
     }
 
     fn visit_continue_stmt(&mut self) -> VisitorResult {
@@ -145,18 +164,7 @@ pub trait Visitor<'a>: Sized {
     }
 
     fn visit_expr(&mut self, expr: &mut Expr) -> VisitorResult {
-        match expr {
-            Expr::ArrayExpr(array, _) => self.visit_array(array),
-            Expr::MapExpr(values, _) => self.visit_map(values),
-            Expr::BinaryExpr(expr, _) => self.visit_binary_expr(expr),
-            Expr::ParenExpr(expr, _) => self.visit_paren_expr(expr),
-            Expr::UnaryExpr(op, arg, _) => self.visit_unary_expr(op, arg),
-            Expr::LogicalExpr(expr, _) => self.visit_logical_expr(expr),
-            Expr::CallExpr(callee, args, _) => self.visit_call_expr(callee, args),
-            Expr::MemberExpr(obj, prop, _) => self.visit_member_expr(obj, prop),
-            Expr::Identifier(ident) => self.visit_ident(ident),
-            Expr::Number(_, _) | Expr::Bool(_, _) | Expr::String(_, _) | Expr::Nil(_) => Ok(()),
-        }
+        walk_expr(self, expr)
     }
 
     fn visit_array(&mut self, array: &mut [Expr]) -> VisitorResult {
@@ -175,6 +183,14 @@ pub trait Visitor<'a>: Sized {
         Ok(())
     }
 
+    fn visit_interpolation(&mut self, parts: &mut [Expr]) -> VisitorResult {
+        for part in parts.iter_mut() {
+            self.visit_expr(part)?;
+        }
+
+        Ok(())
+    }
+
     fn visit_binary_expr(&mut self, expr: &mut BinaryExpr) -> VisitorResult {
         self.visit_expr(&mut expr.lhs)?;
         self.visit_expr(&mut expr.rhs)
@@ -185,6 +201,17 @@ pub trait Visitor<'a>: Sized {
         self.visit_expr(&mut expr.rhs)
     }
 
+    fn visit_ternary_expr(
+        &mut self,
+        condition: &mut Expr,
+        then: &mut Expr,
+        alt: &mut Expr,
+    ) -> VisitorResult {
+        self.visit_expr(condition)?;
+        self.visit_expr(then)?;
+        self.visit_expr(alt)
+    }
+
     fn visit_paren_expr(&mut self, expr: &mut Expr) -> VisitorResult {
         self.visit_expr(expr)
     }
@@ -193,7 +220,12 @@ pub trait Visitor<'a>: Sized {
         self.visit_expr(arg)
     }
 
-    fn visit_call_expr(&mut self, callee: &mut Expr, args: &mut Vec<Expr>) -> VisitorResult {
+    fn visit_call_expr(
+        &mut self,
+        callee: &mut Expr,
+        args: &mut Vec<Expr>,
+        _span: &Span,
+    ) -> VisitorResult {
         self.visit_expr(callee)?;
 
         for arg in args.iter_mut() {
@@ -218,3 +250,56 @@ pub trait Visitor<'a>: Sized {
         Ok(())
     }
 }
+
+/// Dispatches `stmt` to the matching `visit_*` method of `visitor`. This is
+/// what [`Visitor::visit_stmt`] does by default, so passes that override it
+/// can still fall back to walking the statement's children.
+pub fn walk_stmt<'a, V: Visitor<'a>>(visitor: &mut V, stmt: &mut Stmt) -> VisitorResult {
+    match stmt {
+        Stmt::BlockStmt(block, _) => visitor.visit_block_stmt(block),
+        Stmt::ExpressionStmt(expr) => visitor.visit_expr_stmt(expr),
+        Stmt::FunDeclaration(fun, _) => visitor.visit_fun_decl(fun),
+        Stmt::ConDeclaration(con, _) => visitor.visit_con_decl(con),
+        Stmt::ClassDeclaration(class, _) => visitor.visit_class_decl(class),
+        Stmt::VarDeclaration(Pattern::Name(id), expr, kind, _) => {
+            visitor.visit_var_decl(id, expr, *kind)
+        }
+        Stmt::VarDeclaration(target, expr, kind, _) => {
+            visitor.visit_destructure(target, expr, *kind)
+        }
+        Stmt::AssignmentStmt(stmt, _) => visitor.visit_assignment(stmt),
+        Stmt::IfStmt(condition, body, alt, _) => visitor.visit_if_stmt(condition, body, alt),
+        Stmt::LoopStmt(body, _) => visitor.visit_loop_stmt(body),
+        Stmt::WhileStmt(condition, body, _) => visitor.visit_while_stmt(condition, body),
+        Stmt::ForStmt(stmt, _) => visitor.visit_for_stmt(stmt),
+        Stmt::ImportStmt(stmt) => visitor.visit_import_stmt(stmt),
+        Stmt::BreakStmt(value, _) => visitor.visit_break_stmt(value),
+        Stmt::ContinueStmt(_) => visitor.visit_continue_stmt(),
+        Stmt::ReturnStmt(return_expr, _) => visitor.visit_return_stmt(return_expr),
+        Stmt::PrintStmt(expr, _) => visitor.visit_print_stmt(expr),
+    }
+}
+
+/// Dispatches `expr` to the matching `visit_*` method of `visitor`. This is
+/// what [`Visitor::visit_expr`] does by default, so passes that override it
+/// can still fall back to walking the expression's children.
+pub fn walk_expr<'a, V: Visitor<'a>>(visitor: &mut V, expr: &mut Expr) -> VisitorResult {
+    match expr {
+        Expr::ArrayExpr(array, _) => visitor.visit_array(array),
+        Expr::Interpolation(parts, _) => visitor.visit_interpolation(parts),
+        Expr::MapExpr(values, _) => visitor.visit_map(values),
+        Expr::BinaryExpr(expr, _) => visitor.visit_binary_expr(expr),
+        Expr::ParenExpr(expr, _) => visitor.visit_paren_expr(expr),
+        Expr::UnaryExpr(op, _, arg, _) => visitor.visit_unary_expr(op, arg),
+        Expr::LogicalExpr(expr, _) => visitor.visit_logical_expr(expr),
+        Expr::LoopExpr(stmt, _) => visitor.visit_stmt(stmt),
+        Expr::Lambda(fun, _) => visitor.visit_lambda(fun),
+        Expr::TernaryExpr(condition, then, alt, _) => {
+            visitor.visit_ternary_expr(condition, then, alt)
+        }
+        Expr::CallExpr(callee, args, span) => visitor.visit_call_expr(callee, args, span),
+        Expr::MemberExpr(obj, prop, _) => visitor.visit_member_expr(obj, prop),
+        Expr::Identifier(ident) => visitor.visit_ident(ident),
+        Expr::Number(_, _) | Expr::Integer(_, _) | Expr::Bool(_, _) | Expr::String(_, _) | Expr::Bytes(_, _) | Expr::Nil(_) => Ok(()),
+    }
+}
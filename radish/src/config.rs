@@ -1,4 +1,17 @@
+use crate::error::{diagnostic::Severity, renderer::DEFAULT_TAB_WIDTH};
+
 const DEFAULT_FILE_NAME: &str = "EVAL";
+/// The longest numeric literal the parser accepts by default.
+pub const DEFAULT_MAX_NUMBER_LENGTH: usize = 256;
+/// How deeply blocks and expressions may nest by default before the parser gives up.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+/// How many top-level statements a source may have by default.
+pub const DEFAULT_MAX_STATEMENTS: usize = usize::MAX;
+/// How many values the VM's stack may hold by default.
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1 << 20;
+/// How many constants a chunk may hold by default, as many as a
+/// `LoadConstLong` operand can index.
+pub const DEFAULT_MAX_CONSTANTS: usize = u32::MAX as usize;
 
 #[derive(Debug)]
 pub struct Config {
@@ -6,9 +19,63 @@ pub struct Config {
     pub dump_ast: bool,
     pub dump_bytecode: bool,
     pub trace: bool,
+    /// Count how many times each opcode is executed.
+    pub profile: bool,
+    /// Style diagnostics with ANSI colors when writing to a terminal.
+    pub color: bool,
+    /// The maximum number of characters allowed in a numeric literal.
+    pub max_number_length: usize,
+    /// The base of number literals written without a prefix, so that with 16
+    /// `10` is sixteen. Only integers are affected, `1.5` and `1e3` are always
+    /// decimal. Must be between 2 and 36.
+    pub default_radix: u32,
+    /// The maximum nesting depth of blocks and expressions, e.g. `((((1))))`.
+    /// Each level of a nested `if` or loop body counts the same as a level of
+    /// parentheses.
+    pub max_nesting_depth: usize,
+    /// The maximum number of statements at the top level of a source.
+    pub max_statements: usize,
+    /// Allow identifiers to contain Unicode letters and digits, not just
+    /// ASCII ones.
+    pub unicode_identifiers: bool,
+    /// The maximum number of values on the VM's stack, beyond which a runtime
+    /// error is raised.
+    pub max_stack_size: usize,
+    /// The maximum number of constants in a single function's chunk.
+    pub max_constants: usize,
+    /// Reject code that relies on variables or functions implicitly being `nil`.
+    pub strict: bool,
+    /// Convert numeric strings to numbers when they're used in arithmetic that
+    /// isn't otherwise defined for strings, so `"5" + 3` is `8`. Strings that
+    /// aren't numbers are a runtime error.
+    pub coerce_numeric_strings: bool,
+    /// Don't define the builtin functions, so that referring to one is an
+    /// unresolved variable like any other undefined name.
+    pub no_builtins: bool,
+    /// Diagnostics less severe than this aren't shown.
+    pub severity_floor: Severity,
+    /// Show warnings as notes, which the default floor hides.
+    pub warn_as_note: bool,
+    /// How many columns a tab in a diagnostic's source line is shown as.
+    pub tab_width: usize,
+    /// What dividing one integer by another produces.
+    pub int_division: IntDivision,
     pub default_filename: String,
 }
 
+/// How `/` treats two integer operands. In either mode dividing an integer
+/// by the integer `0` is a runtime error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntDivision {
+    /// Divide as floats, so `7 / 2` is `3.5`.
+    #[default]
+    Float,
+    /// Divide to an integer, truncating the quotient toward zero, so `7 / 2`
+    /// is `3` and `-7 / 2` is `-3`, not the `-4` that rounding down would
+    /// give. This matches `%`, whose result takes the sign of the dividend.
+    Floor,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config::new()
@@ -22,6 +89,22 @@ impl Config {
             dump_ast: false,
             dump_bytecode: false,
             trace: false,
+            profile: false,
+            color: true,
+            max_number_length: DEFAULT_MAX_NUMBER_LENGTH,
+            default_radix: 10,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_statements: DEFAULT_MAX_STATEMENTS,
+            unicode_identifiers: false,
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            max_constants: DEFAULT_MAX_CONSTANTS,
+            strict: false,
+            coerce_numeric_strings: false,
+            no_builtins: false,
+            severity_floor: Severity::Warning,
+            warn_as_note: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            int_division: IntDivision::Float,
             default_filename: DEFAULT_FILE_NAME.to_string(),
         }
     }
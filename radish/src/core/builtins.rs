@@ -0,0 +1,430 @@
+//! Functions that are available in every module without being imported.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    common::Module,
+    vm::{trace::Trace, value::Range},
+    Value, VM,
+};
+
+/// The names of all builtin functions.
+pub(crate) const NAMES: &[&str] = &[
+    "range", "len", "min", "max", "abs", "upper", "lower", "trim", "split", "sort",
+];
+
+/// Whether `name` refers to a builtin function.
+pub(crate) fn is_builtin(name: &str) -> bool {
+    NAMES.contains(&name)
+}
+
+/// Add every builtin function to `module`.
+pub(crate) fn define(module: &mut Module) {
+    module.add_native_with_optional_args("range", 2, 1, range);
+    module.add_native("len", 1, len);
+    module.add_native("min", 2, min);
+    module.add_native("max", 2, max);
+    module.add_native("abs", 1, abs);
+    module.add_native("upper", 1, upper);
+    module.add_native("lower", 1, lower);
+    module.add_native("trim", 1, trim);
+    module.add_native("split", 2, split);
+    module.add_native("sort", 1, sort);
+}
+
+/// `range(start, end[, step])`: a range of the integers from `start` up to,
+/// but not including, `end`. A negative `step` counts down instead.
+pub fn range(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let mut bounds = vec![];
+
+    for arg in args.iter() {
+        match arg {
+            Value::Number(n) if n.fract() == 0.0 => bounds.push(*n as i64),
+            Value::Integer(n) => bounds.push(*n),
+            _ => {
+                return Err(Trace::new(format!(
+                    "range expects integer arguments, got {}",
+                    arg.repr()
+                )))
+            }
+        }
+    }
+
+    let (start, end) = (bounds[0], bounds[1]);
+    let step = bounds.get(2).copied().unwrap_or(1);
+
+    if step == 0 {
+        return Err(Trace::new("range step cannot be zero"));
+    }
+
+    Ok(Value::Range(Rc::new(Range::new(start, end, step))))
+}
+
+/// `len(value)`: the number of items in an array, map or range, the number of
+/// characters in a string, or the number of bytes in a byte string.
+pub fn len(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let length = match &args[0] {
+        Value::Array(items) => items.borrow().len(),
+        Value::Map(entries) => entries.borrow().len(),
+        Value::String(string) => string.borrow().chars().count(),
+        Value::Range(range) => range.len(),
+        Value::Bytes(bytes) => bytes.borrow().len(),
+        value => {
+            return Err(Trace::new(format!(
+                "value of type {} has no length",
+                value.type_name()
+            )))
+        }
+    };
+
+    Ok(Value::Integer(length as i64))
+}
+
+/// The arguments of the math function `name` as numbers.
+fn numeric_args(name: &str, args: &[Value]) -> Result<Vec<f64>, Trace> {
+    args.iter()
+        .map(|arg| arg.as_number().map_err(|err| Trace::new(format!("{}: {}", name, err))))
+        .collect()
+}
+
+/// The arguments of a math function as integers, if they all are.
+fn integer_args(args: &[Value]) -> Option<Vec<i64>> {
+    args.iter()
+        .map(|arg| match arg {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `min(a, b)`: the smaller of two numbers. The result is an integer if both
+/// arguments are.
+pub fn min(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    if let Some(n) = integer_args(&args) {
+        return Ok(Value::Integer(n[0].min(n[1])));
+    }
+
+    let n = numeric_args("min", &args)?;
+    Ok(Value::Number(n[0].min(n[1])))
+}
+
+/// `max(a, b)`: the larger of two numbers. The result is an integer if both
+/// arguments are.
+pub fn max(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    if let Some(n) = integer_args(&args) {
+        return Ok(Value::Integer(n[0].max(n[1])));
+    }
+
+    let n = numeric_args("max", &args)?;
+    Ok(Value::Number(n[0].max(n[1])))
+}
+
+/// `abs(x)`: the absolute value of a number. The absolute value of the
+/// smallest integer doesn't fit in an integer, and is an error.
+pub fn abs(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    if let Some(n) = integer_args(&args) {
+        return n[0]
+            .checked_abs()
+            .map(Value::Integer)
+            .ok_or_else(|| Trace::new(format!("abs: the absolute value of {} is too large", n[0])));
+    }
+
+    let n = numeric_args("abs", &args)?;
+    Ok(Value::Number(n[0].abs()))
+}
+
+/// The arguments of the string function `name` as strings.
+fn string_args(name: &str, args: &[Value]) -> Result<Vec<String>, Trace> {
+    args.iter()
+        .map(|arg| arg.as_string().map_err(|err| Trace::new(format!("{}: {}", name, err))))
+        .collect()
+}
+
+/// `upper(s)`: `s` with every letter in upper case.
+pub fn upper(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let s = string_args("upper", &args)?;
+    Ok(Value::from(&s[0].to_uppercase()))
+}
+
+/// `lower(s)`: `s` with every letter in lower case.
+pub fn lower(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let s = string_args("lower", &args)?;
+    Ok(Value::from(&s[0].to_lowercase()))
+}
+
+/// `trim(s)`: `s` without leading and trailing whitespace.
+pub fn trim(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let s = string_args("trim", &args)?;
+    Ok(Value::from(s[0].trim()))
+}
+
+/// `split(s, sep)`: an array of the parts of `s` between each `sep`.
+///
+/// An empty `sep` splits `s` into its characters. Splitting an empty string
+/// always gives an array holding one empty string, whatever the separator.
+pub fn split(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let s = string_args("split", &args)?;
+    let (string, sep) = (&s[0], &s[1]);
+
+    let parts: Vec<Value> = if string.is_empty() {
+        vec![Value::from("")]
+    } else if sep.is_empty() {
+        string.chars().map(|ch| Value::from(&ch.to_string())).collect()
+    } else {
+        string.split(sep.as_str()).map(Value::from).collect()
+    };
+
+    Ok(Value::Array(Rc::new(RefCell::new(parts))))
+}
+
+/// `sort(array)`: a new array of the items of `array` in ascending order, as
+/// ordered by [`Value::total_cmp`]. Items that compare equal keep their order.
+/// It's an error for the array to hold items that can't be ordered against
+/// each other, such as a number and a string.
+pub fn sort(_vm: &mut VM, args: Vec<Value>) -> Result<Value, Trace> {
+    let list = args[0]
+        .as_list()
+        .map_err(|err| Trace::new(format!("sort: {}", err)))?;
+    let mut items = list.borrow().clone();
+
+    // every value that can be ordered against the first can be ordered
+    // against the others, so the comparisons below can't fail.
+    if let Some((first, rest)) = items.split_first() {
+        for item in rest {
+            first
+                .total_cmp(item)
+                .map_err(|err| Trace::new(format!("sort: {}", err)))?;
+        }
+    }
+
+    items.sort_by(|a, b| a.total_cmp(b).unwrap());
+
+    Ok(Value::Array(Rc::new(RefCell::new(items))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(value: Value) -> Vec<f64> {
+        match value {
+            Value::Range(range) => (0..range.len())
+                .map(|index| range.get(index).unwrap() as f64)
+                .collect(),
+            _ => panic!("expected a range, got {}", value),
+        }
+    }
+
+    fn call(args: &[f64]) -> Result<Value, Trace> {
+        let args = args.iter().map(|n| Value::Number(*n)).collect();
+        range(&mut VM::new(), args)
+    }
+
+    #[test]
+    fn range_up_to_end() {
+        assert_eq!(numbers(call(&[0.0, 3.0]).unwrap()), vec![0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn range_with_step() {
+        assert_eq!(numbers(call(&[0.0, 7.0, 3.0]).unwrap()), vec![0.0, 3.0, 6.0]);
+        assert_eq!(numbers(call(&[3.0, 0.0, -1.0]).unwrap()), vec![3.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn empty_range() {
+        assert!(numbers(call(&[2.0, 2.0]).unwrap()).is_empty());
+        assert!(numbers(call(&[3.0, 0.0]).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn zero_step() {
+        let err = call(&[0.0, 3.0, 0.0]).unwrap_err();
+
+        assert_eq!(err.message, "range step cannot be zero");
+    }
+
+    #[test]
+    fn non_integer_bound() {
+        assert!(call(&[0.0, 1.5]).is_err());
+    }
+
+    #[test]
+    fn length_of_values() {
+        let length = |value: Value| len(&mut VM::new(), vec![value]);
+
+        assert_eq!(length(Value::from("héllo")).unwrap(), Value::Integer(5));
+        assert_eq!(length(call(&[0.0, 1e12, 3.0]).unwrap()).unwrap(), Value::Integer(333333333334));
+        assert_eq!(
+            length(Value::Nil).unwrap_err().message,
+            "value of type nil has no length"
+        );
+    }
+
+    fn math(
+        fun: fn(&mut VM, Vec<Value>) -> Result<Value, Trace>,
+        args: Vec<Value>,
+    ) -> Result<Value, Trace> {
+        fun(&mut VM::new(), args)
+    }
+
+    #[test]
+    fn min_and_max() {
+        let n = Value::Number;
+
+        assert_eq!(math(min, vec![n(3.0), n(-2.0)]).unwrap(), n(-2.0));
+        assert_eq!(math(max, vec![n(3.0), n(-2.0)]).unwrap(), n(3.0));
+        assert_eq!(math(min, vec![n(1.5), n(1.25)]).unwrap(), n(1.25));
+        assert_eq!(math(max, vec![n(1.0), n(1.5)]).unwrap(), n(1.5));
+    }
+
+    #[test]
+    fn absolute_value() {
+        let n = Value::Number;
+
+        assert_eq!(math(abs, vec![n(-4.0)]).unwrap(), n(4.0));
+        assert_eq!(math(abs, vec![n(2.5)]).unwrap(), n(2.5));
+        assert_eq!(math(abs, vec![n(-0.0)]).unwrap(), n(0.0));
+        assert_eq!(
+            math(abs, vec![n(i64::MIN as f64)]).unwrap(),
+            n(9223372036854775808.0)
+        );
+    }
+
+    #[test]
+    fn integer_math() {
+        let i = Value::Integer;
+
+        assert_eq!(math(min, vec![i(3), i(-2)]).unwrap(), i(-2));
+        assert_eq!(math(max, vec![i(3), i(-2)]).unwrap(), i(3));
+        assert_eq!(math(abs, vec![i(-4)]).unwrap(), i(4));
+        assert_eq!(math(abs, vec![i(i64::MAX)]).unwrap(), i(i64::MAX));
+        // mixing integers and floats gives a float.
+        assert_eq!(math(max, vec![i(3), Value::Number(1.5)]).unwrap(), Value::Number(3.0));
+        assert!(matches!(math(min, vec![i(3), Value::Number(1.5)]).unwrap(), Value::Number(_)));
+    }
+
+    #[test]
+    fn integer_abs_overflow() {
+        let err = math(abs, vec![Value::Integer(i64::MIN)]).unwrap_err();
+        assert_eq!(err.message, "abs: the absolute value of -9223372036854775808 is too large");
+    }
+
+    #[test]
+    fn math_on_non_numbers() {
+        let err = math(min, vec![Value::Number(1.0), Value::from("2")]).unwrap_err();
+        assert_eq!(err.message, "min: expected number, got string");
+
+        let err = math(abs, vec![Value::Nil]).unwrap_err();
+        assert_eq!(err.message, "abs: expected number, got nil");
+    }
+
+    fn strings(value: Value) -> Vec<String> {
+        match value {
+            Value::Array(items) => items.borrow().iter().map(|item| item.to_string()).collect(),
+            _ => panic!("expected an array, got {}", value),
+        }
+    }
+
+    fn call_split(string: &str, sep: &str) -> Vec<String> {
+        strings(split(&mut VM::new(), vec![Value::from(string), Value::from(sep)]).unwrap())
+    }
+
+    #[test]
+    fn change_case() {
+        let call = |fun: fn(&mut VM, Vec<Value>) -> Result<Value, Trace>, s: &str| {
+            fun(&mut VM::new(), vec![Value::from(s)]).unwrap()
+        };
+
+        assert_eq!(call(upper, "Radish 1ß"), Value::from("RADISH 1SS"));
+        assert_eq!(call(lower, "RaDiSh"), Value::from("radish"));
+        assert_eq!(call(trim, " \t radish\n "), Value::from("radish"));
+        assert_eq!(call(trim, ""), Value::from(""));
+    }
+
+    #[test]
+    fn split_on_separator() {
+        assert_eq!(call_split("a,b,,c", ","), vec!["a", "b", "", "c"]);
+        assert_eq!(call_split("a, b", ", "), vec!["a", "b"]);
+        assert_eq!(call_split("abc", "-"), vec!["abc"]);
+    }
+
+    #[test]
+    fn split_empty_separator() {
+        assert_eq!(call_split("héy", ""), vec!["h", "é", "y"]);
+    }
+
+    #[test]
+    fn split_empty_string() {
+        assert_eq!(call_split("", ","), vec![""]);
+        assert_eq!(call_split("", ""), vec![""]);
+    }
+
+    #[test]
+    fn string_functions_on_non_strings() {
+        let err = upper(&mut VM::new(), vec![Value::Number(1.0)]).unwrap_err();
+        assert_eq!(err.message, "upper: expected string, got number");
+
+        let err = split(&mut VM::new(), vec![Value::from("a b"), Value::Nil]).unwrap_err();
+        assert_eq!(err.message, "split: expected string, got nil");
+    }
+
+    fn call_sort(items: Vec<Value>) -> Result<Vec<String>, Trace> {
+        let list = Value::Array(Rc::new(RefCell::new(items)));
+
+        match sort(&mut VM::new(), vec![list])? {
+            Value::Array(items) => Ok(items.borrow().iter().map(Value::repr).collect()),
+            value => panic!("expected an array, got {}", value),
+        }
+    }
+
+    #[test]
+    fn sort_numbers() {
+        let items = vec![
+            Value::Integer(3),
+            Value::Number(f64::NAN),
+            Value::Number(-1.5),
+            Value::Integer(2),
+            Value::Number(2.5),
+        ];
+
+        assert_eq!(call_sort(items).unwrap(), ["-1.5", "2", "2.5", "3", "nan"]);
+    }
+
+    #[test]
+    fn sort_strings() {
+        let items = ["pear", "Apple", "apple", "", "banana"].iter().map(|s| Value::from(*s)).collect();
+
+        assert_eq!(
+            call_sort(items).unwrap(),
+            ["\"\"", "\"Apple\"", "\"apple\"", "\"banana\"", "\"pear\""]
+        );
+    }
+
+    #[test]
+    fn sort_leaves_list_alone() {
+        let list = Value::Array(Rc::new(RefCell::new(vec![Value::Boolean(true), Value::Boolean(false)])));
+        let sorted = sort(&mut VM::new(), vec![list.clone()]).unwrap();
+
+        assert_eq!(sorted.repr(), "[false, true]");
+        assert_eq!(list.repr(), "[true, false]");
+    }
+
+    #[test]
+    fn sort_mixed_types() {
+        let err = call_sort(vec![Value::Integer(1), Value::from("a"), Value::Integer(0)]).unwrap_err();
+        assert_eq!(err.message, "sort: cannot order values of type number and string");
+
+        let err = call_sort(vec![Value::Nil, Value::Boolean(true)]).unwrap_err();
+        assert_eq!(err.message, "sort: cannot order values of type nil and boolean");
+
+        let err = sort(&mut VM::new(), vec![Value::Integer(1)]).unwrap_err();
+        assert_eq!(err.message, "sort: expected array, got number");
+
+        // a single item is never compared, even if it couldn't be.
+        assert!(call_sort(vec![Value::Nil]).is_ok());
+
+        let array = Value::Array(Rc::new(RefCell::new(vec![Value::Integer(1)])));
+        assert!(call_sort(vec![array]).is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) mod builtins;
+
 use crate::{
     common::{module::ModuleBuilder, Module},
     Value, VM, Namespace, vm::trace::Trace,
@@ -27,7 +29,7 @@ impl ModuleBuilder for Math {
     fn build(self) -> Result<Module, String> {
         let mut module = Module::new_("math");
 
-        module.add_value("pi", 3.14);
+        module.add_value("pi", std::f64::consts::PI);
 
         Ok(module)
     }
@@ -2,10 +2,12 @@ pub mod diagnostic;
 pub mod renderer;
 pub mod views;
 
-use termcolor::WriteColor;
+use termcolor::{StandardStream, WriteColor};
 
 pub use diagnostic::{AsDiagnostic, Diagnostic, Label, LabelStyle};
-use crate::{common::span::Span, compiler::SyntaxError, vm::trace::Trace};
+use diagnostic::Severity;
+use renderer::DEFAULT_TAB_WIDTH;
+use crate::{common::span::Span, compiler::SyntaxError, config::Config, vm::trace::Trace};
 
 use std::{io, fmt};
 
@@ -36,17 +38,61 @@ pub fn emit(
     writer: &mut dyn WriteColor, 
     diagnostic: &Diagnostic,
     display_style: DisplayStyle,
+) -> io::Result<()> {
+    emit_with_tab_width(writer, diagnostic, display_style, DEFAULT_TAB_WIDTH)
+}
+
+/// Emit a diagnostic, expanding tabs in its source lines to `tab_width` columns.
+pub fn emit_with_tab_width(
+    writer: &mut dyn WriteColor,
+    diagnostic: &Diagnostic,
+    display_style: DisplayStyle,
+    tab_width: usize,
 ) -> io::Result<()> {
     use self::renderer::Renderer;
     use self::views::{ShortDiagnostic, RichDiagnostic};
 
-    let mut renderer = Renderer::new(writer);
+    let mut renderer = Renderer::with_tab_width(writer, tab_width);
     match display_style {
         DisplayStyle::Short => ShortDiagnostic::new(diagnostic).render(&mut renderer),
         DisplayStyle::Verbose => RichDiagnostic::new(diagnostic).render(&mut renderer),
     }
 }
 
+/// Emit the diagnostics that are at least as severe as the config's
+/// `severity_floor`, after downgrading warnings to notes if it asks for that.
+pub fn emit_filtered(
+    writer: &mut dyn WriteColor,
+    diagnostics: Vec<Diagnostic>,
+    config: &Config,
+) -> io::Result<()> {
+    for mut diagnostic in diagnostics {
+        if config.warn_as_note && diagnostic.severity == Severity::Warning {
+            diagnostic.severity = Severity::Note;
+        }
+
+        if diagnostic.severity >= config.severity_floor {
+            emit_with_tab_width(writer, &diagnostic, DisplayStyle::Verbose, config.tab_width)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A handle to stderr, styled with ANSI colors only if `color` is set and
+/// stderr is a terminal.
+pub fn stderr_with_color(color: bool) -> StandardStream {
+    use std::io::IsTerminal;
+    use termcolor::ColorChoice;
+
+    let choice = if color && io::stderr().is_terminal() {
+        ColorChoice::Always
+    } else {
+        ColorChoice::Never
+    };
+
+    StandardStream::stderr(choice)
+}
 
 #[derive(Debug, Clone)]
 pub struct IOError(std::sync::Arc<std::io::Error>);
@@ -111,14 +157,26 @@ impl From<&str> for RadishError {
 
 impl RadishError {
     pub fn emit(&self) {
+        self.emit_with_color(true);
+    }
+
+    /// Emit the error, styling it with ANSI colors only if `color` is set and
+    /// stderr is a terminal.
+    pub fn emit_with_color(&self, color: bool) {
+        self.emit_with_tab_width(color, DEFAULT_TAB_WIDTH);
+    }
+
+    /// Emit the error like [`emit_with_color()`](Self::emit_with_color),
+    /// expanding tabs in source lines to `tab_width` columns.
+    pub fn emit_with_tab_width(&self, color: bool, tab_width: usize) {
         match &self {
             RadishError::CompilerError(err) => {
-                use termcolor::{ColorChoice, StandardStream};
-                let mut temp_stderr = StandardStream::stderr(ColorChoice::Always);
-                emit(
+                let mut temp_stderr = stderr_with_color(color);
+                emit_with_tab_width(
                     &mut temp_stderr,
                     &err.report(),
                     DisplayStyle::Verbose,
+                    tab_width,
                 )
                 .unwrap();
             }
@@ -127,4 +185,87 @@ impl RadishError {
             RadishError::Other(err) => print!("{}", err),
         }
     }
+
+    /// Write the error to the given writer.
+    pub fn emit_to(&self, writer: &mut dyn WriteColor) -> io::Result<()> {
+        self.emit_to_with_tab_width(writer, DEFAULT_TAB_WIDTH)
+    }
+
+    /// Write the error to the given writer, expanding tabs in source lines to
+    /// `tab_width` columns.
+    pub fn emit_to_with_tab_width(&self, writer: &mut dyn WriteColor, tab_width: usize) -> io::Result<()> {
+        match &self {
+            RadishError::CompilerError(err) => {
+                emit_with_tab_width(writer, &err.report(), DisplayStyle::Verbose, tab_width)
+            }
+            RadishError::RuntimeError(err) => write!(writer, "{}", err),
+            RadishError::IOError(err) => writeln!(writer, "{}", err),
+            RadishError::Other(err) => writeln!(writer, "{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use termcolor::NoColor;
+
+    use crate::common::source::Source;
+
+    use super::*;
+
+    fn render(config: &Config) -> String {
+        let source = Source::new("var unused = 1\n", "main.rdsh");
+        let warning = Diagnostic::warning()
+            .with_message("unused variable `unused`")
+            .with_labels(vec![Label::primary(Span::new(source, 4, 10))]);
+
+        let mut out = NoColor::new(vec![]);
+        emit_filtered(&mut out, vec![warning], config).unwrap();
+
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn warnings_are_shown_by_default() {
+        assert!(render(&Config::new()).starts_with("warning: unused variable `unused`"));
+    }
+
+    #[test]
+    fn quiet_hides_warnings() {
+        let mut config = Config::new();
+        config.severity_floor = Severity::Error;
+
+        assert_eq!(render(&config), "");
+    }
+
+    #[test]
+    fn warnings_as_notes_need_verbose() {
+        let mut config = Config::new();
+        config.warn_as_note = true;
+        assert_eq!(render(&config), "");
+
+        config.severity_floor = Severity::Help;
+        assert!(render(&config).starts_with("note: unused variable `unused`"));
+    }
+
+    #[test]
+    fn config_sets_the_tab_width() {
+        let source = Source::new("\tvar unused = 1\n", "main.rdsh");
+        let warning = Diagnostic::warning()
+            .with_message("unused variable `unused`")
+            .with_labels(vec![Label::primary(Span::new(source, 5, 11))]);
+
+        let mut config = Config::new();
+        config.tab_width = 8;
+
+        let mut out = NoColor::new(vec![]);
+        emit_filtered(&mut out, vec![warning], &config).unwrap();
+        let out = String::from_utf8(out.into_inner()).unwrap();
+
+        let line = out.lines().find(|line| line.contains("var unused")).unwrap();
+        let carets = out.lines().find(|line| line.contains('^')).unwrap();
+
+        assert!(line.contains("|         var unused = 1"), "{}", out);
+        assert_eq!(carets.find('^'), line.find("unused"), "{}", out);
+    }
 }
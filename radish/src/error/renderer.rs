@@ -49,25 +49,79 @@ impl Styles {
     }
 }
 
+/// The number of columns a tab character is expanded to by default.
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+/// The number of source lines shown around a labelled line by default.
+pub const DEFAULT_CONTEXT_LINES: usize = 0;
+
 pub struct Renderer<'writer> {
     pub writer: &'writer mut dyn WriteColor,
     styles: Styles,
+    tab_width: usize,
+    context_lines: usize,
 }
 
 // TODO: replace 'unwraps' with errors or such.
 
 impl<'writer> Renderer<'writer> {
     pub fn new(writer: &'writer mut dyn WriteColor) -> Renderer<'writer> {
+        Renderer::with_tab_width(writer, DEFAULT_TAB_WIDTH)
+    }
+
+    /// Create a renderer that expands tabs in source lines to `tab_width` columns.
+    pub fn with_tab_width(writer: &'writer mut dyn WriteColor, tab_width: usize) -> Renderer<'writer> {
         Renderer {
             writer,
             styles: Styles::new(),
+            tab_width,
+            context_lines: DEFAULT_CONTEXT_LINES,
         }
     }
 
+    /// Show up to `context_lines` unlabelled source lines before and after
+    /// each labelled line.
+    pub fn with_context_lines(mut self, context_lines: usize) -> Renderer<'writer> {
+        self.context_lines = context_lines;
+        self
+    }
+
     pub fn styles(&self) -> &Styles {
         &self.styles
     }
 
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    pub fn context_lines(&self) -> usize {
+        self.context_lines
+    }
+
+    /// Replace each tab in `line` with spaces up to the next tab stop.
+    fn expand_tabs(&self, line: &str) -> String {
+        let mut expanded = String::with_capacity(line.len());
+
+        for ch in line.chars() {
+            if ch == '\t' {
+                let width = self.tab_width - expanded.chars().count() % self.tab_width.max(1);
+                expanded.push_str(&" ".repeat(width));
+            } else {
+                expanded.push(ch);
+            }
+        }
+
+        expanded
+    }
+
+    /// Convert a character column in `line` into the column it is displayed at
+    /// once tabs have been expanded.
+    fn display_column(&self, line: &str, col: usize) -> usize {
+        let prefix: String = line.chars().take(col).collect();
+        let overflow = col.saturating_sub(prefix.chars().count());
+
+        self.expand_tabs(&prefix).chars().count() + overflow
+    }
+
     /// Render diagnostic's severity level and message
     ///
     /// ```text
@@ -103,7 +157,7 @@ impl<'writer> Renderer<'writer> {
     ///  --> path/to/file.rdsh:12:3
     /// ```
     pub fn render_location(&mut self, span: &Span, padding: usize) -> io::Result<()> {
-        let (line_num, col_num) = Span::get_line_index(&span.source.contents, span.start);
+        let (line_num, col_num) = span.source.line_col(span.start);
 
         self.set_color(&self.styles().source_border.clone())?;
 
@@ -138,7 +192,8 @@ impl<'writer> Renderer<'writer> {
         // 23 | some line of source code.
         {
             // 23
-            self.outer_gutter_number(line_number, outer_padding)?;
+            let base_line = labels[0].span.source.base_line;
+            self.outer_gutter_number(base_line + line_number, outer_padding)?;
             // 23 |
             self.border_left()?;
 
@@ -147,13 +202,14 @@ impl<'writer> Renderer<'writer> {
 
             // Write source text.
             let lines = Span::lines(source);
-            write!(self, "{}", lines[line_number])?;
+            write!(self, "{}", self.expand_tabs(&lines[line_number]))?;
 
             writeln!(self)?;
         }
 
         {
             let padding = outer_padding + 1;
+            let line = &Span::lines(source)[line_number];
 
             // the end of the last label
             let mut end_of_last = 0;
@@ -166,21 +222,31 @@ impl<'writer> Renderer<'writer> {
                 let end = label.span.end;
 
                 let (_, start_col) = Span::get_line_index(&label.span.source.contents, start);
-                let (_, end_col) = Span::get_line_index(&label.span.source.contents, end);
+                let (end_line, end_col) = Span::get_line_index(&label.span.source.contents, end);
+
+                let start_col = self.display_column(line, start_col);
+                let end_col = if end_line == line_number {
+                    self.display_column(line, end_col)
+                } else {
+                    start_col + (end - start)
+                };
+
+                // always draw at least one marker, even for empty spans (e.g. at <eof>).
+                let width = end_col.saturating_sub(start_col).max(1);
 
                 let label = if label.style == LabelStyle::Primary {
                     self.set_color(&self.styles().primary_label.clone())?;
-                    "^".repeat(label.span.end - start)
+                    "^".repeat(width)
                 } else {
                     self.set_color(&self.styles().secondary_label.clone())?;
-                    "-".repeat(label.span.end - start)
+                    "-".repeat(width)
                 };
 
                 let space_before = " ".repeat(start_col - end_of_last + 1);
                 
                 write!(self, "{}{}", space_before, label)?;
 
-                end_of_last = end_col + 1;
+                end_of_last = start_col + width + 1;
             }
 
             // render the trailing label
@@ -195,6 +261,7 @@ impl<'writer> Renderer<'writer> {
 
                 let (_, start_col) =
                     Span::get_line_index(&label.span.source.contents, label.span.start);
+                let start_col = self.display_column(line, start_col);
 
                 let space_before = " ".repeat(start_col + 1);
                 writeln!(self, "{}|", space_before).unwrap();
@@ -244,6 +311,32 @@ impl<'writer> Renderer<'writer> {
         Ok(())
     }
 
+    /// Render a source line without any labels, to give the labelled lines
+    /// around it some context.
+    ///
+    /// ```text
+    /// 22 | the line before
+    /// ```
+    pub fn render_context_line(
+        &mut self,
+        outer_padding: usize,
+        line_number: usize,
+        source: &str,
+        base_line: usize,
+    ) -> io::Result<()> {
+        self.outer_gutter_number(base_line + line_number, outer_padding)?;
+        self.border_left()?;
+
+        let lines = Span::lines(source);
+        let line = self.expand_tabs(&lines[line_number]);
+
+        if line.is_empty() {
+            writeln!(self)
+        } else {
+            writeln!(self, " {}", line)
+        }
+    }
+
     pub fn render_line_break(&mut self, padding: usize) -> io::Result<()> {
         self.set_color(&self.styles().source_border.clone())?;
 
@@ -335,3 +428,145 @@ impl<'writer> WriteColor for Renderer<'writer> {
         self.writer.is_synchronous()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::source::Source;
+    use crate::error::{views::RichDiagnostic, Diagnostic};
+    use std::rc::Rc;
+    use termcolor::{Ansi, NoColor};
+
+    fn render_line(source: &str, start: usize, end: usize, tab_width: usize) -> String {
+        let span = Span::new(Source::new(source, "./main"), start, end);
+        let label = Label::primary(span).with_message("here");
+
+        let mut writer = NoColor::new(vec![]);
+        let mut renderer = Renderer::with_tab_width(&mut writer, tab_width);
+        renderer
+            .render_snippet_source(1, 0, source, &[&label], Severity::Error)
+            .unwrap();
+
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn expand_tabs_in_snippet() {
+        let source = "\tx = 1 + y";
+        //                    ^---- error on `y`
+        let output = render_line(source, 9, 10, 4);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "1 |     x = 1 + y");
+        assert_eq!(lines[1], "  |             ^ here");
+
+        let source_col = lines[0].find('y').unwrap();
+        let caret_col = lines[1].find('^').unwrap();
+        assert_eq!(source_col, caret_col);
+    }
+
+    #[test]
+    fn expand_tabs_with_custom_width() {
+        let source = "\tx = 1 + y";
+        let output = render_line(source, 9, 10, 8);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0].find('y'), lines[1].find('^'));
+        assert_eq!(lines[1].find('^'), Some(4 + 8 + 8));
+    }
+
+    #[test]
+    fn color_surrounds_caret() {
+        let source = "var x = )";
+        let span = Span::new(Source::new(source, "./main"), 8, 9);
+        let label = Label::primary(span);
+
+        let mut writer = Ansi::new(vec![]);
+        let mut renderer = Renderer::new(&mut writer);
+        renderer
+            .render_snippet_source(1, 0, source, &[&label], Severity::Error)
+            .unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        // the caret is drawn in bold, intense red and followed by a reset.
+        assert!(output.contains("\x1b[1m\x1b[38;5;9m         ^ \n\x1b[0m"));
+    }
+
+    fn render_with_context(labels: Vec<Label>, context_lines: usize) -> String {
+        let diagnostic = Diagnostic::error().with_message("oops").with_labels(labels);
+
+        let mut writer = NoColor::new(vec![]);
+        let mut renderer = Renderer::new(&mut writer).with_context_lines(context_lines);
+        RichDiagnostic::new(&diagnostic).render(&mut renderer).unwrap();
+
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn context_lines_around_error() {
+        let source = Source::new("var a = 1\nvar b = 2\nvar c = )\nvar d = 4\nvar e = 5\n", "./main");
+        let label = Label::primary(Span::new(source, 28, 29)).with_message("here");
+
+        let output = render_with_context(vec![label], 1);
+
+        assert_eq!(
+            output,
+            "error: oops\n \
+             --> ./main:3:9\n  \
+             |\n\
+             2 | var b = 2\n\
+             3 | var c = )\n  \
+             |         ^ here\n\
+             4 | var d = 4\n  \
+             |\n"
+        );
+    }
+
+    #[test]
+    fn context_lines_clamp_to_source() {
+        let source = Source::new("var a = )\nvar b = 2", "./main");
+        let label = Label::primary(Span::new(source, 8, 9)).with_message("here");
+
+        let output = render_with_context(vec![label], 2);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[3], "1 | var a = )");
+        assert_eq!(lines[5], "2 | var b = 2");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn context_lines_between_labels_are_not_repeated() {
+        let source = Source::new("var a = 1\nvar b = a\nvar c = 3\nvar d = b\n", "./main");
+        let first = Label::secondary(Span::new(Rc::clone(&source), 8, 9)).with_message("first");
+        let second = Label::primary(Span::new(source, 38, 39)).with_message("second");
+
+        let output = render_with_context(vec![first, second], 1);
+        let numbered: Vec<&str> = output.lines().filter(|line| line.starts_with(char::is_numeric)).collect();
+
+        assert_eq!(numbered, ["1 | var a = 1", "2 | var b = a", "3 | var c = 3", "4 | var d = b"]);
+        // the groups are adjacent once context is added, so there's no line break.
+        assert!(!output.lines().any(|line| line.trim() == "."));
+    }
+
+    #[test]
+    fn location_and_gutter_count_from_base_line() {
+        let source = "var x = )";
+        let span = Span::new(Source::with_base_line(source, "<repl>", 2), 8, 9);
+        let label = Label::primary(span.clone());
+
+        let mut writer = NoColor::new(vec![]);
+        let mut renderer = Renderer::new(&mut writer);
+        renderer.render_location(&span, 1).unwrap();
+        renderer
+            .render_snippet_source(1, 0, source, &[&label], Severity::Error)
+            .unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], " --> <repl>:3:9");
+        assert_eq!(lines[1], "3 | var x = )");
+    }
+}
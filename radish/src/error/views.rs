@@ -17,7 +17,7 @@ impl<'diagnostic> RichDiagnostic<'diagnostic> {
         // sort the labels based on their line number so that they get 
         // rendered in the correct order.
         let mut sorted_labels = self.diagnostic.labels.clone();
-        sorted_labels.sort_by(|a, b| a.span.start.cmp(&b.span.start));
+        sorted_labels.sort_by_key(|a| a.span.start);
 
         let mut primary_label: Option<&Label> = None;
 
@@ -39,7 +39,7 @@ impl<'diagnostic> RichDiagnostic<'diagnostic> {
                 Span::get_line_index(&label.span.source.contents, start);
 
             // have we seen this line before?
-            if last_line == None || last_line.unwrap() == start_line {
+            if last_line.is_none() || last_line.unwrap() == start_line {
                 // yup we've seen it before, so go ahead and just add it to the current
                 // group of labels.
                 current.push(label);
@@ -57,7 +57,19 @@ impl<'diagnostic> RichDiagnostic<'diagnostic> {
         // push the last group of labels to the collection of all labels
         all_labels.push(current);
 
-        let padding = last_line.unwrap().to_string().len();
+        let source = &sorted_labels[0].span.source;
+        let base_line = source.base_line;
+
+        // the last line that can be shown, ignoring the empty one after a
+        // trailing newline.
+        let mut final_line = Span::lines(&source.contents).len() - 1;
+        if final_line > 0 && source.contents.ends_with('\n') {
+            final_line -= 1;
+        }
+
+        let context = renderer.context_lines();
+        let last_shown = (last_line.unwrap() + context).min(final_line).max(last_line.unwrap());
+        let padding = (base_line + last_shown).to_string().len();
 
         // render this diagnostics's location
         if let Some(label) = primary_label {
@@ -70,24 +82,31 @@ impl<'diagnostic> RichDiagnostic<'diagnostic> {
 
         renderer.render_empty_line(padding + 1)?;
 
-        // render each source line and their labels
-        let mut previous_group: Option<&Vec<&Label>> = None;
-        for group in all_labels.iter() {
-            let start = group[0].span.start;
-            let (start_line, _) =
-                Span::get_line_index(&group[0].span.source.contents, start);
+        let group_lines: Vec<usize> = all_labels
+            .iter()
+            .map(|group| Span::get_line_index(&group[0].span.source.contents, group[0].span.start).0)
+            .collect();
 
-            // if there was a gap between this line and the last, render a line break.
-            if let Some(prev) = previous_group {
-                let prev_start = prev[0].span.start;
-                let (prev_start_line, _) =
-                    Span::get_line_index(&prev[0].span.source.contents, prev_start);
+        // render each source line and their labels, along with the lines
+        // around them that aren't part of another group.
+        let mut last_rendered: Option<usize> = None;
+        for (i, group) in all_labels.iter().enumerate() {
+            let start_line = group_lines[i];
 
-                if prev_start_line + 1 != start_line {
-                    renderer.render_line_break(padding)?;
-                }
+            let first = match last_rendered {
+                Some(last) => start_line.saturating_sub(context).max(last + 1),
+                None => start_line.saturating_sub(context),
             };
 
+            // if there was a gap between this line and the last, render a line break.
+            if matches!(last_rendered, Some(last) if last + 1 != first) {
+                renderer.render_line_break(padding)?;
+            }
+
+            for line in first..start_line {
+                renderer.render_context_line(padding, line, &source.contents, base_line)?;
+            }
+
             renderer.render_snippet_source(
                 padding,
                 start_line,
@@ -96,7 +115,16 @@ impl<'diagnostic> RichDiagnostic<'diagnostic> {
                 self.diagnostic.severity,
             )?;
 
-            previous_group = Some(group);
+            let mut last = (start_line + context).min(final_line).max(start_line);
+            if let Some(next) = group_lines.get(i + 1) {
+                last = last.min(next - 1);
+            }
+
+            for line in start_line + 1..=last {
+                renderer.render_context_line(padding, line, &source.contents, base_line)?;
+            }
+
+            last_rendered = Some(last.max(start_line));
         }
 
         renderer.render_empty_line(padding + 1)?;
@@ -13,3 +13,60 @@ pub use vm::value::Value;
 pub use common::module::{Module, ModuleBuilder};
 pub use namespace::{Namespace, NamespaceBuilder};
 pub use crate::core::RadishCore;
+
+/// Run `source` in a fresh VM and return the value of its last expression, or
+/// `nil` if it doesn't end with one. Errors are returned rather than printed.
+///
+/// # Examples
+///
+/// ```
+/// use radish::{run_str, Value};
+///
+/// assert_eq!(run_str("var a = 2\na * 21"), Ok(Value::Integer(42)));
+/// assert_eq!(run_str("print \"hi\""), Ok(Value::Nil));
+/// assert!(run_str("1 +").is_err());
+/// ```
+pub fn run_str(source: &str) -> Result<Value, RadishError> {
+    let mut config = config::Config::new();
+    // in REPL mode a trailing expression is the script's result.
+    config.repl = true;
+
+    let mut vm = VM::with_config(config);
+    vm.set_stderr(std::io::sink());
+
+    vm.eval(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::error::SyntaxErrorKind;
+
+    #[test]
+    fn run_str_returns_the_last_value() {
+        let value = run_str("1 + 2 * 3").unwrap();
+
+        assert_eq!(value, Value::Integer(7));
+        assert!(value.equals(&Value::Number(7.0)));
+        assert_eq!(run_str("1.5 * 2"), Ok(Value::Number(3.0)));
+    }
+
+    #[test]
+    fn run_str_starts_fresh() {
+        run_str("var a = 1").unwrap();
+
+        assert!(matches!(run_str("a"), Err(RadishError::CompilerError(_))));
+    }
+
+    #[test]
+    fn run_str_syntax_error() {
+        match run_str("1 + * 2") {
+            Err(RadishError::CompilerError(err)) => {
+                assert!(matches!(err.kind, SyntaxErrorKind::ExpectedExpression { .. }), "{:?}", err.kind)
+            }
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+
+        assert!(matches!(run_str("true - 1"), Err(RadishError::RuntimeError(_))));
+    }
+}
@@ -9,6 +9,12 @@ pub struct NamespaceBuilder {
     modules: Vec<Module>,
 }
 
+impl Default for NamespaceBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NamespaceBuilder {
     pub fn new() -> Self {
         Self { modules: vec![] }
@@ -34,6 +40,6 @@ impl NamespaceBuilder {
         }
 
         vm.load_module(namespace)
-            .expect(&format!("failed to load namespace: {}", name));
+            .unwrap_or_else(|_| panic!("failed to load namespace: {}", name));
     }
 }
@@ -2,6 +2,7 @@
 
 use crate::{
     common::CompiledModule,
+    compiler::AST,
     vm::{from_value::FromValue, trace::Trace, value::Closure, VM},
     RadishError, Value,
 };
@@ -25,7 +26,7 @@ impl VM {
     /// # }
     /// ```
     pub fn exec(&mut self, src: &str) -> Result<(), RadishError> {
-        self._eval(src)?;
+        self._eval::<()>(src)?;
         Ok(())
     }
 
@@ -50,6 +51,38 @@ impl VM {
         self._eval(src)
     }
 
+    /// Evaluate a string against the globals left by the code the VM ran
+    /// before it, without running that code again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), radish::RadishError> {
+    /// use radish::{config::Config, VM};
+    ///
+    /// let mut config = Config::new();
+    /// config.repl = true;
+    ///
+    /// let mut vm = VM::with_config(config);
+    ///
+    /// vm.exec("var a = 1\nprint \"hi\"")?;
+    ///
+    /// assert_eq!(vm.eval_in_session::<i32>("a + 1"), Ok(2));
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn eval_in_session<I: FromValue>(&mut self, src: &str) -> Result<I, RadishError> {
+        let module = self
+            .compiler
+            .compile_in_session(&self.config.default_filename, src)?;
+
+        match self.interpret(module) {
+            Ok(val) => I::from_value(val),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Execute a file.
     ///
     /// **NOTE**: if you require a return value, use [`eval_file()`][crate::vm::eval_file].
@@ -96,9 +129,84 @@ impl VM {
         }
     }
 
+    /// Execute the file `file_name` from its `ast`, such as one parsed with
+    /// [`parse_with_warnings()`][crate::compiler::pipeline::CompilerPipeLine::parse_with_warnings]
+    /// to report its warnings first, so the file isn't read and parsed again.
+    ///
+    /// **NOTE**: if you require a return value, use [`eval_file_ast()`][crate::vm::eval_file_ast].
+    pub fn exec_file_ast(&mut self, file_name: &str, ast: &AST) -> Result<(), RadishError> {
+        self._eval_file_ast(file_name, ast)?;
+        Ok(())
+    }
+
+    /// Evaluate the file `file_name` from its `ast`, returning its value.
+    ///
+    /// **NOTE**: if you do not require a return value, use [`exec_file_ast()`][crate::vm::exec_file_ast].
+    pub fn eval_file_ast<I: FromValue>(&mut self, file_name: &str, ast: &AST) -> Result<I, RadishError> {
+        match self._eval_file_ast(file_name, ast) {
+            Ok(val) => I::from_value(val),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Call a function value with `args`, returning what it returns. Both
+    /// functions written in radish and native functions can be called. The VM
+    /// must not be running, so this can't be used from inside a native
+    /// function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), radish::RadishError> {
+    /// use radish::{Value, VM};
+    ///
+    /// let mut vm = VM::new();
+    /// vm.exec("fun double(n) { return n * 2 }")?;
+    ///
+    /// let (_, double) = vm.globals().into_iter().find(|(name, _)| name == "double").unwrap();
+    ///
+    /// assert_eq!(vm.call_value(double, vec![Value::Integer(21)]), Ok(Value::Integer(42)));
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_value(&mut self, callable: Value, args: Vec<Value>) -> Result<Value, RadishError> {
+        let arg_count = args.len();
+        let runs_bytecode = matches!(callable, Value::Closure(_));
+
+        self.stack.push(callable.clone());
+        for arg in args {
+            self.stack.push(arg);
+        }
+
+        let called = self.call(callable, arg_count).inspect_err(|_| self.unwind());
+
+        let res = called.and_then(|_| match runs_bytecode {
+            true => self.run(),
+            // a native function has already left its result on the stack.
+            false => Ok(self.stack.pop()),
+        });
+
+        self.report(res).map_err(RadishError::from)
+    }
+
     /// Evaluate a file.
     fn _eval_file(&mut self, file_name: &str) -> Result<Value, RadishError> {
-        let module = self.loader.load(file_name, &mut self.compiler)?;
+        let name = self.loader.resolve(file_name, "");
+        let module = self.loader.load(&name, &mut self.compiler)?;
+
+        match self.interpret(module) {
+            Ok(res) => Ok(res),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Evaluate a file that has already been parsed.
+    fn _eval_file_ast(&mut self, file_name: &str, ast: &AST) -> Result<Value, RadishError> {
+        let name = self.loader.resolve(file_name, "");
+        let module = self.compiler.compile_module_ast(&name, ast)?;
+
+        self.loader.load_compiled(&name, module.clone());
 
         match self.interpret(module) {
             Ok(res) => Ok(res),
@@ -116,8 +224,29 @@ impl VM {
         }
     }
 
-    /// Interprete a compiled module.
+    /// Interprete a compiled module, reporting any runtime error to the VM's
+    /// error writer.
     fn interpret(&mut self, module: CompiledModule) -> Result<Value, Trace> {
+        let res = self.enter(module).and_then(|_| self.run());
+        self.report(res)
+    }
+
+    /// Report the runtime error in `res`, if there is one, to the VM's error
+    /// writer.
+    fn report(&mut self, res: Result<Value, Trace>) -> Result<Value, Trace> {
+        use std::io::Write;
+
+        if let Err(trace) = &res {
+            // there isn't much that can be done if the error can't be reported.
+            let _ = write!(self.err, "{}", trace);
+        }
+
+        res
+    }
+
+    /// Make `module` the last module and call its entry point, leaving the VM
+    /// ready to execute the entry point's first instruction.
+    pub(super) fn enter(&mut self, module: CompiledModule) -> Result<(), Trace> {
         use std::rc::Rc;
 
         self.last_module = module;
@@ -131,10 +260,637 @@ impl VM {
         let closure = Rc::new(Closure::new(entry));
 
         self.stack.push(Value::Closure(closure.clone()));
-        self.call_function(closure, 0)?;
 
-        let res = self.run()?;
+        self.call_function(closure, 0).inspect_err(|_| self.unwind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        common::opcode::Opcode,
+        compiler::error::{SyntaxError, SyntaxErrorKind},
+        config::{Config, IntDivision},
+        Module, RadishError, Value, VM,
+    };
+    use std::{cell::RefCell, io, rc::Rc};
+
+    /// An in-memory writer that can be inspected after being handed to the VM.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn repl_echoes_repr() {
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+
+        let result: Value = vm.eval("\"hi\"").unwrap();
+        assert_eq!(result.to_string(), "hi");
+        assert_eq!(result.repr(), "\"hi\"");
+    }
+
+    #[test]
+    fn indexing_bytes_gives_integers() {
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+
+        assert_eq!(vm.eval::<Value>("b\"A\\xff\"[0]"), Ok(Value::Integer(65)));
+        assert_eq!(vm.eval::<Value>("b\"A\\xff\"[-1]"), Ok(Value::Integer(255)));
+    }
+
+    #[test]
+    fn indexing_a_range_gives_integers() {
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+
+        assert_eq!(vm.eval::<Value>("range(10, 0, -3)[1]"), Ok(Value::Integer(7)));
+        assert_eq!(vm.eval::<Value>("range(10, 0, -3)[-1]"), Ok(Value::Integer(1)));
+    }
+
+    #[test]
+    fn repeat_string_negative_count_is_runtime_error() {
+        let mut vm = quiet_vm(false);
+
+        let result = vm.exec("var a = \"ab\" * -1");
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+    }
+
+    #[test]
+    fn redirect_output_and_errors() {
+        let out = SharedBuffer::default();
+        let err = SharedBuffer::default();
+
+        let mut vm = VM::new();
+        vm.set_stdout(out.clone()).set_stderr(err.clone());
+
+        let result = vm.exec("print \"hello\"\nvar a = \"ab\" * -1");
+
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+        assert_eq!(out.contents(), "hello\n");
+        assert_eq!(
+            err.contents(),
+            "cannot repeat a string a negative number of times\n    at 2:14\n"
+        );
+    }
+
+    const UNINITIALIZED_VAR: &str = "var x\n";
+    const UNINITIALIZED_READ: &str = "fun f() {\n    return g\n}\nf()\nvar g = 1\n";
+    const MISSING_RETURN: &str = "fun f(a) {\n    if a then\n        return 1\n    endif\n}\n";
+
+    /// A VM that discards its output, optionally in strict mode.
+    fn quiet_vm(strict: bool) -> VM {
+        let mut config = Config::new();
+        config.strict = strict;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(io::sink()).set_stderr(io::sink());
+
+        vm
+    }
+
+    #[test]
+    fn strict_rejects_uninitialized_var() {
+        let result = quiet_vm(true).exec(UNINITIALIZED_VAR);
+        assert!(matches!(result, Err(RadishError::CompilerError(_))));
+    }
+
+    #[test]
+    fn strict_rejects_uninitialized_read() {
+        let result = quiet_vm(true).exec(UNINITIALIZED_READ);
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+    }
+
+    #[test]
+    fn strict_rejects_missing_return() {
+        let result = quiet_vm(true).exec(MISSING_RETURN);
+        assert!(matches!(result, Err(RadishError::CompilerError(_))));
+
+        let returns = "fun f(a) {\n    if a then\n        return 1\n    else\n        return 2\n    endif\n}\n";
+        assert_eq!(quiet_vm(true).exec(returns), Ok(()));
+    }
+
+    #[test]
+    fn lenient_allows_implicit_nil() {
+        for src in &[UNINITIALIZED_VAR, UNINITIALIZED_READ, MISSING_RETURN] {
+            assert_eq!(quiet_vm(false).exec(src), Ok(()));
+        }
+    }
+
+    fn coercing_vm(coerce: bool) -> VM {
+        let mut config = Config::new();
+        config.coerce_numeric_strings = coerce;
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stderr(io::sink());
+
+        vm
+    }
+
+    fn dividing_vm(int_division: IntDivision) -> VM {
+        let mut config = Config::new();
+        config.int_division = int_division;
+        config.repl = true;
+
+        VM::with_config(config)
+    }
+
+    #[test]
+    fn float_int_division() {
+        let mut vm = dividing_vm(IntDivision::Float);
+
+        assert_eq!(vm.eval::<Value>("7 / 2"), Ok(Value::Number(3.5)));
+        assert_eq!(vm.eval::<Value>("6 / 2"), Ok(Value::Number(3.0)));
+        assert_eq!(vm.eval::<Value>("7.0 / 2"), Ok(Value::Number(3.5)));
+    }
+
+    #[test]
+    fn floor_int_division() {
+        let mut vm = dividing_vm(IntDivision::Floor);
+
+        assert_eq!(vm.eval::<Value>("7 / 2"), Ok(Value::Integer(3)));
+        // the quotient is truncated toward zero.
+        assert_eq!(vm.eval::<Value>("-7 / 2"), Ok(Value::Integer(-3)));
+        assert_eq!(vm.eval::<Value>("7 / -2"), Ok(Value::Integer(-3)));
+        assert_eq!(vm.eval::<Value>("-6 / -2"), Ok(Value::Integer(3)));
+        // only integers are divided as integers.
+        assert_eq!(vm.eval::<Value>("7.0 / 2"), Ok(Value::Number(3.5)));
+    }
+
+    #[test]
+    fn division_by_zero_in_each_mode() {
+        for mode in [IntDivision::Float, IntDivision::Floor] {
+            let mut vm = dividing_vm(mode);
+            vm.set_stderr(io::sink());
+
+            for src in ["1 / 0", "-1 / 0", "0 / 0"] {
+                match vm.eval::<Value>(src) {
+                    Err(RadishError::RuntimeError(trace)) => {
+                        assert_eq!(trace.message, "division by zero", "{} in {:?}", src, mode)
+                    }
+                    other => panic!("expected a runtime error for {} in {:?}, got {:?}", src, mode, other),
+                }
+            }
+
+            // floats still follow IEEE 754.
+            assert_eq!(vm.eval::<Value>("1.0 / 0"), Ok(Value::Number(f64::INFINITY)), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn coerce_numeric_strings() {
+        let mut vm = coercing_vm(true);
+
+        assert_eq!(vm.eval::<f64>("\"5\" + 3"), Ok(8.0));
+        assert_eq!(vm.eval::<f64>("\" 10 \" - \"4\""), Ok(6.0));
+        assert_eq!(vm.eval::<f64>("7 % \"4\""), Ok(3.0));
+    }
+
+    #[test]
+    fn coercion_keeps_string_operations() {
+        let mut vm = coercing_vm(true);
+
+        assert_eq!(vm.eval::<Value>("\"5\" + \"3\""), Ok(Value::from("53")));
+        assert_eq!(vm.eval::<Value>("\"5\" * 3"), Ok(Value::from("555")));
+    }
+
+    #[test]
+    fn coerce_unparseable_string() {
+        let result = coercing_vm(true).eval::<f64>("\"five\" + 3");
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+    }
+
+    #[test]
+    fn string_arithmetic_without_coercion() {
+        let result = coercing_vm(false).eval::<f64>("\"5\" + 3");
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+    }
+
+    fn arg_count_error(src: &str) -> Option<(usize, usize, (usize, usize))> {
+        match quiet_vm(false).exec(src) {
+            Err(RadishError::CompilerError(SyntaxError {
+                kind:
+                    SyntaxErrorKind::WrongArgCount {
+                        call,
+                        expected,
+                        got,
+                        ..
+                    },
+                ..
+            })) => Some((expected, got, (call.span.start, call.span.end))),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn direct_call_arg_count_checked_at_compile_time() {
+        let too_few = "fun add(a, b) {\n    return a + b\n}\nprint add(1)\n";
+        let too_many = "fun add(a, b) {\n    return a + b\n}\nprint add(1, 2, 3)\n";
+
+        assert_eq!(arg_count_error(too_few), Some((2, 1, (41, 47))));
+        assert_eq!(arg_count_error(too_many), Some((2, 3, (41, 53))));
+    }
+
+    #[test]
+    fn indirect_call_arg_count_checked_at_runtime() {
+        let indirect = "fun add(a, b) {\n    return a + b\n}\nvar f = add\nprint f(1)\n";
+        let reassigned = "fun add(a, b) {\n    return a + b\n}\nadd = fun(a) { return a }\nprint add(1)\n";
+
+        assert!(matches!(
+            quiet_vm(false).exec(indirect),
+            Err(RadishError::RuntimeError(_))
+        ));
+        assert_eq!(quiet_vm(false).exec(reassigned), Ok(()));
+    }
+
+    #[test]
+    fn profile_counts_opcodes() {
+        let mut config = Config::new();
+        config.profile = true;
+
+        let mut vm = VM::with_config(config);
+        vm.exec("var i = 0\nwhile i < 1000 loop\n    i += 1\nendloop\n")
+            .unwrap();
+
+        let histogram = vm.profile().unwrap().histogram();
+        let dominant: Vec<_> = histogram.iter().take(3).map(|(op, _)| op).collect();
+
+        assert_eq!(
+            dominant,
+            vec![&Opcode::Del, &Opcode::LoadGlobal, &Opcode::LoadConst]
+        );
+        assert!(histogram.contains(&(Opcode::Add, 1000)));
+        assert!(histogram.contains(&(Opcode::Loop, 1000)));
+    }
+
+    #[test]
+    fn no_profile_by_default() {
+        let mut vm = quiet_vm(false);
+        vm.exec("print 1").unwrap();
+
+        assert!(vm.profile().is_none());
+    }
+
+    /// The computed stack depth of the script compiled from `src`, and the
+    /// peak actually reached running it.
+    fn stack_depths(src: &str) -> (usize, usize) {
+        let mut config = Config::new();
+        config.profile = true;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(io::sink());
+        vm.exec(src).unwrap();
+
+        let entry = vm.last_module.borrow().entry().unwrap();
+        // the script's own closure sits below its stack frame.
+        (entry.chunk.max_stack + 1, vm.profile().unwrap().peak_stack())
+    }
+
+    #[test]
+    fn max_stack_matches_peak() {
+        let (computed, peak) = stack_depths("print 1 + (2 * (3 - (4 + (5 * (6 - [7, 8][0])))))\n");
+        assert_eq!(computed, 10);
+        assert_eq!(computed, peak);
+
+        let src = "var t = 0\nfor i in range(0, 3) loop\n    t = t + i * (i + 1)\nendloop\nprint t\n";
+        let (computed, peak) = stack_depths(src);
+        assert_eq!(computed, peak);
+    }
+
+    fn nested_sum(depth: usize) -> String {
+        format!("print {}1{}", "1 + (".repeat(depth), ")".repeat(depth))
+    }
+
+    #[test]
+    fn value_stack_overflow() {
+        let mut config = Config::new();
+        config.max_stack_size = 8;
+
+        let err = SharedBuffer::default();
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(io::sink()).set_stderr(err.clone());
+
+        assert_eq!(vm.exec(&nested_sum(4)), Ok(()));
+        assert!(matches!(
+            vm.exec(&nested_sum(20)),
+            Err(RadishError::RuntimeError(_))
+        ));
+        assert_eq!(err.contents(), "value stack overflow\n    at 1:42\n");
+    }
+
+    #[test]
+    fn reset_forgets_globals() {
+        let mut module = Module::new_("greet");
+        module.add_native("hello", 0, |_, _| Ok(Value::from("hello")));
+
+        let mut config = Config::new();
+        config.repl = true;
+
+        let mut vm = VM::with_config(config);
+        vm.load_module(module).unwrap();
+
+        vm.exec("var answer = 42").unwrap();
+        assert_eq!(vm.globals(), vec![("answer".to_string(), Value::Integer(42))]);
+
+        vm.reset();
+
+        assert!(vm.globals().is_empty());
+
+        vm.exec("var other = 1").unwrap();
+        assert_eq!(vm.globals(), vec![("other".to_string(), Value::Integer(1))]);
+
+        assert_eq!(
+            vm.eval::<Value>("import \"greet\"\ngreet.hello()"),
+            Ok(Value::from("hello"))
+        );
+    }
+
+    #[test]
+    fn builtins_can_be_disabled() {
+        let src = "print len([1, 2])\n";
+
+        let mut config = Config::new();
+        config.no_builtins = true;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(io::sink()).set_stderr(io::sink());
+
+        match vm.exec(src) {
+            Err(RadishError::CompilerError(SyntaxError {
+                kind: SyntaxErrorKind::UnresolvedIdent { item },
+                ..
+            })) => assert_eq!(item.content, "len"),
+            other => panic!("expected an unresolved `len`, got {:?}", other),
+        }
+
+        assert_eq!(quiet_vm(false).exec(src), Ok(()));
+    }
+
+    #[test]
+    fn panics_become_runtime_errors() {
+        let mut vm = quiet_vm(false);
+
+        // indexing a number still panics.
+        match vm.exec("var a = 1\nprint a[0]") {
+            Err(RadishError::RuntimeError(trace)) => {
+                assert_eq!(
+                    trace.message,
+                    "internal error: not implemented: field access on 1 is unsupported"
+                )
+            }
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+
+        // the VM is left usable.
+        assert_eq!(vm.exec("var b = 1 + 2"), Ok(()));
+    }
+
+    /// A script adding up 400 distinct literals, one per line, so that its
+    /// chunk needs more constants than a one-byte index can reach.
+    fn many_constants() -> String {
+        let mut src = String::from("var total = 0\n");
+
+        for n in 2..402 {
+            src.push_str(&format!("total += {}\n", n));
+        }
+
+        src.push_str("print total");
+        src
+    }
+
+    #[test]
+    fn constants_past_a_byte_index() {
+        let out = SharedBuffer::default();
+
+        let mut vm = VM::new();
+        vm.set_stdout(out.clone());
+
+        assert_eq!(vm.exec(&many_constants()), Ok(()));
+        assert_eq!(out.contents(), format!("{}\n", (2..402).sum::<i64>()));
+
+        let code = &vm.last_module.borrow().entry().unwrap().chunk.code;
+        assert!(code.contains(&(Opcode::LoadConstLong as u8)));
+    }
+
+    #[test]
+    fn constant_limit_is_configurable() {
+        let mut config = Config::new();
+        config.max_constants = 100;
+
+        let mut vm = VM::with_config(config);
+        vm.set_stdout(io::sink()).set_stderr(io::sink());
+
+        match vm.exec(&many_constants()) {
+            Err(RadishError::CompilerError(SyntaxError {
+                kind: SyntaxErrorKind::TooManyConstants { item, max: 100 },
+                ..
+            })) => assert_eq!(item.content, "102"),
+            other => panic!("expected too many constants, got {:?}", other),
+        }
+
+        // the compiler is left usable.
+        assert_eq!(vm.exec("var a = 2 + 3"), Ok(()));
+    }
+
+    #[test]
+    fn errors_in_calls_carry_the_call_stack() {
+        let src = "fun b(x) {\n    return x + true\n}\nfun a() {\n    return 1 + b(2)\n}\nprint a()\n";
+
+        let err = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_stderr(err.clone());
+
+        let trace = match vm.exec(src) {
+            Err(RadishError::RuntimeError(trace)) => trace,
+            other => panic!("expected a runtime error, got {:?}", other),
+        };
+
+        let calls: Vec<_> = trace
+            .calls
+            .iter()
+            .map(|call| (call.function.as_str(), call.span.as_ref().map(|span| span.text())))
+            .collect();
+
+        assert_eq!(calls, [("b", Some("b(2)")), ("a", Some("a()"))]);
+        assert_eq!(
+            err.contents(),
+            "cannot apply `+` to number and boolean\n    at 2:14\n    in `b`, called at 5:16\n    in `a`, called at 7:7\n"
+        );
+
+        // errors outside of any call have no stack.
+        match vm.exec("var c = -nil") {
+            Err(RadishError::RuntimeError(trace)) => assert!(trace.calls.is_empty()),
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors_unwind_the_vm() {
+        let mut vm = quiet_vm(false);
+
+        let result = vm.exec("fun f(a) {\n    return [a, a + nil]\n}\nprint f(1)\n");
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+
+        assert!(vm.stack().is_empty());
+        assert_eq!(vm.instruction_pointer(), None);
+        assert_eq!(vm.exec("print 1 + 2"), Ok(()));
+    }
+
+    #[test]
+    fn errors_in_imported_modules_name_the_module() {
+        // named after the process so that concurrent test runs don't share it.
+        let name = format!("radish_trace_module_{}.rdsh", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, "var x = 1\nvar y = x + true\n").unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let err = SharedBuffer::default();
+        let mut vm = VM::new();
+        vm.set_stdout(io::sink()).set_stderr(err.clone());
+
+        let result = vm.exec(&format!("print 1\nimport \"{}\"\n", path));
+        // remove the module before anything can fail, so it's never left behind.
+        std::fs::remove_file(&path).unwrap();
+
+        let trace = match result {
+            Err(RadishError::RuntimeError(trace)) => trace,
+            other => panic!("expected a runtime error, got {:?}", other),
+        };
+
+        assert_eq!(trace.calls.len(), 1);
+        assert!(trace.calls[0].module);
+        assert_eq!(trace.calls[0].function, path);
+        assert_eq!(
+            err.contents(),
+            format!(
+                "cannot apply `+` to number and boolean\n    at 2:11\n    in module `{}`, imported at 2:1\n",
+                path
+            )
+        );
+    }
+
+    #[test]
+    fn import_cycles_name_every_module() {
+        let dir = std::env::temp_dir();
+        let first = dir.join("radish_cycle_first.rdsh");
+        let second = dir.join("radish_cycle_second.rdsh");
+        std::fs::write(&first, "import \"radish_cycle_second\"\n").unwrap();
+        std::fs::write(&second, "import \"radish_cycle_first\"\n").unwrap();
+        let first = first.to_str().unwrap().to_string();
+        let second = second.to_str().unwrap().to_string();
+
+        let mut vm = quiet_vm(false);
+
+        let trace = match vm.exec_file(&first) {
+            Err(RadishError::RuntimeError(trace)) => trace,
+            other => panic!("expected a runtime error, got {:?}", other),
+        };
+
+        assert_eq!(
+            trace.message,
+            format!("import cycle detected: '{}' -> '{}' -> '{}'", first, second, first)
+        );
+
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+    }
+
+    #[test]
+    fn operator_errors_point_at_the_operator() {
+        let mut vm = quiet_vm(false);
+
+        let trace = match vm.exec("var a = 1\nprint a  +  true") {
+            Err(RadishError::RuntimeError(trace)) => trace,
+            other => panic!("expected a runtime error, got {:?}", other),
+        };
+
+        assert_eq!(trace.message, "cannot apply `+` to number and boolean");
+
+        let span = trace.span.expect("the error has a span");
+        assert_eq!((span.start, span.end), (19, 20));
+        assert_eq!(span.text(), "+");
+
+        // so do errors caught as panics.
+        match vm.exec("print !1") {
+            Err(RadishError::RuntimeError(trace)) => {
+                assert_eq!(trace.span.map(|span| span.start), Some(6))
+            }
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    fn global(vm: &VM, name: &str) -> Value {
+        let (_, value) = vm.globals().into_iter().find(|(global, _)| global == name).unwrap();
+        value
+    }
+
+    #[test]
+    fn call_value_calls_functions() {
+        let mut vm = quiet_vm(false);
+        vm.exec("fun add(a, b) {\n    return a + b\n}\nvar not_a_function = 1\n").unwrap();
+
+        let add = global(&vm, "add");
+        let args = vec![Value::Integer(1), Value::Integer(2)];
+        assert_eq!(vm.call_value(add.clone(), args), Ok(Value::Integer(3)));
+
+        // the VM can be used again afterwards.
+        let args = vec![Value::from("a"), Value::from("b")];
+        assert_eq!(vm.call_value(add, args), Ok(Value::from("ab")));
+
+        match vm.call_value(global(&vm, "not_a_function"), vec![]) {
+            Err(RadishError::RuntimeError(trace)) => assert_eq!(trace.message, "'1' is not callable"),
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn call_value_calls_natives() {
+        use crate::vm::native::NativeFunction;
+
+        let mut vm = quiet_vm(false);
+        let negate = Value::NativeFunction(Rc::new(NativeFunction::new(
+            Rc::new(|_, args| Ok(-args[0].clone())),
+            1,
+        )));
+
+        assert_eq!(vm.call_value(negate, vec![Value::Integer(2)]), Ok(Value::Integer(-2)));
+    }
+
+    #[test]
+    fn call_value_checks_arity() {
+        let mut vm = quiet_vm(false);
+        vm.exec("fun one(a) {\n    return a\n}\n").unwrap();
+
+        match vm.call_value(global(&vm, "one"), vec![]) {
+            Err(RadishError::RuntimeError(trace)) => {
+                assert_eq!(trace.message, "expected 1 arguments but got 0")
+            }
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
 
-        Ok(res)
+        assert_eq!(vm.call_value(global(&vm, "one"), vec![Value::Nil]), Ok(Value::Nil));
     }
 }
@@ -6,6 +6,12 @@ pub trait FromValue: Sized {
     fn from_value(val: Value) -> Result<Self, RadishError>;
 }
 
+impl FromValue for Value {
+    fn from_value(val: Value) -> Result<Self, RadishError> {
+        Ok(val)
+    }
+}
+
 impl FromValue for () {
     fn from_value(val: Value) -> Result<Self, RadishError> {
         match val {
@@ -31,6 +37,7 @@ macro_rules! impl_number {
             fn from_value(val: Value) -> Result<Self, RadishError> {
                 let num = match val {
                     Value::Number(val) => val,
+                    Value::Integer(val) => val as f64,
                     _ => return Err("cannot coerce type into number".into()),
                 };
 
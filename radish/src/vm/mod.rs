@@ -1,13 +1,19 @@
 //! Module containing Radish's runtime implementation and
 //! its datastructures.
 
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+    rc::Rc,
+};
 
 use crate::{
     common::{loader::Loader, CompiledModule, Module},
     compiler::pipeline::CompilerPipeLine,
     config::Config,
-    RadishCore,
+    core::builtins,
+    RadishCore, RadishError,
 };
 
 use self::stack::Stack;
@@ -16,13 +22,17 @@ mod eval;
 pub mod from_value;
 mod load;
 pub(crate) mod native;
+pub mod profile;
 mod run;
 mod stack;
+mod step;
 pub mod to_value;
 pub mod trace;
 pub mod value;
 
-use value::Closure;
+use profile::Profile;
+pub use step::StepResult;
+use value::{Closure, Value};
 
 #[derive(Debug)]
 pub struct CallFrame {
@@ -34,7 +44,6 @@ pub struct CallFrame {
     pub offset: usize,
 }
 
-#[derive(Debug)]
 pub struct VM {
     /// VM configuration.
     config: Box<Config>,
@@ -57,6 +66,12 @@ pub struct VM {
     compiler: CompilerPipeLine,
     /// VM's loader.
     pub(crate) loader: Loader,
+    /// Where the output of `print` is written.
+    out: Box<dyn Write>,
+    /// Where runtime errors are reported.
+    err: Box<dyn Write>,
+    /// Opcode counts, when profiling is enabled.
+    profile: Option<Profile>,
 }
 
 impl VM {
@@ -69,12 +84,24 @@ impl VM {
         vm
     }
 
+    /// Create a VM for `config`.
+    ///
+    /// # Panics
+    ///
+    /// If the config is invalid, such as a `default_radix` outside of 2 to 36.
+    /// Use [`try_with_config()`](VM::try_with_config) to handle that instead.
     pub fn with_config(config: Config) -> Self {
+        VM::try_with_config(config).expect("invalid config")
+    }
+
+    /// Create a VM for `config`, failing if the config is invalid.
+    pub fn try_with_config(config: Config) -> Result<Self, RadishError> {
         let config = Box::new(config);
 
-        let pipeline = CompilerPipeLine::new(&config).with_default_passes();
+        let pipeline = CompilerPipeLine::new(&config)?.with_default_passes();
+        let profile = if config.profile { Some(Profile::new()) } else { None };
 
-        Self {
+        Ok(Self {
             config,
             stack: Stack::new(),
             frames: Vec::new(),
@@ -84,8 +111,73 @@ impl VM {
             modules: Vec::new(),
             loader: Loader::new(),
             compiler: pipeline,
+            out: Box::new(io::stdout()),
+            err: Box::new(io::stderr()),
+            profile,
+        })
+    }
+
+    /// Redirect the output of `print` statements.
+    pub fn set_stdout(&mut self, out: impl Write + 'static) -> &mut Self {
+        self.out = Box::new(out);
+        self
+    }
+
+    /// Redirect the reporting of runtime errors.
+    pub fn set_stderr(&mut self, err: impl Write + 'static) -> &mut Self {
+        self.err = Box::new(err);
+        self
+    }
+
+    /// Return the VM to the state it was created in, forgetting the globals,
+    /// stack and call frames of everything that was run. The config, output
+    /// writers and natively registered modules are kept.
+    pub fn reset(&mut self) {
+        self.stack = Stack::new();
+        self.frames.clear();
+        self.frame_count = 0;
+        self.upvalues.clear();
+        self.last_module = Module::empty();
+        self.modules.clear();
+        self.compiler.reset();
+        self.loader.unload_files();
+
+        if let Some(profile) = &mut self.profile {
+            *profile = Profile::new();
         }
     }
+
+    /// How many times each opcode has been executed, if the VM was configured
+    /// to profile.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// The global variables of the last module that was run, in the order they
+    /// were defined. Builtin functions are left out.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        self.last_module
+            .borrow()
+            .globals()
+            .filter(|(name, value)| {
+                !(builtins::is_builtin(name) && matches!(value, Value::NativeFunction(_)))
+            })
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+}
+
+impl fmt::Debug for VM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VM")
+            .field("config", &self.config)
+            .field("stack", &self.stack)
+            .field("frames", &self.frames)
+            .field("frame_count", &self.frame_count)
+            .field("last_module", &self.last_module)
+            .field("compiler", &self.compiler)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Default for VM {
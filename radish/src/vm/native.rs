@@ -9,11 +9,22 @@ pub type InnerFn = dyn Fn(&mut VM, Vec<Value>) -> Result<Value, Trace>;
 pub struct NativeFunction {
     pub fun: Rc<InnerFn>,
     pub arity: u8,
+    /// How many arguments may follow the required ones.
+    pub optional: u8,
 }
 
 impl NativeFunction {
-    pub fn new<F: 'static>(fun: Rc<InnerFn>, arity: u8) -> Self {
-        Self { fun, arity }
+    pub fn new(fun: Rc<InnerFn>, arity: u8) -> Self {
+        Self::with_optional_args(fun, arity, 0)
+    }
+
+    pub fn with_optional_args(fun: Rc<InnerFn>, arity: u8, optional: u8) -> Self {
+        Self { fun, arity, optional }
+    }
+
+    /// Whether the function can be called with `arg_count` arguments.
+    pub fn accepts(&self, arg_count: usize) -> bool {
+        (self.arity as usize..=self.arity as usize + self.optional as usize).contains(&arg_count)
     }
 }
 
@@ -26,7 +37,7 @@ impl PartialOrd for NativeFunction {
 impl PartialEq for NativeFunction {
     fn eq(&self, other: &Self) -> bool {
         // FIXME: clippy doesn't like that we compare two trait object pointers.
-        Rc::ptr_eq(&self.fun, &other.fun) && self.arity == other.arity
+        Rc::ptr_eq(&self.fun, &other.fun) && self.arity == other.arity && self.optional == other.optional
     }
 }
 
@@ -35,6 +46,7 @@ impl fmt::Debug for NativeFunction {
         f.debug_struct("NativeFunction")
             .field("inner", &"Rc<InnerFn>")
             .field("airty", &self.arity)
+            .field("optional", &self.optional)
             .finish()
         }
 }
@@ -44,6 +56,7 @@ impl Clone for NativeFunction {
         Self {
             fun: Rc::clone(&self.fun),
             arity: self.arity,
+            optional: self.optional,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+//! Counting how often each opcode is executed, for `--profile`.
+
+use std::fmt;
+
+use crate::common::opcode::Opcode;
+
+/// The number of times each opcode has been executed, and how high the value
+/// stack got.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    counts: Box<[u64; 256]>,
+    peak_stack: usize,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Profile {
+            counts: Box::new([0; 256]),
+            peak_stack: 0,
+        }
+    }
+
+    /// Count one execution of the opcode `op`.
+    #[inline]
+    pub(crate) fn record(&mut self, op: u8) {
+        self.counts[op as usize] += 1;
+    }
+
+    /// Note the number of values on the stack.
+    #[inline]
+    pub(crate) fn record_stack(&mut self, len: usize) {
+        self.peak_stack = self.peak_stack.max(len);
+    }
+
+    /// The most values that were on the stack at once.
+    pub fn peak_stack(&self) -> usize {
+        self.peak_stack
+    }
+
+    /// The total number of instructions executed.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Each executed opcode with its count, most frequent first. Opcodes that
+    /// are executed equally often are in instruction set order.
+    pub fn histogram(&self) -> Vec<(Opcode, u64)> {
+        let mut counts: Vec<_> = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(op, count)| (op as u8, *count))
+            .collect();
+
+        counts.sort_by(|(a_op, a), (b_op, b)| b.cmp(a).then(a_op.cmp(b_op)));
+
+        counts
+            .into_iter()
+            .map(|(op, count)| (Opcode::from(op), count))
+            .collect()
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::new()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+
+        writeln!(f, "{:<16} {:>12} {:>7}", "opcode", "count", "%")?;
+
+        for (op, count) in self.histogram() {
+            let percent = count as f64 / total as f64 * 100.0;
+
+            writeln!(
+                f,
+                "{:<16} {:>12} {:>6.2}%",
+                format!("{:?}", op),
+                count,
+                percent
+            )?;
+        }
+
+        writeln!(f, "{:<16} {:>12}", "total", total)?;
+        writeln!(f, "{:<16} {:>12}", "peak stack", self.peak_stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_sorted_by_count() {
+        let mut profile = Profile::new();
+
+        for _ in 0..3 {
+            profile.record(Opcode::Add as u8);
+        }
+        profile.record(Opcode::Print as u8);
+        profile.record(Opcode::Nil as u8);
+
+        assert_eq!(
+            profile.histogram(),
+            vec![(Opcode::Add, 3), (Opcode::Nil, 1), (Opcode::Print, 1)]
+        );
+        assert_eq!(profile.total(), 5);
+    }
+}
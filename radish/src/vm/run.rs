@@ -1,27 +1,82 @@
-use std::{cell::RefCell, collections::HashMap, convert::TryInto, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    convert::TryInto,
+    io::Write,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    sync::Once,
+};
 
 use crate::{
-    common::{Disassembler, Opcode},
+    common::{span::Span, CompiledModule, Disassembler, Module, Opcode},
+    compiler::ast::Op,
     vm::trace::Trace,
-    vm::value::{Class, Closure, Value},
+    vm::value::{Class, Closure, Function, Value, ValueError},
 };
 
 use crate::vm::{CallFrame, VM};
 
 use super::{native::NativeFunction, value::UpValue};
 
+thread_local! {
+    /// How many calls to `catch_panics` are running on this thread.
+    static CATCHING_PANICS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Install, once per process, a panic hook that is silent on threads that are
+/// catching a VM's panics and defers to the previous hook everywhere else.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING_PANICS.with(Cell::get) == 0 {
+                previous(info);
+            }
+        }));
+    });
+}
+
 impl VM {
     /// Create a new [`Trace`] with the given message, adding context to it.
-    fn error(&mut self, message: impl ToString) -> Trace {
+    /// The VM is left as it is, [`unwind()`](VM::unwind) resets it.
+    fn error(&self, message: impl ToString) -> Trace {
         let mut trace = Trace::new(message);
+        trace.span = self.current_span();
+
+        // the script at the bottom wasn't called by anything.
+        for (caller, frame) in self.frames.iter().zip(self.frames.iter().skip(1)).rev() {
+            // the caller is still on the instruction that made the call.
+            let span = instruction_span(caller);
+            let function = &frame.closure.function;
 
-        while let Some(frame) = self.frames.pop() {
-            trace.add_context(frame.closure.function.name.to_string());
+            match function.module.upgrade() {
+                Some(module) if is_entry_of(function, &module.borrow()) => {
+                    trace.add_import(module.borrow().name.to_string(), span)
+                }
+                _ => trace.add_call(function.name.to_string(), span),
+            }
         }
 
         trace
     }
 
+    /// Abandon the running script after an error, leaving the VM ready to run
+    /// another.
+    pub(super) fn unwind(&mut self) {
+        self.frames.clear();
+        self.frame_count = 0;
+        self.stack.stack.clear();
+    }
+
+    /// The source span of the instruction being executed.
+    fn current_span(&self) -> Option<Span> {
+        instruction_span(self.frames.last()?)
+    }
+
     /// Return a reference to the top most frame on the call stack.
     #[inline]
     fn current_frame(&self) -> &CallFrame {
@@ -97,17 +152,16 @@ impl VM {
     /// Check if the top [`Value`] on the stack is falsey.
     #[inline]
     fn is_falsey(&mut self) -> bool {
-        matches!(
-            self.stack.peek(),
-            Some(Value::Nil) | Some(Value::Boolean(false))
-        )
+        self.stack.peek().is_some_and(|value| !value.truthy())
     }
 
+    /// Call `callee` with the `arg_count` arguments on top of the stack, which
+    /// sit above the callee itself.
     #[inline]
-    fn call_value(&mut self, callee: Value, arg_count: usize) -> Result<(), Trace> {
+    pub(super) fn call(&mut self, callee: Value, arg_count: usize) -> Result<(), Trace> {
         match callee {
             Value::Closure(fun) => self.call_function(fun, arg_count),
-            Value::NativeFunction(fun) => self.call_native(fun),
+            Value::NativeFunction(fun) => self.call_native(fun, arg_count),
             _ => {
                 let message = format!("'{}' is not callable", callee);
                 let trace = self.error(message);
@@ -128,8 +182,22 @@ impl VM {
         //    &closure.non_locals.borrow().len()
         //);
 
+        let arity = closure.function.arity as usize;
+
+        if arg_count != arity {
+            return Err(self.error(format!(
+                "expected {} arguments but got {}",
+                arity, arg_count
+            )));
+        }
+
         let offset = self.stack.stack.len() - arg_count;
 
+        // make room for everything the function will push up front, rather
+        // than growing the stack as it runs.
+        let max_stack = closure.function.chunk.max_stack;
+        self.stack.stack.reserve(max_stack.saturating_sub(arg_count));
+
         let frame = CallFrame {
             closure: Rc::clone(&closure),
             ip: 0,
@@ -159,6 +227,20 @@ impl VM {
         Ok(())
     }
 
+    fn make_string(&mut self) -> Result<(), Trace> {
+        let part_count = self.read_long() as usize;
+        let mut parts = Vec::with_capacity(part_count);
+
+        for _ in 0..part_count {
+            parts.push(self.stack.pop());
+        }
+
+        let string: String = parts.iter().rev().map(ToString::to_string).collect();
+        self.stack.push(Value::from(string.as_str()));
+
+        Ok(())
+    }
+
     fn make_object(&mut self) -> Result<(), Trace> {
         let element_count = self.read_long() as usize;
         let mut elements = HashMap::with_capacity(element_count);
@@ -167,7 +249,7 @@ impl VM {
             let value = self.stack.pop();
             let key = self.stack.pop();
 
-            elements.insert(key.to_string(), value);
+            elements.insert(key.map_key(), value);
         }
 
         let array = Value::Map(Rc::new(RefCell::new(elements)));
@@ -178,13 +260,29 @@ impl VM {
     }
 
     #[inline]
-    fn call_native(&mut self, fun: Rc<NativeFunction>) -> Result<(), Trace> {
+    fn call_native(&mut self, fun: Rc<NativeFunction>, arg_count: usize) -> Result<(), Trace> {
+        if !fun.accepts(arg_count) {
+            let expected = match fun.optional {
+                0 => fun.arity.to_string(),
+                optional => format!("{} to {}", fun.arity, fun.arity + optional),
+            };
+
+            return Err(self.error(format!(
+                "expected {} arguments but got {}",
+                expected, arg_count
+            )));
+        }
+
         let mut args = vec![];
-        for _ in 0..fun.arity {
+        for _ in 0..arg_count {
             args.push(self.stack.pop());
         }
+        args.reverse();
 
-        let result = (fun.fun)(self, args)?;
+        let result = match (fun.fun)(self, args) {
+            Ok(result) => result,
+            Err(trace) => return Err(self.error(trace.message)),
+        };
         self.stack.pop();
         self.stack.push(result);
 
@@ -232,7 +330,7 @@ impl VM {
         //println!("[vm] closing upvalues");
 
         for upval in closure.non_locals.borrow_mut().iter_mut() {
-            upval.close(&self);
+            upval.close(self);
         }
 
         //println!(
@@ -275,13 +373,58 @@ impl VM {
         Ok(())
     }
 
+    /// Apply a binary operator to the top two values on the stack, converting
+    /// numeric strings to numbers first if enabled and the operands need it.
+    #[inline]
+    fn binary_op(&mut self, op: Op) -> Result<(), Trace> {
+        let b = self.stack.pop();
+        let a = self.stack.pop();
+
+        let int_division = self.config.int_division;
+
+        let result = match Value::binary_op_with_division(&op, &a, &b, int_division) {
+            Err(ValueError::UnsupportedOperands { .. })
+                if self.config.coerce_numeric_strings && is_arithmetic(&op) =>
+            {
+                match (a.coerce_numeric_string(), b.coerce_numeric_string()) {
+                    (Ok(a), Ok(b)) => Value::binary_op_with_division(&op, &a, &b, int_division),
+                    (Err(msg), _) | (_, Err(msg)) => Err(ValueError::Invalid(msg)),
+                }
+            }
+            result => result,
+        };
+
+        match result {
+            Ok(value) => {
+                self.stack.push(value);
+                Ok(())
+            }
+            Err(err) => Err(self.error(err)),
+        }
+    }
+
+    /// Apply a unary operator to the value on top of the stack.
+    #[inline]
+    fn unary_op(&mut self, op: Op) -> Result<(), Trace> {
+        let operand = self.stack.pop();
+
+        match Value::unary_op(&op, &operand) {
+            Ok(value) => {
+                self.stack.push(value);
+                Ok(())
+            }
+            Err(err) => Err(self.error(err)),
+        }
+    }
+
     #[inline]
     fn print(&mut self) -> Result<(), Trace> {
         let msg = self.stack.pop();
 
-        println!("{}", msg);
+        if let Err(err) = writeln!(self.out, "{}", msg) {
+            return Err(self.error(err));
+        }
 
-        //self.config.stdout.write(&format!("{}", msg));
         Ok(())
     }
 
@@ -322,7 +465,7 @@ impl VM {
 
         let slot_index = relative_index + offset; // - 1;
 
-        self.stack.stack[slot_index as usize] = self.stack.peek().unwrap();
+        self.stack.stack[slot_index] = self.stack.peek().unwrap();
 
         Ok(())
     }
@@ -331,16 +474,23 @@ impl VM {
     fn load_global(&mut self) -> Result<(), Trace> {
         let index = self.read_long() as usize;
 
-        let value = self
+        let module = self
             .current_frame()
             .closure
             .function
             .module
             .upgrade()
-            .expect("Module has already been dropped")
-            .borrow()
-            .get_value_at_index(index)
-            .clone();
+            .expect("Module has already been dropped");
+
+        if self.config.strict && !module.borrow().is_initialized(index) {
+            let msg = format!(
+                "variable '{}' was read before it was initialized",
+                module.borrow().name_of(index).unwrap_or_default()
+            );
+            return Err(self.error(msg));
+        }
+
+        let value = module.borrow().get_value_at_index(index).clone();
 
         self.stack.push(value);
 
@@ -383,6 +533,7 @@ impl VM {
                 // check if the index is a number.
                 let index = match prop {
                     Value::Number(val) => val,
+                    Value::Integer(val) => val as f64,
                     _ => {
                         return Err(self.error("Array indices must be integers"));
                     }
@@ -412,8 +563,47 @@ impl VM {
 
                 self.stack.push(value);
             }
+            Value::Range(range) => {
+                let index = match prop {
+                    Value::Number(val) if val.fract() == 0.0 => val,
+                    Value::Integer(val) => val as f64,
+                    _ => {
+                        return Err(self.error("Range indices must be integers"));
+                    }
+                };
+
+                let length = range.len() as f64;
+
+                // count negative indices back from the end.
+                let index = if index < 0.0 { length + index } else { index };
+
+                match range.get(index as usize) {
+                    Some(n) if index >= 0.0 => self.stack.push(Value::Integer(n)),
+                    _ => return Err(self.error("Index out of bounds")),
+                }
+            }
+            Value::Bytes(bytes) => {
+                let index = match prop {
+                    Value::Number(val) if val.fract() == 0.0 => val,
+                    Value::Integer(val) => val as f64,
+                    _ => {
+                        return Err(self.error("Byte indices must be integers"));
+                    }
+                };
+
+                let bytes = bytes.borrow();
+                let length = bytes.len() as f64;
+
+                // count negative indices back from the end.
+                let index = if index < 0.0 { length + index } else { index };
+
+                match bytes.get(index as usize) {
+                    Some(byte) if index >= 0.0 => self.stack.push(Value::Integer(*byte as i64)),
+                    _ => return Err(self.error("Index out of bounds")),
+                }
+            }
             Value::Map(map) => {
-                let key = prop.to_string();
+                let key = prop.map_key();
 
                 let value = match map.borrow().get(&key) {
                     Some(val) => val.clone(),
@@ -439,6 +629,7 @@ impl VM {
                 // check if the index is a number.
                 let index = match idx {
                     Value::Number(val) => val,
+                    Value::Integer(val) => val as f64,
                     _ => {
                         return Err(self.error("Array indices must be integers"));
                     }
@@ -466,10 +657,45 @@ impl VM {
                 elements.borrow_mut()[index] = val;
             }
             Value::Map(map) => {
-                let key = idx.to_string();
+                let key = idx.map_key();
 
                 map.borrow_mut().insert(key, val);
             }
+            Value::Range(_) => {
+                return Err(self.error("Ranges cannot be modified"));
+            }
+            Value::Bytes(bytes) => {
+                let index = match idx {
+                    Value::Number(val) if val.fract() == 0.0 => val,
+                    Value::Integer(val) => val as f64,
+                    _ => {
+                        return Err(self.error("Byte indices must be integers"));
+                    }
+                };
+
+                let byte = match val {
+                    Value::Integer(val) => val.try_into().ok(),
+                    Value::Number(val) if val.fract() == 0.0 && (0.0..=255.0).contains(&val) => {
+                        Some(val as u8)
+                    }
+                    _ => None,
+                };
+
+                let byte = match byte {
+                    Some(byte) => byte,
+                    None => return Err(self.error("Bytes can only hold integers from 0 to 255")),
+                };
+
+                let length = bytes.borrow().len() as f64;
+
+                // count negative indices back from the end.
+                let index = if index < 0.0 { length + index } else { index };
+
+                match bytes.borrow_mut().get_mut(index as usize) {
+                    Some(slot) if index >= 0.0 => *slot = byte,
+                    _ => return Err(self.error("Index out of bounds")),
+                }
+            }
             _ => unimplemented!("field access on {} is unsupported", obj),
         }
 
@@ -503,7 +729,7 @@ impl VM {
             "the closure's upvalue list should not be empty"
         );
 
-        let val = upvalues.borrow()[index - 1].inner(&self);
+        let val = upvalues.borrow()[index].inner(self);
 
         self.stack.push(val);
 
@@ -558,6 +784,28 @@ impl VM {
         Ok(())
     }
 
+    #[inline]
+    fn iterate(&mut self) -> Result<(), Trace> {
+        let slot_index = self.read_long() as usize + self.current_frame().offset;
+
+        let position = match self.stack.stack[slot_index + 1] {
+            Value::Number(position) => position as usize,
+            _ => unreachable!("the position of a `for` loop is always a number"),
+        };
+
+        match self.stack.stack[slot_index].iter_next(position) {
+            Ok(Some((item, next))) => {
+                self.stack.stack[slot_index + 1] = Value::Number(next as f64);
+                self.stack.push(item);
+                self.stack.push(Value::Boolean(true));
+            }
+            Ok(None) => self.stack.push(Value::Boolean(false)),
+            Err(msg) => return Err(self.error(msg)),
+        }
+
+        Ok(())
+    }
+
     #[inline]
     fn import(&mut self) -> Result<(), Trace> {
         let path = self
@@ -566,22 +814,42 @@ impl VM {
             .into_string()
             .expect("path is not a string");
 
-        let module = match self.loader.load(&path.borrow(), &mut self.compiler) {
-            Ok(m) => m,
-            Err(e) => {
-                e.emit();
-                let msg = "failed to load module";
-                return Err(Trace::new(msg));
-            }
+        // native modules are loaded under their own name, files are resolved
+        // relative to the importing file.
+        let name = if self.loader.is_cached(&path.borrow()) {
+            path.borrow().to_string()
+        } else {
+            let importer = self.last_module.borrow().name.to_string();
+            self.loader.resolve(&path.borrow(), &importer)
         };
 
-        self.stack.push(Value::Module(Rc::clone(&module)));
+        // a module that has been loaded before has already been run, unless it
+        // is still running, in which case it (indirectly) imports itself.
+        if let Some(module) = self.loader.get(&name) {
+            if let Some(cycle) = self.import_cycle(&module) {
+                let msg = match cycle.as_slice() {
+                    [_] => format!("import cycle detected: '{}' imports itself", name),
+                    _ => format!("import cycle detected: {} -> '{}'", cycle.join(" -> "), name),
+                };
+                return Err(self.error(msg));
+            }
 
-        if self.loader.is_cached(&*path.borrow()) {
+            self.stack.push(Value::Module(module));
             return Ok(());
+        }
+
+        let module = match self.loader.load(&name, &mut self.compiler) {
+            Ok(m) => m,
+            Err(e) => {
+                let tab_width = self.config.tab_width;
+                let _ = e.emit_to_with_tab_width(&mut termcolor::NoColor::new(&mut self.err), tab_width);
+                let msg = format!("failed to load module '{}'", name);
+                return Err(self.error(msg));
+            }
         };
 
         if !module.borrow().has_entry() {
+            self.stack.push(Value::Module(module));
             return Ok(());
         }
 
@@ -601,13 +869,36 @@ impl VM {
         Ok(())
     }
 
+    /// Whether `frame` is running the top level code of the current module.
+    fn is_module_entry(&self, frame: &CallFrame) -> bool {
+        match self.last_module.borrow().entry() {
+            Some(entry) => Rc::ptr_eq(&frame.closure.function, &entry),
+            None => false,
+        }
+    }
+
+    /// If the top level code of `module` is currently being run, the quoted
+    /// names of the modules from `module` to the one running now, each of
+    /// which imported the next.
+    fn import_cycle(&self, module: &CompiledModule) -> Option<Vec<String>> {
+        let running: Vec<&CompiledModule> = self.modules.iter().chain(Some(&self.last_module)).collect();
+        let start = running.iter().position(|m| Rc::ptr_eq(m, module))?;
+
+        let names = running[start..]
+            .iter()
+            .map(|m| format!("'{}'", m.borrow().name))
+            .collect();
+
+        Some(names)
+    }
+
     fn _close_upvalues(&mut self) {
         println!("[vm] closing upvalues");
 
         let closure = Rc::clone(&self.current_frame_mut().closure);
 
         for upval in closure.non_locals.borrow_mut().iter_mut() {
-            upval.close(&self);
+            upval.close(self);
         }
 
         println!(
@@ -617,166 +908,248 @@ impl VM {
         );
     }
 
+    /// Execute instructions until the script returns.
     pub(crate) fn run(&mut self) -> Result<Value, Trace> {
-        macro_rules! binary_op {
-            ($op:tt) => {{
-                let b = self.stack.pop();
-                let a = self.stack.pop();
-                self.stack.push(Value::from(a $op b));
-            }};
+        self.catch_panics(|vm| loop {
+            if let Some(result) = vm.execute_instruction()? {
+                return Ok(result);
+            }
+        })
+        .inspect_err(|_| self.unwind())
+    }
+
+    /// Execute a single instruction, returning the script's result if it was
+    /// the script's final `return`.
+    pub(crate) fn run_one(&mut self) -> Result<Option<Value>, Trace> {
+        self.catch_panics(|vm| vm.execute_instruction())
+            .inspect_err(|_| self.unwind())
+    }
+
+    /// Call `f`, turning a panic into a runtime error.
+    ///
+    /// Some operations on values still panic instead of returning an error. A
+    /// panic is caught here and turned into a runtime error, so a bad
+    /// operation doesn't take the REPL or an embedding program down with it.
+    /// The panic hook stays silent on this thread meanwhile, so the panic is
+    /// only reported as that error, without a message and backtrace of its
+    /// own. Panics on other threads are reported as usual.
+    fn catch_panics<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Trace>,
+    ) -> Result<T, Trace> {
+        install_panic_hook();
+
+        CATCHING_PANICS.with(|count| count.set(count.get() + 1));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        CATCHING_PANICS.with(|count| count.set(count.get() - 1));
+
+        match result {
+            Ok(res) => res,
+            Err(payload) => {
+                let detail = payload
+                    .downcast_ref::<&str>()
+                    .map(|msg| msg.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned());
+
+                Err(self.error(match detail {
+                    Some(detail) => format!("internal error: {}", detail),
+                    None => "internal error".to_string(),
+                }))
+            }
+        }
+    }
+
+    /// Decode and execute the instruction at the current frame's instruction
+    /// pointer, returning the script's result once its last frame returns.
+    fn execute_instruction(&mut self) -> Result<Option<Value>, Trace> {
+        if self.config.trace {
+            let dis = Disassembler::new(
+                "script",
+                &self.frames[self.frame_count - 1].closure.function,
+            );
+            let offset = &self.frames[self.frame_count - 1].ip;
+            dis.disassemble_instruction(*offset);
+            print!("    ");
+            for slot in &self.stack.stack {
+                print!("[ {} ]", &slot);
+            }
+            println!();
+        }
+
+        if self.stack.stack.len() > self.config.max_stack_size {
+            return Err(self.error("value stack overflow"));
         }
 
-        macro_rules! unary_op {
-            ($op:tt) => {{
-                let val = self.stack.pop();
-                self.stack.push(Value::from($op val));
-            }};
+        if let Some(profile) = &mut self.profile {
+            let frame = &self.frames[self.frame_count - 1];
+            profile.record(frame.closure.function.chunk.code[frame.ip]);
+            profile.record_stack(self.stack.stack.len());
         }
 
-        loop {
-            if self.config.trace {
-                let dis = Disassembler::new(
-                    "script",
-                    &self.frames[self.frame_count - 1].closure.function,
+        match self.decode_opcode() {
+            Opcode::LoadConst => {
+                let index = self.read_byte() as usize;
+                self.stack.push(
+                    self.frames[self.frame_count - 1]
+                        .closure
+                        .function
+                        .chunk
+                        .constants[index]
+                        .clone(),
                 );
-                let offset = &self.frames[self.frame_count - 1].ip;
-                dis.disassemble_instruction(*offset);
-                print!("    ");
-                for slot in &self.stack.stack {
-                    print!("[ {} ]", &slot);
-                }
-                println!();
-            }
-
-            match self.decode_opcode() {
-                Opcode::LoadConst => {
-                    let index = self.read_byte() as usize;
-                    self.stack.push(
-                        self.frames[self.frame_count - 1]
-                            .closure
-                            .function
-                            .chunk
-                            .constants[index]
-                            .clone(),
-                    );
-                }
-                Opcode::LoadConstLong => {
-                    let constant = self.read_constant_long();
-                    self.stack.push(constant);
-                }
-                Opcode::True => {
-                    self.stack.push(Value::Boolean(true));
-                }
-                Opcode::False => {
-                    self.stack.push(Value::Boolean(false));
-                }
-                Opcode::Nil => {
-                    self.stack.push(Value::Nil);
-                }
-                Opcode::Del => {
-                    self.stack.pop();
-                }
-                Opcode::Neg => unary_op!(-),
-                Opcode::Not => unary_op!(!),
-                Opcode::Add => binary_op!(+),
-                Opcode::Sub => binary_op!(-),
-                Opcode::Mul => binary_op!(*),
-                Opcode::Div => binary_op!(/),
-                Opcode::Rem => binary_op!(%),
-                Opcode::CmpLT => binary_op!(<),
-                Opcode::CmpGT => binary_op!(>),
-                Opcode::CmpEq => binary_op!(==),
-                Opcode::CmpLTEq => binary_op!(<=),
-                Opcode::CmpGTEq => binary_op!(>=),
-                Opcode::CmpNotEq => binary_op!(!=),
-                Opcode::DefGlobal => {
-                    let index = self.read_long() as usize;
-                    self.last_module
-                        .borrow_mut()
-                        .set_value_at_index(index, self.stack.peek().unwrap());
+            }
+            Opcode::LoadConstLong => {
+                let constant = self.read_constant_long();
+                self.stack.push(constant);
+            }
+            Opcode::True => {
+                self.stack.push(Value::Boolean(true));
+            }
+            Opcode::False => {
+                self.stack.push(Value::Boolean(false));
+            }
+            Opcode::Nil => {
+                self.stack.push(Value::Nil);
+            }
+            Opcode::Zero => {
+                self.stack.push(Value::Integer(0));
+            }
+            Opcode::One => {
+                self.stack.push(Value::Integer(1));
+            }
+            Opcode::Del => {
+                self.stack.pop();
+            }
+            Opcode::Neg => self.unary_op(Op::Subtract)?,
+            Opcode::Not => self.unary_op(Op::Bang)?,
+            Opcode::Add => self.binary_op(Op::Add)?,
+            Opcode::Sub => self.binary_op(Op::Subtract)?,
+            Opcode::Mul => self.binary_op(Op::Multiply)?,
+            Opcode::Div => self.binary_op(Op::Divide)?,
+            Opcode::Rem => self.binary_op(Op::Remainder)?,
+            Opcode::CmpLT => self.binary_op(Op::LessThan)?,
+            Opcode::CmpGT => self.binary_op(Op::GreaterThan)?,
+            Opcode::CmpEq => self.binary_op(Op::EqualsTo)?,
+            Opcode::CmpLTEq => self.binary_op(Op::LessThanEquals)?,
+            Opcode::CmpGTEq => self.binary_op(Op::GreaterThanEquals)?,
+            Opcode::CmpNotEq => self.binary_op(Op::NotEqual)?,
+            Opcode::DefGlobal => {
+                let index = self.read_long() as usize;
+                self.last_module
+                    .borrow_mut()
+                    .set_value_at_index(index, self.stack.peek().unwrap());
+
+                self.stack.pop();
+            }
+            Opcode::LoadGlobal => self.load_global()?,
+            Opcode::SaveGlobal => self.save_global()?,
+            Opcode::LoadLocal => self.load_local()?,
+            Opcode::SaveLocal => self.save_local()?,
+            Opcode::DefCapture => self.def_upvalue()?,
+            Opcode::LoadCapture => self.load_upvalue()?,
+            Opcode::SaveCapture => self.save_upvalue()?,
+            Opcode::LoadField => self.load_field()?,
+            Opcode::SaveField => self.save_field()?,
+            Opcode::JumpIfFalse => self.jump_if_false()?,
+            Opcode::JumpIfTrue => self.jump_if_true()?,
+            Opcode::Jump => self.jump()?,
+            Opcode::Loop => self.loop_()?,
+            Opcode::Iterate => self.iterate()?,
+            Opcode::BuildArray => self.make_array()?,
+            Opcode::BuildMap => self.make_object()?,
+            Opcode::BuildString => self.make_string()?,
+            Opcode::Closure => self.make_closure()?,
+            Opcode::BuildClass => self.make_class()?,
+            Opcode::BuildCon => self.make_constructor()?,
+            Opcode::Print => self.print()?,
+            Opcode::Import => self.import()?,
+            Opcode::Call => {
+                let arg_count = self.read_byte() as usize;
+                let callee = self.stack.peek_n(arg_count + 1).unwrap();
+                self.call(callee, arg_count)?;
+            }
+            Opcode::Return => {
+                let result = self.stack.pop(); // pop return value
 
-                    self.stack.pop();
-                }
-                Opcode::LoadGlobal => self.load_global()?,
-                Opcode::SaveGlobal => self.save_global()?,
-                Opcode::LoadLocal => self.load_local()?,
-                Opcode::SaveLocal => self.save_local()?,
-                Opcode::DefCapture => self.def_upvalue()?,
-                Opcode::LoadCapture => self.load_upvalue()?,
-                Opcode::SaveCapture => self.save_upvalue()?,
-                Opcode::LoadField => self.load_field()?,
-                Opcode::SaveField => self.save_field()?,
-                Opcode::JumpIfFalse => self.jump_if_false()?,
-                Opcode::JumpIfTrue => self.jump_if_true()?,
-                Opcode::Jump => self.jump()?,
-                Opcode::Loop => self.loop_()?,
-                Opcode::BuildArray => self.make_array()?,
-                Opcode::BuildMap => self.make_object()?,
-                Opcode::Closure => self.make_closure()?,
-                Opcode::BuildClass => self.make_class()?,
-                Opcode::BuildCon => self.make_constructor()?,
-                Opcode::Print => self.print()?,
-                Opcode::Import => self.import()?,
-                Opcode::Call => {
-                    let arg_count = self.read_byte() as usize;
-                    let callee = self.stack.peek_n(arg_count + 1).unwrap();
-                    self.call_value(callee, arg_count)?;
-                }
-                Opcode::Return => {
-                    let result = self.stack.pop(); // pop return value
+                // if that was the last frame, exit the VM.
+                if self.frame_count - 1 == 0 {
+                    // leave the VM ready to run another script.
+                    self.frames.pop();
+                    self.frame_count = 0;
+                    self.stack.stack.clear();
 
-                    // if that was the last frame, exit the VM.
-                    if self.frame_count - 1 == 0 {
-                        //self.stack.pop();
-                        return Ok(result);
-                    }
+                    return Ok(Some(result));
+                }
 
-                    self.frame_count -= 1;
+                self.frame_count -= 1;
 
-                    while self.stack.stack.len() > self.frames[self.frame_count].offset {
-                        self.stack.stack.pop();
-                    }
+                while self.stack.stack.len() > self.frames[self.frame_count].offset {
+                    self.stack.stack.pop();
+                }
 
-                    // pop the function being called.
-                    self.stack.pop();
+                // pop the function being called.
+                self.stack.pop();
 
-                    // push the result back onto the stack.
-                    self.stack.push(result);
+                // push the result back onto the stack.
+                self.stack.push(result);
 
-                    self.frames.pop();
+                let frame = self.frames.pop().unwrap();
 
-                    if !self.modules.is_empty() {
-                        // The previous module
-                        let last_module = self.modules.pop().unwrap();
+                if !self.modules.is_empty() && self.is_module_entry(&frame) {
+                    // The previous module
+                    let last_module = self.modules.pop().unwrap();
 
-                        let current_module = std::mem::replace(&mut self.last_module, last_module);
+                    let current_module = std::mem::replace(&mut self.last_module, last_module);
 
-                        // FIXME: needlessly push and pop the `nil` value returned by the module.
-                        self.stack.pop();
+                    // FIXME: needlessly push and pop the `nil` value returned by the module.
+                    self.stack.pop();
 
-                        self.stack.push(Value::Module(current_module));
-                    }
+                    self.stack.push(Value::Module(current_module));
                 }
             }
+        }
 
-            if self.config.trace {
-                let mut width = 0;
+        if self.config.trace {
+            let mut width = 0;
 
-                print!("    ");
-                for slot in &self.stack.stack {
-                    width += 4 + &slot.to_string().len();
+            print!("    ");
+            for slot in &self.stack.stack {
+                width += 4 + &slot.to_string().len();
 
-                    print!("[ {} ]", &slot);
-                }
+                print!("[ {} ]", &slot);
+            }
 
-                let spacing = " ".repeat(100_usize.checked_sub(width).unwrap_or(10));
+            let spacing = " ".repeat(100_usize.checked_sub(width).unwrap_or(10));
 
-                let module_name = &self.last_module.borrow().name;
+            let module_name = &self.last_module.borrow().name;
 
-                print!("{}{}", spacing, module_name);
+            print!("{}{}", spacing, module_name);
 
-                println!();
-            }
+            println!();
         }
+
+        Ok(None)
+    }
+}
+
+/// Whether `op` does arithmetic, as opposed to comparing its operands.
+fn is_arithmetic(op: &Op) -> bool {
+    matches!(op, Op::Add | Op::Subtract | Op::Multiply | Op::Divide | Op::Remainder)
+}
+
+/// Whether `function` is the top level code of `module`.
+fn is_entry_of(function: &Rc<Function>, module: &Module) -> bool {
+    module.entry().is_some_and(|entry| Rc::ptr_eq(function, &entry))
+}
+
+/// The source span of the instruction `frame` is executing. The instruction
+/// pointer has already moved past its opcode, but every byte of an
+/// instruction shares the opcode's span, so the byte before it will do.
+fn instruction_span(frame: &CallFrame) -> Option<Span> {
+    let span = frame.closure.function.chunk.span_at(frame.ip.checked_sub(1)?);
+
+    match span.source.contents.is_empty() {
+        true => None,
+        false => Some(span),
     }
 }
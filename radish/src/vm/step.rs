@@ -0,0 +1,166 @@
+//! Module containing the VM's single-stepping methods, for debuggers and for
+//! watching the VM work.
+
+use std::io::Write;
+
+use crate::{
+    common::Opcode,
+    vm::{trace::Trace, VM},
+    RadishError, Value,
+};
+
+/// The outcome of executing a single instruction with [`VM::step()`].
+#[derive(Debug, PartialEq)]
+pub enum StepResult {
+    /// The script has more instructions to execute.
+    Running,
+    /// The script returned, with the value it returned.
+    Finished(Value),
+}
+
+impl VM {
+    /// Compile `src` and prepare to run it one instruction at a time with
+    /// [`step()`][VM::step], without executing any of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), radish::RadishError> {
+    /// use radish::{vm::StepResult, VM};
+    ///
+    /// let mut vm = VM::new();
+    /// vm.start("print 1 + 2")?;
+    ///
+    /// while let StepResult::Running = vm.step()? {
+    ///     println!("{:?} {:?}", vm.current_opcode(), vm.stack());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn start(&mut self, src: &str) -> Result<(), RadishError> {
+        let module = self.compiler.compile(&self.config.default_filename, src)?;
+
+        Ok(self.enter(module)?)
+    }
+
+    /// Like [`start()`][VM::start], for the file at `file_name`.
+    pub fn start_file(&mut self, file_name: &str) -> Result<(), RadishError> {
+        let name = self.loader.resolve(file_name, "");
+        let module = self.loader.load(&name, &mut self.compiler)?;
+
+        Ok(self.enter(module)?)
+    }
+
+    /// Execute exactly one instruction of the script given to
+    /// [`start()`][VM::start]. A runtime error is reported to the VM's error
+    /// writer as well as returned, and ends the script.
+    pub fn step(&mut self) -> Result<StepResult, RadishError> {
+        if self.frame_count == 0 {
+            return Err(Trace::new("there is no script to step through").into());
+        }
+
+        match self.run_one() {
+            Ok(Some(result)) => Ok(StepResult::Finished(result)),
+            Ok(None) => Ok(StepResult::Running),
+            Err(trace) => {
+                // there isn't much that can be done if the error can't be reported.
+                let _ = write!(self.err, "{}", trace);
+
+                Err(trace.into())
+            }
+        }
+    }
+
+    /// Where the current call frame is in its function's code, or `None` if
+    /// nothing is running.
+    pub fn instruction_pointer(&self) -> Option<usize> {
+        self.frames.last().map(|frame| frame.ip)
+    }
+
+    /// The instruction that the next [`step()`][VM::step] will execute, or
+    /// `None` if nothing is running.
+    pub fn current_opcode(&self) -> Option<Opcode> {
+        self.frames
+            .last()
+            .map(|frame| Opcode::from(frame.closure.function.chunk.code[frame.ip]))
+    }
+
+    /// The VM's value stack, from the bottom up.
+    pub fn stack(&self) -> &[Value] {
+        &self.stack.stack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_through_instructions() {
+        let mut vm = VM::new();
+        vm.start("var a = 1 + 2\na * 2").unwrap();
+
+        let mut steps = vec![];
+
+        loop {
+            let opcode = vm.current_opcode().unwrap();
+            let ip = vm.instruction_pointer().unwrap();
+
+            match vm.step().unwrap() {
+                StepResult::Running => {
+                    // skip the script's own closure, which is always at the bottom.
+                    let stack: Vec<String> = vm.stack()[1..].iter().map(Value::repr).collect();
+                    steps.push((ip, opcode, stack));
+                }
+                StepResult::Finished(result) => {
+                    assert_eq!(result, Value::Nil);
+                    assert_eq!(opcode, Opcode::Return);
+                    break;
+                }
+            }
+        }
+
+        let opcodes: Vec<_> = steps.iter().map(|(_, op, _)| op).collect();
+        let stacks: Vec<_> = steps.iter().map(|(_, _, stack)| stack.join(" ")).collect();
+
+        assert_eq!(
+            opcodes,
+            [
+                &Opcode::One,
+                &Opcode::LoadConst,
+                &Opcode::Add,
+                &Opcode::DefGlobal,
+                &Opcode::LoadGlobal,
+                &Opcode::LoadConst,
+                &Opcode::Mul,
+                &Opcode::Del,
+                &Opcode::Nil,
+            ]
+        );
+        assert_eq!(stacks, ["1", "1 2", "3", "", "3", "3 2", "6", "", "nil"]);
+
+        // instruction pointers only move forward in straight-line code.
+        assert!(steps.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        assert!(vm.instruction_pointer().is_none());
+        assert!(vm.stack().is_empty());
+        assert!(vm.step().is_err());
+    }
+
+    #[test]
+    fn step_reports_runtime_errors() {
+        let mut vm = VM::new();
+        vm.set_stderr(std::io::sink());
+        vm.start("var a = true - 1").unwrap();
+
+        let result = loop {
+            match vm.step() {
+                Ok(StepResult::Running) => continue,
+                other => break other,
+            }
+        };
+
+        assert!(matches!(result, Err(RadishError::RuntimeError(_))));
+        assert!(vm.instruction_pointer().is_none());
+    }
+}
@@ -1,9 +1,28 @@
 use std::fmt::{self, Display, Formatter};
 
+use crate::common::span::Span;
+use crate::compiler::error::position;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Trace {
     pub message: String,
-    pub calls: Vec<String>, // TODO: different data type.
+    /// The calls that were in progress when the error happened, innermost
+    /// first.
+    pub calls: Vec<CallSite>,
+    /// The source of the instruction that failed, if it's known.
+    pub span: Option<Span>,
+}
+
+/// A function call that hadn't returned when an error happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSite {
+    /// The name of the function called, empty for an anonymous one. For the
+    /// top level code of an imported module, the module's name.
+    pub function: String,
+    /// Whether the call ran the top level code of an imported module.
+    pub module: bool,
+    /// The call expression, or the import statement, if it's known.
+    pub span: Option<Span>,
 }
 
 impl Trace {
@@ -11,23 +30,51 @@ impl Trace {
         Trace {
             message: message.to_string(),
             calls: vec![],
+            span: None,
         }
     }
 
-    pub fn add_context(&mut self, ctx: String) {
-        self.calls.push(ctx);
+    pub fn add_call(&mut self, function: String, span: Option<Span>) {
+        self.calls.push(CallSite {
+            function,
+            module: false,
+            span,
+        });
+    }
+
+    /// Add the top level code of the module `name`, imported at `span`.
+    pub fn add_import(&mut self, name: String, span: Option<Span>) {
+        self.calls.push(CallSite {
+            function: name,
+            module: true,
+            span,
+        });
     }
 }
 
 impl Display for Trace {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}", self.message)
+        writeln!(f, "{}", self.message)?;
 
-        // TODO: print backtrace
-        //for call in self.calls.iter() {
-        //    writeln!(f, "    In {}", call.to_string())?;
-        //};
+        if let Some(span) = &self.span {
+            writeln!(f, "    at {}", position(span))?;
+        }
+
+        for call in &self.calls {
+            match call.function.as_str() {
+                name if call.module => write!(f, "    in module `{}`", name)?,
+                "" => write!(f, "    in an anonymous function")?,
+                name => write!(f, "    in `{}`", name)?,
+            }
+
+            let verb = if call.module { "imported" } else { "called" };
+
+            match &call.span {
+                Some(span) => writeln!(f, ", {} at {}", verb, position(span))?,
+                None => writeln!(f)?,
+            }
+        }
 
-        //write!(f, "")
+        Ok(())
     }
 }
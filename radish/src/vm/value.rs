@@ -1,4 +1,6 @@
 use crate::common::{Chunk, Module};
+use crate::compiler::ast::Op;
+use crate::config::IntDivision;
 use crate::vm::native::NativeFunction;
 use crate::VM;
 use std::cell::RefCell;
@@ -11,9 +13,77 @@ use std::rc::{Rc, Weak};
 use super::stack::Stack;
 use super::CallFrame;
 
-#[derive(Debug, PartialEq/*, PartialOrd*/)]
+/// The longest string, in bytes, that repeating a string may produce.
+pub const MAX_REPEAT_LENGTH: usize = 1 << 30;
+
+/// An error produced by an operation on values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueError {
+    /// The operator isn't defined for the operands' types.
+    UnsupportedOperands {
+        op: Op,
+        lhs: &'static str,
+        rhs: &'static str,
+    },
+    /// The unary operator isn't defined for the operand's type.
+    UnsupportedOperand { op: Op, operand: &'static str },
+    /// The operand types are fine, but their values aren't.
+    Invalid(String),
+    /// A value of one type was needed, but a value of another was given.
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+    /// The values have no order relative to each other.
+    Incomparable {
+        lhs: &'static str,
+        rhs: &'static str,
+    },
+}
+
+impl ValueError {
+    fn unsupported(op: &Op, lhs: &Value, rhs: &Value) -> ValueError {
+        ValueError::UnsupportedOperands {
+            op: op.clone(),
+            lhs: lhs.type_name(),
+            rhs: rhs.type_name(),
+        }
+    }
+
+    fn mismatch(expected: &'static str, actual: &Value) -> ValueError {
+        ValueError::TypeMismatch {
+            expected,
+            actual: actual.type_name(),
+        }
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::UnsupportedOperands { op, lhs, rhs } => {
+                write!(f, "cannot apply `{}` to {} and {}", op, lhs, rhs)
+            }
+            ValueError::UnsupportedOperand { op, operand } => {
+                write!(f, "cannot apply `{}` to {}", op, operand)
+            }
+            ValueError::Invalid(msg) => f.write_str(msg),
+            ValueError::TypeMismatch { expected, actual } => {
+                write!(f, "expected {}, got {}", expected, actual)
+            }
+            ValueError::Incomparable { lhs, rhs } => {
+                write!(f, "cannot order values of type {} and {}", lhs, rhs)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
 pub enum Value {
     Number(f64),
+    /// A whole number written without a fraction or exponent, such as `3`.
+    /// See [`Value::binary_op`] for how it mixes with `Number`.
+    Integer(i64),
     Boolean(bool),
     String(Rc<RefCell<String>>),
     Function(Rc<Function>),
@@ -24,6 +94,8 @@ pub enum Value {
     NativeFunction(Rc<NativeFunction>),
     Array(Rc<RefCell<Vec<Value>>>),
     Map(Rc<RefCell<HashMap<String, Value>>>),
+    Range(Rc<Range>),
+    Bytes(Rc<RefCell<Vec<u8>>>),
     Nil,
 }
 
@@ -67,6 +139,407 @@ impl Value {
             _ => Err("expected a module".to_string()),
         }
     }
+
+    /// Repeat a string `count` times, returning a new string. The count must be
+    /// a non-negative integer, and the result at most [`MAX_REPEAT_LENGTH`]
+    /// bytes long.
+    pub fn repeat(&self, count: f64) -> Result<Value, ValueError> {
+        let invalid = |msg: &str| Err(ValueError::Invalid(msg.to_string()));
+
+        let string = match self {
+            Value::String(string) => string.borrow(),
+            _ => return invalid("only strings can be repeated"),
+        };
+
+        if count.fract() != 0.0 {
+            return invalid("cannot repeat a string a non-integer number of times");
+        }
+
+        if count < 0.0 {
+            return invalid("cannot repeat a string a negative number of times");
+        }
+
+        // a count too large for a usize saturates, and so is too large anyway.
+        match string.len().checked_mul(count as usize) {
+            Some(len) if len <= MAX_REPEAT_LENGTH => Ok(Value::from(&string.repeat(count as usize))),
+            _ => Err(ValueError::Invalid(format!(
+                "cannot repeat a string to more than {} bytes",
+                MAX_REPEAT_LENGTH
+            ))),
+        }
+    }
+
+    /// The value of a number, whether it's an integer or a float.
+    pub fn as_number(&self) -> Result<f64, ValueError> {
+        match self {
+            Value::Number(num) => Ok(*num),
+            Value::Integer(num) => Ok(*num as f64),
+            _ => Err(ValueError::mismatch("number", self)),
+        }
+    }
+
+    /// Whether the value counts as true in a condition. Only `nil` and
+    /// `false` are falsey.
+    pub fn truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Boolean(false))
+    }
+
+    /// Order two values for sorting. Numbers are ordered numerically, whether
+    /// they're integers or floats, with NaN after every other number. Strings
+    /// are ordered by their characters' code points, `false` comes before
+    /// `true` and `nil` is equal to itself. Values of other types, or of two
+    /// different types, can't be ordered.
+    pub fn total_cmp(&self, other: &Value) -> Result<Ordering, ValueError> {
+        match (self, other) {
+            (Value::Integer(_) | Value::Number(_), Value::Integer(_) | Value::Number(_)) => {
+                let is_nan = |value: &Value| matches!(value, Value::Number(n) if n.is_nan());
+
+                // only NaN makes numbers unordered.
+                Ok(self
+                    .partial_cmp(other)
+                    .unwrap_or_else(|| is_nan(self).cmp(&is_nan(other))))
+            }
+            (Value::String(a), Value::String(b)) => Ok(compare_strings(a, b)),
+            (Value::Boolean(a), Value::Boolean(b)) => Ok(a.cmp(b)),
+            (Value::Nil, Value::Nil) => Ok(Ordering::Equal),
+            _ => Err(ValueError::Incomparable {
+                lhs: self.type_name(),
+                rhs: other.type_name(),
+            }),
+        }
+    }
+
+    /// The value of a boolean. Other values aren't converted by truthiness.
+    pub fn as_bool(&self) -> Result<bool, ValueError> {
+        match self {
+            Value::Boolean(val) => Ok(*val),
+            _ => Err(ValueError::mismatch("boolean", self)),
+        }
+    }
+
+    /// A copy of a string's contents.
+    pub fn as_string(&self) -> Result<String, ValueError> {
+        match self {
+            Value::String(string) => Ok(string.borrow().clone()),
+            _ => Err(ValueError::mismatch("string", self)),
+        }
+    }
+
+    /// The elements of an array. They're shared with the value, so changes
+    /// made through one are seen by the other.
+    pub fn as_list(&self) -> Result<Rc<RefCell<Vec<Value>>>, ValueError> {
+        match self {
+            Value::Array(array) => Ok(Rc::clone(array)),
+            _ => Err(ValueError::mismatch("array", self)),
+        }
+    }
+
+    /// The name of this value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) | Value::Integer(_) => "number",
+            Value::Boolean(_) => "boolean",
+            Value::String(_) => "string",
+            Value::Function(_) | Value::Closure(_) | Value::NativeFunction(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::Module(_) => "module",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Range(_) => "range",
+            Value::Bytes(_) => "bytes",
+            Value::Nil => "nil",
+        }
+    }
+
+    /// Whether the value is a reference to shared data. Cloning one only bumps
+    /// a reference count, and the clone sees any changes made through the
+    /// original. Numbers, booleans and `nil` are plain values instead.
+    pub fn is_reference_type(&self) -> bool {
+        match self {
+            Value::Number(_) | Value::Integer(_) | Value::Boolean(_) | Value::Nil => false,
+            Value::String(_)
+            | Value::Function(_)
+            | Value::Closure(_)
+            | Value::Class(_)
+            | Value::Instance(_)
+            | Value::Module(_)
+            | Value::NativeFunction(_)
+            | Value::Array(_)
+            | Value::Map(_)
+            | Value::Range(_)
+            | Value::Bytes(_) => true,
+        }
+    }
+
+    /// Parse a string as a number. Values other than strings are returned
+    /// unchanged.
+    pub fn coerce_numeric_string(self) -> Result<Value, String> {
+        let number = match &self {
+            Value::String(string) => string.borrow().trim().parse::<f64>(),
+            _ => return Ok(self),
+        };
+
+        number
+            .map(Value::Number)
+            .map_err(|_| format!("cannot convert {} to a number", self.repr()))
+    }
+
+    /// Step through an iterable value. Returns the item found at `position`
+    /// along with the position of the item after it, or `None` once there are
+    /// no items left. Arrays yield their elements, ranges their numbers and
+    /// strings their characters, with a string's position being a byte offset.
+    pub fn iter_next(&self, position: usize) -> Result<Option<(Value, usize)>, String> {
+        match self {
+            Value::Array(array) => Ok(array
+                .borrow()
+                .get(position)
+                .map(|item| (item.clone(), position + 1))),
+            Value::String(string) => Ok(string
+                .borrow()
+                .get(position..)
+                .and_then(|rest| rest.chars().next())
+                .map(|ch| (Value::from(&ch.to_string()), position + ch.len_utf8()))),
+            Value::Range(range) => Ok(range
+                .get(position)
+                .map(|n| (Value::Integer(n), position + 1))),
+            Value::Bytes(bytes) => Ok(bytes
+                .borrow()
+                .get(position)
+                .map(|byte| (Value::Integer(*byte as i64), position + 1))),
+            _ => Err(format!("value of type {} is not iterable", self.type_name())),
+        }
+    }
+
+    /// Apply the binary operator `op` to `lhs` and `rhs`. This is the single
+    /// place that decides which operand types each operator supports.
+    ///
+    /// Arithmetic on two integers stays an integer, except for `/`, which
+    /// gives a float so that `7 / 2` is `3.5`. An integer result that would
+    /// overflow is an error, as is dividing an integer, or taking its
+    /// remainder, by the integer `0`. When only one operand is an integer
+    /// it's promoted to a float. Comparisons between the two types go
+    /// by mathematical value, so `1 == 1.0`.
+    pub fn binary_op(op: &Op, lhs: &Value, rhs: &Value) -> Result<Value, ValueError> {
+        Value::binary_op_with_division(op, lhs, rhs, IntDivision::default())
+    }
+
+    /// Apply the binary operator `op` like [`binary_op()`](Value::binary_op),
+    /// dividing two integers as `int_division` says. Dividing an integer by
+    /// the integer `0` is an error in every mode.
+    pub fn binary_op_with_division(
+        op: &Op,
+        lhs: &Value,
+        rhs: &Value,
+        int_division: IntDivision,
+    ) -> Result<Value, ValueError> {
+        let result = match (op, lhs, rhs) {
+            (Op::EqualsTo, _, _) => Value::Boolean(lhs.equals(rhs)),
+            (Op::NotEqual, _, _) => Value::Boolean(!lhs.equals(rhs)),
+            (Op::Add, Value::String(a), Value::String(b)) => {
+                Value::from(&format!("{}{}", a.borrow(), b.borrow()))
+            }
+            (Op::Multiply, string @ Value::String(_), Value::Number(count))
+            | (Op::Multiply, Value::Number(count), string @ Value::String(_)) => {
+                return string.repeat(*count)
+            }
+            (Op::Multiply, string @ Value::String(_), Value::Integer(count))
+            | (Op::Multiply, Value::Integer(count), string @ Value::String(_)) => {
+                return string.repeat(*count as f64)
+            }
+            (Op::Divide | Op::Remainder, Value::Integer(_), Value::Integer(0)) => {
+                return Err(ValueError::Invalid("division by zero".to_string()))
+            }
+            (_, Value::Integer(a), Value::Integer(b)) => {
+                let (a, b) = (*a, *b);
+
+                let exact = match op {
+                    Op::Divide if int_division == IntDivision::Floor => a.checked_div(b),
+                    Op::Add => a.checked_add(b),
+                    Op::Subtract => a.checked_sub(b),
+                    Op::Multiply => a.checked_mul(b),
+                    // `i64::MIN % -1` only overflows in the division.
+                    Op::Remainder => Some(a.wrapping_rem(b)),
+                    Op::LessThan => return Ok(Value::Boolean(a < b)),
+                    Op::LessThanEquals => return Ok(Value::Boolean(a <= b)),
+                    Op::GreaterThan => return Ok(Value::Boolean(a > b)),
+                    Op::GreaterThanEquals => return Ok(Value::Boolean(a >= b)),
+                    _ => {
+                        return Value::binary_op(op, &Value::Number(a as f64), &Value::Number(b as f64))
+                    }
+                };
+
+                match exact {
+                    Some(result) => Value::Integer(result),
+                    None => {
+                        return Err(ValueError::Invalid(format!(
+                            "the result of {} {} {} is too large",
+                            a, op, b
+                        )))
+                    }
+                }
+            }
+            (
+                Op::LessThan | Op::LessThanEquals | Op::GreaterThan | Op::GreaterThanEquals,
+                Value::Integer(_) | Value::Number(_),
+                Value::Integer(_) | Value::Number(_),
+            ) => {
+                let ordering = lhs.partial_cmp(rhs);
+
+                Value::Boolean(match op {
+                    Op::LessThan => ordering == Some(Ordering::Less),
+                    Op::LessThanEquals => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    Op::GreaterThan => ordering == Some(Ordering::Greater),
+                    _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                })
+            }
+            (_, Value::Integer(a), Value::Number(_)) => {
+                return Value::binary_op(op, &Value::Number(*a as f64), rhs)
+            }
+            (_, Value::Number(_), Value::Integer(b)) => {
+                return Value::binary_op(op, lhs, &Value::Number(*b as f64))
+            }
+            (_, Value::Number(a), Value::Number(b)) => match op {
+                Op::Add => Value::Number(a + b),
+                Op::Subtract => Value::Number(a - b),
+                Op::Multiply => Value::Number(a * b),
+                Op::Divide => Value::Number(a / b),
+                Op::Remainder => Value::Number(a % b),
+                Op::LessThan => Value::Boolean(a < b),
+                Op::LessThanEquals => Value::Boolean(a <= b),
+                Op::GreaterThan => Value::Boolean(a > b),
+                Op::GreaterThanEquals => Value::Boolean(a >= b),
+                _ => return Err(ValueError::unsupported(op, lhs, rhs)),
+            },
+            _ => return Err(ValueError::unsupported(op, lhs, rhs)),
+        };
+
+        Ok(result)
+    }
+
+    /// Apply the unary operator `op` to `operand`: `-` negates a number and
+    /// `!` inverts a boolean. Negating the smallest integer is an error, like
+    /// other integer overflow.
+    pub fn unary_op(op: &Op, operand: &Value) -> Result<Value, ValueError> {
+        match (op, operand) {
+            (Op::Subtract, Value::Number(val)) => Ok(Value::Number(-val)),
+            (Op::Subtract, Value::Integer(val)) => match val.checked_neg() {
+                Some(negated) => Ok(Value::Integer(negated)),
+                None => Err(ValueError::Invalid(format!("the negation of {} is too large", val))),
+            },
+            (Op::Bang, Value::Boolean(val)) => Ok(Value::Boolean(!val)),
+            _ => Err(ValueError::UnsupportedOperand {
+                op: op.clone(),
+                operand: operand.type_name(),
+            }),
+        }
+    }
+
+    /// Equality as seen by the `==` and `!=` operators. Numbers follow IEEE 754
+    /// semantics, so `NaN` is never equal to anything, including itself.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Integer(_), Value::Number(_)) | (Value::Number(_), Value::Integer(_)) => {
+                self.partial_cmp(other) == Some(Ordering::Equal)
+            }
+            _ => self == other,
+        }
+    }
+
+    /// The key this value is stored under when used to index a map.
+    ///
+    /// Unlike [`Value::equals`], keys compare by their exact representation,
+    /// which is a total equality: a `NaN` key always finds the entry it was
+    /// stored under.
+    pub fn map_key(&self) -> String {
+        self.repr()
+    }
+
+    /// Serialize the value to JSON.
+    ///
+    /// Arrays, ranges and bytes become JSON arrays and maps become objects with their
+    /// keys sorted. Numbers JSON can't represent (`NaN` and the infinities)
+    /// become `null`. Functions, classes, instances and modules have no JSON
+    /// form and are an error, as are maps with keys that aren't strings.
+    pub fn to_json(&self) -> Result<String, ValueError> {
+        use crate::compiler::json;
+
+        let json = match self {
+            Value::Number(num) if *num == 0.0 => "0".to_string(),
+            Value::Number(num) => json::number(*num),
+            Value::Integer(num) => num.to_string(),
+            Value::Boolean(val) => val.to_string(),
+            Value::String(val) => json::string(&val.borrow()),
+            Value::Nil => "null".to_string(),
+            Value::Array(arr) => {
+                let elements = arr
+                    .borrow()
+                    .iter()
+                    .map(Value::to_json)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                format!("[{}]", elements.join(","))
+            }
+            Value::Range(range) => {
+                let elements: Vec<String> = (0..range.len())
+                    .filter_map(|index| range.get(index))
+                    .map(|num| num.to_string())
+                    .collect();
+
+                format!("[{}]", elements.join(","))
+            }
+            Value::Bytes(bytes) => {
+                let elements: Vec<String> =
+                    bytes.borrow().iter().map(|byte| byte.to_string()).collect();
+
+                format!("[{}]", elements.join(","))
+            }
+            Value::Map(map) => {
+                let map = map.borrow();
+                let mut entries = Vec::with_capacity(map.len());
+
+                for (key, value) in map.iter() {
+                    // keys are stored as their `repr()`, so string keys are quoted.
+                    let key = key
+                        .strip_prefix('"')
+                        .and_then(|key| key.strip_suffix('"'))
+                        .ok_or_else(|| {
+                            ValueError::Invalid(format!("cannot convert map key {} to JSON", key))
+                        })?;
+
+                    entries.push((key, value.to_json()?));
+                }
+
+                entries.sort();
+
+                let entries: Vec<String> = entries
+                    .into_iter()
+                    .map(|(key, value)| format!("{}:{}", json::string(key), value))
+                    .collect();
+
+                format!("{{{}}}", entries.join(","))
+            }
+            _ => {
+                return Err(ValueError::Invalid(format!(
+                    "cannot convert a {} to JSON",
+                    self.type_name()
+                )))
+            }
+        };
+
+        Ok(json)
+    }
+
+    /// The debug representation of this value, as shown when echoed by the
+    /// REPL or printed inside an array or map. Unlike `Display`, strings are
+    /// wrapped in quotes.
+    pub fn repr(&self) -> String {
+        match self {
+            Value::String(val) => format!("\"{}\"", val.borrow()),
+            _ => self.to_string(),
+        }
+    }
 }
 
 impl From<f64> for Value {
@@ -75,6 +548,12 @@ impl From<f64> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(num: i64) -> Self {
+        Value::Integer(num)
+    }
+}
+
 impl From<bool> for Value {
     fn from(val: bool) -> Self {
         Value::Boolean(val)
@@ -105,6 +584,12 @@ impl From<Class> for Value {
     }
 }
 
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Bytes(Rc::new(RefCell::new(bytes)))
+    }
+}
+
 impl Clone for Value {
     fn clone(&self) -> Value {
         match self {
@@ -113,6 +598,7 @@ impl Clone for Value {
             Self::Nil => Self::Nil,
             Self::Boolean(val) => Self::Boolean(*val),
             Self::Number(val) => Self::Number(*val),
+            Self::Integer(val) => Self::Integer(*val),
             Self::String(val) => Self::String(Rc::clone(val)),
             Self::Class(val) => Self::Class(Rc::clone(val)),
             Self::Instance(inst) => Self::Instance(Rc::clone(inst)),
@@ -120,19 +606,35 @@ impl Clone for Value {
             Self::NativeFunction(val) => Self::NativeFunction(Rc::clone(val)),
             Self::Array(arr) => Self::Array(Rc::clone(arr)),
             Self::Map(obj) => Self::Map(Rc::clone(obj)),
+            Self::Range(range) => Self::Range(Rc::clone(range)),
+            Self::Bytes(bytes) => Self::Bytes(Rc::clone(bytes)),
         }
     }
 }
 
+/// Numbers are written in the shortest decimal form that parses back to the
+/// same `f64`, so `print` never loses precision: `0.1 + 0.2` shows as
+/// `0.30000000000000004`. There is a single number type, so whole numbers are
+/// written without a fraction (`2.0` is `2`), and `-0` is written as `0`.
+/// Magnitudes from `1e21` up and below `1e-7` use scientific notation, such as
+/// `1e308`, and the special values are `inf`, `-inf` and `nan`.
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            // negative zero prints as `0`, like it compares.
+            Value::Number(num) if *num == 0.0 => f.write_str("0"),
+            Value::Number(num) if num.is_nan() => f.write_str("nan"),
+            Value::Number(num) if num.is_infinite() => {
+                f.write_str(if *num > 0.0 { "inf" } else { "-inf" })
+            }
+            Value::Number(num) if num.abs() >= 1e21 || num.abs() < 1e-7 => write!(f, "{:e}", num),
             Value::Number(num) => f.write_str(&num.to_string()),
+            Value::Integer(num) => write!(f, "{}", num),
             Value::Boolean(false) => f.write_str("false"),
             Value::Boolean(true) => f.write_str("true"),
-            Value::String(val) => f.write_str(&format!("\"{}\"", val.borrow())),
-            Value::Function(val) => write!(f, "<fun {}>", val.format_name()),
-            Value::Closure(val) => write!(f, "<fun {}>", val.function.format_name()),
+            Value::String(val) => f.write_str(&val.borrow()),
+            Value::Function(val) => write!(f, "{}", val),
+            Value::Closure(val) => write!(f, "{}", val.function),
             Value::Class(val) => write!(f, "<class {}>", val.name.borrow()),
             Value::Instance(val) => write!(f, "<{:?} instance>", val.class.name.borrow()),
             Value::Module(module) => write!(f, "<mod {}>", module.borrow().name),
@@ -142,7 +644,7 @@ impl fmt::Display for Value {
 
                 for (index, element) in arr.borrow().iter().enumerate() {
                     let end = if index == arr.borrow().len() - 1 { "" } else { ", " };
-                    write!(f, "{}{}", element, end)?;
+                    write!(f, "{}{}", element.repr(), end)?;
                 }
 
                 write!(f, "]")
@@ -152,100 +654,214 @@ impl fmt::Display for Value {
 
                 for (index, (key, value)) in obj.borrow().iter().enumerate() {
                     let end = if index == obj.borrow().len() - 1 { "" } else { ", " };
-                    write!(f, "{}: {}{}", key, value, end)?;
+                    write!(f, "{}: {}{}", key, value.repr(), end)?;
                 }
 
                 write!(f, "}}")
             }
+            Value::Range(range) => write!(f, "{}", range),
+            Value::Bytes(bytes) => write!(f, "b\"{}\"", escape_bytes(&bytes.borrow())),
             Value::Nil => f.write_str("nil"),
         }
     }
 }
 
+/// Write `bytes` the way they appear between the quotes of a `b"..."`
+/// literal: printable ASCII as is, anything else as a `\xNN` escape.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+
+    for &byte in bytes {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' '..=b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+
+    out
+}
+
 impl Add for Value {
     type Output = Self;
     fn add(self, other: Value) -> <Self as std::ops::Add<Value>>::Output {
-        match (self, other) {
-            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
-            (Value::String(a), Value::String(b)) => {
-                // FIXME: I believe this doesn't work. Need to look into it.
-                a.borrow_mut().push_str(&b.borrow());
-                Value::String(a)
-            }
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::binary_op(&Op::Add, &self, &other).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Sub for Value {
     type Output = Self;
     fn sub(self, other: Value) -> <Self as std::ops::Sub<Value>>::Output {
-        match (self, other) {
-            (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::binary_op(&Op::Subtract, &self, &other).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Mul for Value {
     type Output = Self;
     fn mul(self, other: Value) -> <Self as std::ops::Mul<Value>>::Output {
-        match (self, other) {
-            (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::binary_op(&Op::Multiply, &self, &other).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Div for Value {
     type Output = Self;
     fn div(self, other: Value) -> <Self as std::ops::Div<Value>>::Output {
-        match (self, other) {
-            (Value::Number(a), Value::Number(b)) => Value::Number(a / b),
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::binary_op(&Op::Divide, &self, &other).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Rem for Value {
     type Output = Self;
     fn rem(self, other: Value) -> <Self as std::ops::Rem<Value>>::Output {
-        match (self, other) {
-            (Value::Number(a), Value::Number(b)) => Value::Number(a % b),
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::binary_op(&Op::Remainder, &self, &other).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Neg for Value {
     type Output = Self;
     fn neg(self) -> Self::Output {
-        match self {
-            Value::Number(val) => Value::Number(-val),
-            _ => panic!("Operands must be numbers"),
-        }
+        Value::unary_op(&Op::Subtract, &self).unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
 impl Not for Value {
     type Output = Self;
     fn not(self) -> Self::Output {
-        match self {
-            Value::Boolean(val) => Value::Boolean(!val),
-            _ => panic!("Operand must be boolean"),
+        Value::unary_op(&Op::Bang, &self).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::String(a), Value::String(b)) => compare_strings(a, b) == Ordering::Equal,
+            (Value::Function(a), Value::Function(b)) => a == b,
+            (Value::Closure(a), Value::Closure(b)) => a == b,
+            (Value::Class(a), Value::Class(b)) => a == b,
+            (Value::Instance(a), Value::Instance(b)) => a == b,
+            (Value::Module(a), Value::Module(b)) => a == b,
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::Range(a), Value::Range(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            _ => false,
         }
     }
 }
 
+/// Compare two strings by their contents. A string is equal to itself
+/// without being borrowed, so that comparing it to itself can't panic while
+/// it's borrowed mutably somewhere else. Other strings are only ever
+/// borrowed immutably.
+fn compare_strings(a: &Rc<RefCell<String>>, b: &Rc<RefCell<String>>) -> Ordering {
+    if Rc::ptr_eq(a, b) {
+        return Ordering::Equal;
+    }
+
+    a.borrow().as_str().cmp(b.borrow().as_str())
+}
+
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
             (Value::Number(val1), Value::Number(val2)) => val1.partial_cmp(val2),
+            (Value::Integer(val1), Value::Integer(val2)) => Some(val1.cmp(val2)),
+            (Value::Integer(int), Value::Number(float)) => compare_mixed(*int, *float),
+            (Value::Number(float), Value::Integer(int)) => {
+                compare_mixed(*int, *float).map(Ordering::reverse)
+            }
             _ => None,
         }
     }
 }
 
+/// Compare an integer with a float exactly. Converting the integer to a float
+/// could round it, making e.g. `2^53 + 1` equal to `2^53`.
+fn compare_mixed(int: i64, float: f64) -> Option<Ordering> {
+    // every i64 is within [-2^63, 2^63).
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    if float.is_nan() {
+        None
+    } else if float >= LIMIT {
+        Some(Ordering::Less)
+    } else if float < -LIMIT {
+        Some(Ordering::Greater)
+    } else {
+        let whole = float.trunc();
+
+        match int.cmp(&(whole as i64)) {
+            Ordering::Equal => 0.0.partial_cmp(&(float - whole)),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// The integers from `start` up to, but not including, `end`, counting by
+/// `step`. The numbers are computed as they're needed rather than stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+}
+
+impl Range {
+    /// Create a range. `step` must not be zero.
+    pub fn new(start: i64, end: i64, step: i64) -> Range {
+        debug_assert!(step != 0, "range step cannot be zero");
+
+        Range { start, end, step }
+    }
+
+    /// The number of integers in the range.
+    pub fn len(&self) -> usize {
+        // widen so that ranges spanning most of i64 don't overflow.
+        let (start, end, step) = (self.start as i128, self.end as i128, self.step as i128);
+
+        let (distance, step) = if step > 0 {
+            (end - start, step)
+        } else {
+            (start - end, -step)
+        };
+
+        if distance <= 0 {
+            0
+        } else {
+            ((distance + step - 1) / step) as usize
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The integer at `index`, if it's within the range.
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if index < self.len() {
+            Some((self.start as i128 + index as i128 * self.step as i128) as i64)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.step == 1 {
+            write!(f, "range({}, {})", self.start, self.end)
+        } else {
+            write!(f, "range({}, {}, {})", self.start, self.end, self.step)
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub arity: u8,
@@ -268,11 +884,19 @@ impl Function {
         if self.name.is_empty() {
             "script"
         } else {
-            &*self.name
+            &self.name
         }
     }
 }
 
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = if self.name.is_empty() { "anonymous" } else { &self.name };
+
+        write!(f, "<fun {}/{}>", name, self.arity)
+    }
+}
+
 impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
@@ -299,6 +923,10 @@ pub struct Closure {
     /// This closure's function.
     pub function: Rc<Function>,
     /// Store 'non-locals'.
+    ///
+    /// Non-locals are captured by value when the closure is created. Assigning
+    /// to one changes the closure's own copy, which is kept between calls, but
+    /// isn't seen by the enclosing function or by other closures.
     pub non_locals: RefCell<Vec<UpValue>>,
 }
 
@@ -369,7 +997,7 @@ impl UpValue {
     /// Get this upvalue's inner value.
     pub fn inner(&self, vm: &VM) -> Value {
         if let Some(val) = &self.closed {
-            return val.clone();
+            val.clone()
         } else if let Some(index) = &self.location {
             let val = match index {
                 UpValueLocation::StackIndex(idx) => vm.stack.get(*idx).clone(),
@@ -380,8 +1008,7 @@ impl UpValue {
 
                     let upval = &vm.frames[vm.frame_count - 1].closure.non_locals.borrow()[*idx];
 
-                    let val = upval.inner(vm);
-                    val
+                    upval.inner(vm)
                 }
             };
 
@@ -510,7 +1137,7 @@ pub struct ValueMap {
 }
 
 impl ValueMap {
-    pub fn iter(&self) -> hash_map::Iter<String, Value> {
+    pub fn iter(&self) -> hash_map::Iter<'_, String, Value> {
         self.inner.iter()
     }
 }
@@ -523,7 +1150,11 @@ impl PartialOrd for ValueMap {
 
 #[cfg(test)]
 mod tests {
-    use super::Value;
+    use super::{Function, HashMap, IntDivision, Op, Ordering, Range, Value, ValueError, MAX_REPEAT_LENGTH};
+    use std::{
+        cell::RefCell,
+        rc::{Rc, Weak},
+    };
 
     #[test]
     fn test_size() {
@@ -532,4 +1163,549 @@ mod tests {
             16,
         };
     }
+
+    fn function(name: &str, arity: u8) -> Value {
+        let mut fun = Function::new(name, Weak::new());
+        fun.arity = arity;
+
+        Value::from(fun)
+    }
+
+    #[test]
+    fn display_function_with_arity() {
+        assert_eq!(function("add", 2).to_string(), "<fun add/2>");
+    }
+
+    #[test]
+    fn display_anonymous_function() {
+        assert_eq!(function("", 1).to_string(), "<fun anonymous/1>");
+    }
+
+    #[test]
+    fn total_cmp_within_types() {
+        use std::cmp::Ordering::*;
+
+        let cmp = |a: Value, b: Value| a.total_cmp(&b).unwrap();
+
+        assert_eq!(cmp(Value::Integer(2), Value::Number(2.5)), Less);
+        assert_eq!(cmp(Value::Number(2.0), Value::Integer(2)), Equal);
+        assert_eq!(cmp(Value::Number(f64::NAN), Value::Integer(1)), Greater);
+        assert_eq!(cmp(Value::Number(f64::NAN), Value::Number(f64::NAN)), Equal);
+        assert_eq!(cmp(Value::from("b"), Value::from("ab")), Greater);
+        assert_eq!(cmp(Value::Boolean(false), Value::Boolean(true)), Less);
+        assert_eq!(cmp(Value::Nil, Value::Nil), Equal);
+    }
+
+    #[test]
+    fn total_cmp_across_types() {
+        assert_eq!(
+            Value::Integer(1).total_cmp(&Value::from("1")),
+            Err(ValueError::Incomparable { lhs: "number", rhs: "string" })
+        );
+
+        let array = Value::Array(Rc::new(RefCell::new(vec![])));
+        assert!(array.total_cmp(&array).is_err());
+    }
+
+    #[test]
+    fn string_compares_to_itself_while_borrowed() {
+        let string = Rc::new(RefCell::new("radish".to_string()));
+        let value = Value::String(Rc::clone(&string));
+        let same = Value::String(Rc::clone(&string));
+
+        let _writing = string.borrow_mut();
+
+        assert_eq!(value, same);
+        assert!(value.equals(&same));
+        assert_eq!(value.total_cmp(&same), Ok(Ordering::Equal));
+    }
+
+    #[test]
+    fn strings_compare_by_contents() {
+        assert_eq!(Value::from("a"), Value::from("a"));
+        assert_ne!(Value::from("a"), Value::from("b"));
+        assert_eq!(Value::from("a").total_cmp(&Value::from("b")), Ok(Ordering::Less));
+    }
+
+    #[test]
+    fn nan_is_not_equal_to_itself() {
+        let nan = Value::Number(f64::NAN);
+
+        assert!(!nan.equals(&nan));
+        assert!(Value::Number(1.0).equals(&Value::Number(1.0)));
+    }
+
+    #[test]
+    fn nan_map_key_is_stable() {
+        assert_eq!(
+            Value::Number(f64::NAN).map_key(),
+            Value::Number(f64::NAN).map_key()
+        );
+    }
+
+    #[test]
+    fn typed_accessors() {
+        assert_eq!(Value::Number(1.5).as_number(), Ok(1.5));
+        assert_eq!(Value::Integer(2).as_number(), Ok(2.0));
+        assert_eq!(Value::Boolean(true).as_bool(), Ok(true));
+        assert_eq!(Value::from("hi").as_string(), Ok("hi".to_string()));
+
+        let array = Value::Array(Rc::new(RefCell::new(vec![Value::Integer(1)])));
+        array.as_list().unwrap().borrow_mut().push(Value::Nil);
+        assert_eq!(array.to_string(), "[1, nil]");
+    }
+
+    #[test]
+    fn typed_accessor_errors() {
+        let err = Value::Boolean(true).as_number().unwrap_err();
+        assert_eq!(err, ValueError::TypeMismatch { expected: "number", actual: "boolean" });
+        assert_eq!(err.to_string(), "expected number, got boolean");
+
+        assert_eq!(Value::Integer(1).as_bool().unwrap_err().to_string(), "expected boolean, got number");
+        assert_eq!(Value::Nil.as_string().unwrap_err().to_string(), "expected string, got nil");
+        assert_eq!(Value::from("a").as_list().unwrap_err().to_string(), "expected array, got string");
+    }
+
+    fn op(op: Op, lhs: Value, rhs: Value) -> Value {
+        Value::binary_op(&op, &lhs, &rhs).unwrap()
+    }
+
+    #[test]
+    fn integer_arithmetic_stays_integer() {
+        let int = Value::Integer;
+
+        assert_eq!(op(Op::Add, int(2), int(3)), int(5));
+        assert_eq!(op(Op::Subtract, int(2), int(3)), int(-1));
+        assert_eq!(op(Op::Multiply, int(4), int(3)), int(12));
+        assert_eq!(op(Op::Remainder, int(7), int(3)), int(1));
+        assert_eq!(op(Op::LessThan, int(2), int(3)), Value::Boolean(true));
+    }
+
+    #[test]
+    fn integer_division_gives_a_float() {
+        assert_eq!(op(Op::Divide, Value::Integer(7), Value::Integer(2)), Value::Number(3.5));
+        assert_eq!(op(Op::Divide, Value::Integer(6), Value::Integer(2)), Value::Number(3.0));
+        assert_eq!(
+            Value::binary_op(&Op::Remainder, &Value::Integer(1), &Value::Integer(0)),
+            Err(ValueError::Invalid("division by zero".to_string()))
+        );
+        assert!(matches!(op(Op::Remainder, Value::Number(1.0), Value::Integer(0)), Value::Number(n) if n.is_nan()));
+    }
+
+    #[test]
+    fn integer_division_modes() {
+        let divide = |a, b, mode| {
+            Value::binary_op_with_division(&Op::Divide, &Value::Integer(a), &Value::Integer(b), mode)
+        };
+
+        assert_eq!(divide(-7, 2, IntDivision::Float), Ok(Value::Number(-3.5)));
+        assert_eq!(divide(-7, 2, IntDivision::Floor), Ok(Value::Integer(-3)));
+
+        for mode in [IntDivision::Float, IntDivision::Floor] {
+            assert_eq!(divide(1, 0, mode), Err(ValueError::Invalid("division by zero".to_string())));
+        }
+    }
+
+    #[test]
+    fn integer_overflow_is_an_error() {
+        assert_eq!(
+            Value::binary_op(&Op::Add, &Value::Integer(i64::MAX), &Value::Integer(1)),
+            Err(ValueError::Invalid("the result of 9223372036854775807 + 1 is too large".to_string()))
+        );
+        assert!(Value::binary_op(&Op::Multiply, &Value::Integer(i64::MAX), &Value::Integer(2)).is_err());
+        assert!(Value::binary_op(&Op::Subtract, &Value::Integer(i64::MIN), &Value::Integer(1)).is_err());
+        assert_eq!(
+            Value::unary_op(&Op::Subtract, &Value::Integer(i64::MIN)),
+            Err(ValueError::Invalid("the negation of -9223372036854775808 is too large".to_string()))
+        );
+        assert_eq!(op(Op::Remainder, Value::Integer(i64::MIN), Value::Integer(-1)), Value::Integer(0));
+        assert_eq!(-Value::Integer(5), Value::Integer(-5));
+    }
+
+    #[test]
+    fn mixed_arithmetic_promotes_to_float() {
+        assert_eq!(op(Op::Add, Value::Integer(1), Value::Number(0.5)), Value::Number(1.5));
+        assert_eq!(op(Op::Subtract, Value::Number(0.5), Value::Integer(1)), Value::Number(-0.5));
+        assert_eq!(op(Op::Multiply, Value::Integer(2), Value::Number(2.0)), Value::Number(4.0));
+        assert_eq!(op(Op::Add, Value::Number(0.5), Value::Number(0.25)), Value::Number(0.75));
+    }
+
+    #[test]
+    fn integers_equal_floats_of_the_same_value() {
+        assert_eq!(op(Op::EqualsTo, Value::Integer(1), Value::Number(1.0)), Value::Boolean(true));
+        assert_eq!(op(Op::EqualsTo, Value::Number(1.0), Value::Integer(1)), Value::Boolean(true));
+        assert_eq!(op(Op::NotEqual, Value::Integer(1), Value::Number(1.5)), Value::Boolean(true));
+        assert!(!Value::Integer(0).equals(&Value::Number(f64::NAN)));
+        assert_eq!(Value::Integer(1).map_key(), Value::Number(1.0).map_key());
+
+        // 2^53 + 1 can't be represented as a float, so converting it would round.
+        let big = (1_i64 << 53) + 1;
+        assert!(!Value::Integer(big).equals(&Value::Number((1_i64 << 53) as f64)));
+        assert_eq!(
+            op(Op::GreaterThan, Value::Integer(big), Value::Number((1_i64 << 53) as f64)),
+            Value::Boolean(true)
+        );
+        assert_eq!(op(Op::LessThan, Value::Integer(-2), Value::Number(-1.5)), Value::Boolean(true));
+        assert_eq!(op(Op::LessThan, Value::Integer(i64::MAX), Value::Number(1e19)), Value::Boolean(true));
+        assert_eq!(op(Op::GreaterThanEquals, Value::Number(2.0), Value::Integer(2)), Value::Boolean(true));
+        assert_eq!(op(Op::LessThan, Value::Integer(1), Value::Number(f64::NAN)), Value::Boolean(false));
+    }
+
+    #[test]
+    fn repeat_string() {
+        assert_eq!(Value::from("ab") * Value::Number(3.0), Value::from("ababab"));
+        assert_eq!(Value::Number(3.0) * Value::from("ab"), Value::from("ababab"));
+    }
+
+    #[test]
+    fn repeat_string_zero_times() {
+        assert_eq!(Value::from("ab").repeat(0.0), Ok(Value::from("")));
+    }
+
+    #[test]
+    fn repeat_string_invalid_count() {
+        assert!(Value::from("ab").repeat(-1.0).is_err());
+        assert!(Value::from("ab").repeat(1.5).is_err());
+    }
+
+    #[test]
+    fn repeat_string_too_long() {
+        let err = Value::from("a").repeat(1e18).unwrap_err();
+        assert_eq!(err.to_string(), format!("cannot repeat a string to more than {} bytes", MAX_REPEAT_LENGTH));
+
+        // the length overflowing a usize is too long as well.
+        assert!(Value::from("ab").repeat(usize::MAX as f64).is_err());
+        assert!(Value::from("").repeat(1e18).is_ok());
+    }
+
+    #[test]
+    fn display_string_without_quotes() {
+        let value = Value::from("hi");
+
+        assert_eq!(value.to_string(), "hi");
+        assert_eq!(value.repr(), "\"hi\"");
+    }
+
+    #[test]
+    fn repr_elements_of_array() {
+        let value = Value::Array(Rc::new(RefCell::new(vec![
+            Value::from("a"),
+            Value::Number(1.0),
+        ])));
+
+        assert_eq!(value.to_string(), "[\"a\", 1]");
+        assert_eq!(value.repr(), "[\"a\", 1]");
+    }
+
+    #[test]
+    fn iterate_array() {
+        let value = Value::Array(Rc::new(RefCell::new(vec![Value::Number(1.0), Value::Number(2.0)])));
+
+        assert_eq!(value.iter_next(0), Ok(Some((Value::Number(1.0), 1))));
+        assert_eq!(value.iter_next(1), Ok(Some((Value::Number(2.0), 2))));
+        assert_eq!(value.iter_next(2), Ok(None));
+    }
+
+    #[test]
+    fn iterate_string_chars() {
+        let value = Value::from("é!");
+
+        assert_eq!(value.iter_next(0), Ok(Some((Value::from("é"), 2))));
+        assert_eq!(value.iter_next(2), Ok(Some((Value::from("!"), 3))));
+        assert_eq!(value.iter_next(3), Ok(None));
+    }
+
+    #[test]
+    fn iterate_bytes() {
+        let value = Value::from(b"a\xff".to_vec());
+
+        assert_eq!(value.iter_next(0), Ok(Some((Value::Integer(97), 1))));
+        assert_eq!(value.iter_next(1), Ok(Some((Value::Integer(255), 2))));
+        assert_eq!(value.iter_next(2), Ok(None));
+    }
+
+    #[test]
+    fn range_length() {
+        assert_eq!(Range::new(0, 10, 1).len(), 10);
+        assert_eq!(Range::new(0, 10, 3).len(), 4);
+        assert_eq!(Range::new(10, 0, -4).len(), 3);
+        assert_eq!(Range::new(5, 5, 1).len(), 0);
+        assert_eq!(Range::new(5, 0, 1).len(), 0);
+        assert_eq!(Range::new(0, i64::MAX, 1).len(), i64::MAX as usize);
+    }
+
+    #[test]
+    fn range_indexing() {
+        let range = Range::new(10, 0, -3);
+
+        assert_eq!(range.get(0), Some(10));
+        assert_eq!(range.get(3), Some(1));
+        assert_eq!(range.get(4), None);
+    }
+
+    #[test]
+    fn iterate_large_range() {
+        // the range is never materialized, so this doesn't allocate a
+        // billion numbers.
+        let value = Value::Range(Rc::new(Range::new(0, 1_000_000_000, 1)));
+
+        assert_eq!(value.iter_next(0), Ok(Some((Value::Integer(0), 1))));
+        assert_eq!(
+            value.iter_next(999_999_999),
+            Ok(Some((Value::Integer(999_999_999), 1_000_000_000)))
+        );
+        assert_eq!(value.iter_next(1_000_000_000), Ok(None));
+
+        let mut position = 0;
+        let mut sum = 0;
+
+        while let Ok(Some((Value::Integer(n), next))) = value.iter_next(position) {
+            if next > 100_000 {
+                break;
+            }
+
+            sum += n;
+            position = next;
+        }
+
+        assert_eq!(sum, 4_999_950_000);
+    }
+
+    #[test]
+    fn display_negative_numbers() {
+        assert_eq!(Value::Number(-5.0).to_string(), "-5");
+        assert_eq!(Value::Number(-5.5).to_string(), "-5.5");
+        assert_eq!(Value::Number(-0.0).to_string(), "0");
+        assert_eq!(Value::Number(-0.0).repr(), "0");
+    }
+
+    #[test]
+    fn display_numbers_round_trip() {
+        let round_trips = |num: f64| {
+            let shown = Value::Number(num).to_string();
+            let parsed: f64 = shown.parse().unwrap();
+            assert!(parsed == num, "{:?} is shown as {}", num, shown);
+        };
+
+        for &num in &[
+            0.1,
+            0.1 + 0.2,
+            1.0 / 3.0,
+            -2.5e-8,
+            1e21,
+            123456789012345680.0,
+            f64::EPSILON,
+            f64::MIN_POSITIVE,
+            5e-324,
+            f64::MAX,
+            f64::MIN,
+        ] {
+            round_trips(num);
+        }
+
+        // a fixed xorshift sequence of bit patterns, so failures reproduce.
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        for _ in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            let num = f64::from_bits(state);
+            if num.is_finite() {
+                round_trips(num);
+            }
+        }
+
+        assert_eq!(Value::Number(2.0).to_string(), "2");
+        assert_eq!(Value::Number(0.30000000000000004).to_string(), "0.30000000000000004");
+    }
+
+    #[test]
+    fn display_special_numbers() {
+        assert_eq!(Value::Number(f64::INFINITY).to_string(), "inf");
+        assert_eq!(Value::Number(f64::NEG_INFINITY).to_string(), "-inf");
+        assert_eq!(Value::Number(f64::NAN).to_string(), "nan");
+        assert_eq!(Value::Number(-f64::NAN).to_string(), "nan");
+        assert_eq!(Value::Number(f64::MAX * 2.0).repr(), "inf");
+    }
+
+    #[test]
+    fn display_scientific_numbers() {
+        assert_eq!(Value::Number(1e308).to_string(), "1e308");
+        assert_eq!(Value::Number(-1.5e21).to_string(), "-1.5e21");
+        assert_eq!(Value::Number(1e20).to_string(), "100000000000000000000");
+        assert_eq!(Value::Number(2.5e-8).to_string(), "2.5e-8");
+        assert_eq!(Value::Number(1e-7).to_string(), "0.0000001");
+        assert_eq!(Value::Number(5e-324).to_string(), "5e-324");
+    }
+
+    #[test]
+    fn display_range() {
+        assert_eq!(Value::Range(Rc::new(Range::new(0, 3, 1))).to_string(), "range(0, 3)");
+        assert_eq!(Value::Range(Rc::new(Range::new(3, 0, -1))).to_string(), "range(3, 0, -1)");
+    }
+
+    #[test]
+    fn iterate_non_iterable() {
+        assert_eq!(
+            Value::Number(5.0).iter_next(0),
+            Err("value of type number is not iterable".to_string())
+        );
+    }
+
+    #[test]
+    fn binary_op_arithmetic() {
+        let num = Value::Number;
+
+        assert_eq!(Value::binary_op(&Op::Add, &num(1.0), &num(2.0)), Ok(num(3.0)));
+        assert_eq!(Value::binary_op(&Op::Subtract, &num(1.0), &num(2.0)), Ok(num(-1.0)));
+        assert_eq!(Value::binary_op(&Op::Multiply, &num(3.0), &num(2.0)), Ok(num(6.0)));
+        assert_eq!(Value::binary_op(&Op::Divide, &num(3.0), &num(2.0)), Ok(num(1.5)));
+        assert_eq!(Value::binary_op(&Op::Remainder, &num(7.0), &num(4.0)), Ok(num(3.0)));
+        assert_eq!(
+            Value::binary_op(&Op::Add, &Value::from("a"), &Value::from("b")),
+            Ok(Value::from("ab"))
+        );
+        assert_eq!(
+            Value::binary_op(&Op::Multiply, &num(2.0), &Value::from("ab")),
+            Ok(Value::from("abab"))
+        );
+    }
+
+    #[test]
+    fn binary_op_concatenation_leaves_operands_alone() {
+        let a = Value::from("a");
+
+        Value::binary_op(&Op::Add, &a, &Value::from("b")).unwrap();
+
+        assert_eq!(a, Value::from("a"));
+    }
+
+    #[test]
+    fn binary_op_comparison() {
+        let num = Value::Number;
+
+        assert_eq!(Value::binary_op(&Op::LessThan, &num(1.0), &num(2.0)), Ok(Value::Boolean(true)));
+        assert_eq!(Value::binary_op(&Op::GreaterThanEquals, &num(1.0), &num(2.0)), Ok(Value::Boolean(false)));
+        assert_eq!(Value::binary_op(&Op::EqualsTo, &Value::from("a"), &Value::from("a")), Ok(Value::Boolean(true)));
+        assert_eq!(Value::binary_op(&Op::NotEqual, &num(1.0), &Value::Nil), Ok(Value::Boolean(true)));
+    }
+
+    #[test]
+    fn binary_op_errors() {
+        let err = Value::binary_op(&Op::Subtract, &Value::from("a"), &Value::Number(1.0)).unwrap_err();
+
+        assert_eq!(
+            err,
+            ValueError::UnsupportedOperands { op: Op::Subtract, lhs: "string", rhs: "number" }
+        );
+        assert_eq!(err.to_string(), "cannot apply `-` to string and number");
+
+        assert!(Value::binary_op(&Op::LessThan, &Value::Nil, &Value::Number(1.0)).is_err());
+        assert!(matches!(
+            Value::binary_op(&Op::Multiply, &Value::from("a"), &Value::Number(-1.0)),
+            Err(ValueError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn unary_op_errors() {
+        let err = Value::unary_op(&Op::Bang, &Value::Integer(1)).unwrap_err();
+
+        assert_eq!(err, ValueError::UnsupportedOperand { op: Op::Bang, operand: "number" });
+        assert_eq!(err.to_string(), "cannot apply `!` to number");
+
+        let err = Value::unary_op(&Op::Subtract, &Value::Nil).unwrap_err();
+        assert_eq!(err.to_string(), "cannot apply `-` to nil");
+
+        assert_eq!(Value::unary_op(&Op::Bang, &Value::Boolean(false)), Ok(Value::Boolean(true)));
+        assert_eq!(Value::unary_op(&Op::Subtract, &Value::Number(1.5)), Ok(Value::Number(-1.5)));
+    }
+
+    #[test]
+    fn nested_array_to_json() {
+        let inner = Value::Array(Rc::new(RefCell::new(vec![
+            Value::Number(2.5),
+            Value::from("a \"b\""),
+            Value::Nil,
+        ])));
+        let value = Value::Array(Rc::new(RefCell::new(vec![
+            Value::Number(1.0),
+            inner,
+            Value::Boolean(true),
+            Value::Range(Rc::new(Range::new(0, 3, 1))),
+            Value::Number(f64::NAN),
+        ])));
+
+        assert_eq!(
+            value.to_json(),
+            Ok("[1,[2.5,\"a \\\"b\\\"\",null],true,[0,1,2],null]".to_string())
+        );
+    }
+
+    #[test]
+    fn map_to_json() {
+        let mut entries = HashMap::new();
+        entries.insert(Value::from("b").map_key(), Value::Number(2.0));
+        entries.insert(Value::from("a").map_key(), Value::Number(1.0));
+        let map = Value::Map(Rc::new(RefCell::new(entries)));
+
+        assert_eq!(map.to_json(), Ok("{\"a\":1,\"b\":2}".to_string()));
+
+        let mut entries = HashMap::new();
+        entries.insert(Value::Number(1.0).map_key(), Value::Nil);
+        let map = Value::Map(Rc::new(RefCell::new(entries)));
+
+        assert!(map.to_json().is_err());
+    }
+
+    #[test]
+    fn function_to_json_is_an_error() {
+        let function = Value::Function(Rc::new(Function::new("f", Weak::new())));
+
+        assert_eq!(
+            function.to_json(),
+            Err(ValueError::Invalid("cannot convert a function to JSON".to_string()))
+        );
+    }
+
+    #[test]
+    fn reference_types() {
+        use super::{Class, Closure, Instance, NativeFunction};
+        use crate::common::Module;
+
+        let function = Rc::new(Function::new("f", Weak::new()));
+        let class = Rc::new(Class::new(&Rc::new(RefCell::new("A".to_string()))));
+
+        let plain = vec![Value::Number(1.0), Value::Boolean(true), Value::Nil];
+        let references = vec![
+            Value::from("a"),
+            Value::Function(function.clone()),
+            Value::Closure(Rc::new(Closure::new(function))),
+            Value::Class(class.clone()),
+            Value::Instance(Rc::new(Instance::new(&class))),
+            Value::Module(Module::new("m")),
+            Value::NativeFunction(Rc::new(NativeFunction::new(Rc::new(|_, _| Ok(Value::Nil)), 0))),
+            Value::Array(Rc::new(RefCell::new(vec![]))),
+            Value::Map(Rc::new(RefCell::new(HashMap::new()))),
+            Value::Range(Rc::new(Range::new(0, 1, 1))),
+            Value::from(vec![1, 2]),
+        ];
+
+        for value in plain {
+            assert!(!value.is_reference_type(), "{}", value.type_name());
+        }
+
+        for value in references {
+            assert!(value.is_reference_type(), "{}", value.type_name());
+        }
+    }
+
+    #[test]
+    fn display_bytes() {
+        assert_eq!(Value::from(b"abc".to_vec()).to_string(), "b\"abc\"");
+        assert_eq!(Value::from(vec![0, 0x7f, 0xff]).to_string(), "b\"\\x00\\x7f\\xff\"");
+        assert_eq!(Value::from(b"\"\\".to_vec()).to_string(), "b\"\\\"\\\\\"");
+        assert_eq!(Value::from(vec![1, 255]).to_json().unwrap(), "[1,255]");
+    }
 }
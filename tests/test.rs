@@ -43,6 +43,7 @@ struct Test {
 }
 
 impl Test {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(path: impl ToString) -> CreateTestResult {
         let mut output = vec![];
         let mut _errors = vec![];
@@ -117,7 +118,7 @@ impl Test {
         for line in lines {
             if index >= self.output.len() {
                 self.fail(format!("Got output \"{}\" but expected nothing.", line));
-            } else if &self.output[index].0 != line {
+            } else if self.output[index].0 != line {
                 self.fail(format!(
                     "Expected \"{}\" on line {} but got \"{}\".",
                     self.output[index].0, self.output[index].1, line
@@ -210,7 +211,7 @@ fn main() {
     println!();
 
     visit_dirs(
-        &Path::new("tests/language"),
+        Path::new("tests/language"),
         &run_script,
         &mut test_runner,
     )